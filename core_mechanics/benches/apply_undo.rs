@@ -0,0 +1,63 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use core_mechanics::board::{Action, Board, ProductionTier};
+use core_mechanics::original_game::get_original_game_board;
+use core_mechanics::piece::Piece;
+
+const COLORS: [Piece; 5] = [
+    Piece::Red,
+    Piece::Green,
+    Piece::Blue,
+    Piece::Brown,
+    Piece::White,
+];
+
+/// The moves a search would try from the opening position, where passing is not allowed.
+fn get_candidate_actions() -> Vec<Action> {
+    let mut actions = vec![];
+    for (i, first) in COLORS.iter().enumerate() {
+        for (j, second) in COLORS.iter().enumerate().skip(i + 1) {
+            for third in COLORS.iter().skip(j + 1) {
+                actions.push(Action::CollectPieces(vec![*first, *second, *third], vec![]));
+            }
+        }
+        actions.push(Action::CollectPieces(vec![*first, *first], vec![]));
+    }
+    for tier in [
+        ProductionTier::One,
+        ProductionTier::Two,
+        ProductionTier::Three,
+    ] {
+        actions.push(Action::ReserveCardFromDeck(tier));
+    }
+    actions
+}
+
+fn expand_children(c: &mut Criterion) {
+    let board = get_original_game_board(4);
+    let actions = get_candidate_actions();
+
+    let mut group = c.benchmark_group("expand_children");
+    group.bench_function("do_action", |b| {
+        b.iter(|| {
+            for action in &actions {
+                let child = Board::do_action(board.clone(), action).unwrap();
+                black_box(&child);
+            }
+        })
+    });
+    group.bench_function("apply_undo", |b| {
+        let mut board = board.clone();
+        b.iter(|| {
+            for action in &actions {
+                let undo_token = board.apply(action).unwrap();
+                black_box(&board);
+                board.undo(undo_token);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, expand_children);
+criterion_main!(benches);
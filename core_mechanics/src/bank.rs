@@ -1,9 +1,15 @@
-use std::{collections::HashMap, ops::Add, ops::Sub};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Index, IndexMut, Sub};
 
 use serde::{Deserialize, Serialize};
 
 use super::bank;
 use super::piece::Piece;
+#[cfg(feature = "trading-posts")]
+use super::trading_posts::PlayerPowers;
 
 const MIN_PILE_SIZE_TO_COLLECT_TWO_EQUALS: u8 = 4;
 
@@ -17,8 +23,50 @@ pub enum CollectError {
     CannotDiscardMoreThanThree,
     NotEnoughPiecesToDiscard,
     CannotStoreMoreThan10,
+    /// The bank would hold more than 255 pieces of a kind with the ones given back.
+    TooManyAtTheBank,
 }
 
+impl fmt::Display for CollectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectError::CollectedGolden => write!(
+                f,
+                "You cannot take Golden pieces, you get them by reserving a card"
+            ),
+            CollectError::Collected2OfTheSameWithAnother => write!(
+                f,
+                "You can take 2 pieces of the same color or 3 of different colors, not both"
+            ),
+            CollectError::NotEnoughAtTheBank(piece) => {
+                write!(f, "There are not enough {} pieces left at the bank", piece)
+            }
+            CollectError::CannotCollect2WhenResourceIsAlmostEmpty => write!(
+                f,
+                "You can only take 2 pieces of the same color when the bank has at least {} of them",
+                MIN_PILE_SIZE_TO_COLLECT_TWO_EQUALS
+            ),
+            CollectError::CannotCollectMoreThanThree => {
+                write!(f, "You cannot take more than 3 pieces")
+            }
+            CollectError::CannotDiscardMoreThanThree => {
+                write!(f, "You cannot give back more than 3 pieces")
+            }
+            CollectError::NotEnoughPiecesToDiscard => {
+                write!(f, "You do not have the pieces you want to give back")
+            }
+            CollectError::CannotStoreMoreThan10 => {
+                write!(f, "You cannot hold more than 10 pieces, give some back")
+            }
+            CollectError::TooManyAtTheBank => {
+                write!(f, "The bank has no room for the pieces you give back")
+            }
+        }
+    }
+}
+
+impl Error for CollectError {}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct CollectSuccess {
     pub bank_funds: bank::Funds,
@@ -33,130 +81,292 @@ impl CollectSuccess {
         }
     }
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub struct CollectRequest {
     bank_funds: bank::Funds,
     player_funds: bank::Funds,
-    want_to_collect: Vec<Piece>,
-    discard: Vec<Piece>,
+    want_to_collect: bank::Funds,
+    discard: bank::Funds,
+    #[cfg(feature = "trading-posts")]
+    powers: PlayerPowers,
 }
 
 impl CollectRequest {
     pub fn new(
         bank_funds: Funds,
         player_funds: Funds,
-        want_to_collect: Vec<Piece>,
-        discard: Vec<Piece>,
+        want_to_collect: impl IntoIterator<Item = Piece>,
+        discard: impl IntoIterator<Item = Piece>,
     ) -> Self {
         Self {
             bank_funds,
             player_funds,
-            want_to_collect,
-            discard,
+            want_to_collect: Funds::new_from_list(want_to_collect),
+            discard: Funds::new_from_list(discard),
+            #[cfg(feature = "trading-posts")]
+            powers: PlayerPowers::default(),
+        }
+    }
+
+    /// Collects with the powers of the player, that may allow more pieces.
+    #[cfg(feature = "trading-posts")]
+    pub fn with_powers(self, powers: &PlayerPowers) -> Self {
+        Self {
+            powers: *powers,
+            ..self
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// Amount of each `Piece`, stored inline so that funds are cheap to copy.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(from = "SerializedFunds", into = "SerializedFunds")]
 pub struct Funds {
-    pub funds: HashMap<Piece, u8>,
+    funds: [u8; Piece::CAPACITY],
+    /// Slots in use: the built-in pieces and up to the last custom piece set. The slots past it
+    /// are empty, so that the original game never walks the custom ones.
+    len: u8,
+}
+
+const BUILT_IN_LEN: u8 = Piece::ALL.len() as u8;
+
+impl Default for Funds {
+    fn default() -> Self {
+        Self {
+            funds: [0; Piece::CAPACITY],
+            len: BUILT_IN_LEN,
+        }
+    }
+}
+
+impl PartialEq for Funds {
+    fn eq(&self, other: &Self) -> bool {
+        self.funds == other.funds
+    }
+}
+
+impl Eq for Funds {}
+
+impl Hash for Funds {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.funds.hash(state);
+    }
+}
+
+/// Map based representation kept for (de)serialization, so that boards saved before `Funds`
+/// became an array can still be loaded.
+#[derive(Serialize, Deserialize)]
+struct SerializedFunds {
+    funds: BTreeMap<Piece, u8>,
+}
+
+impl From<SerializedFunds> for Funds {
+    fn from(serialized: SerializedFunds) -> Self {
+        let mut funds = Funds::default();
+        for (piece, quantity) in serialized.funds {
+            funds[piece] = quantity;
+        }
+        funds
+    }
+}
+
+impl From<Funds> for SerializedFunds {
+    fn from(funds: Funds) -> Self {
+        Self {
+            funds: funds.iter_listed().collect(),
+        }
+    }
 }
 
 impl Funds {
-    pub fn new(red: u8, green: u8, blue: u8, brown: u8, white: u8, golden: u8) -> Self {
+    /// Funds of the original game, see `Funds::new_from` for other pieces.
+    pub const fn new(red: u8, green: u8, blue: u8, brown: u8, white: u8, golden: u8) -> Self {
+        let mut funds = [0; Piece::CAPACITY];
+        funds[Piece::Red.index()] = red;
+        funds[Piece::Green.index()] = green;
+        funds[Piece::Blue.index()] = blue;
+        funds[Piece::Brown.index()] = brown;
+        funds[Piece::White.index()] = white;
+        funds[Piece::Golden.index()] = golden;
         Self {
-            funds: HashMap::from([
-                (Piece::Red, red),
-                (Piece::Green, green),
-                (Piece::Blue, blue),
-                (Piece::Brown, brown),
-                (Piece::White, white),
-                (Piece::Golden, golden),
-            ]),
+            funds,
+            len: BUILT_IN_LEN,
         }
     }
 
+    pub const fn with_pearls(mut self, pearls: u8) -> Self {
+        self.funds[Piece::Pearl.index()] = pearls;
+        self
+    }
+
     pub fn new_from(funds: HashMap<Piece, u8>) -> Self {
-        Self {
-            funds: HashMap::from([
-                (Piece::Red, *funds.get(&Piece::Red).unwrap_or(&0)),
-                (Piece::Green, *funds.get(&Piece::Green).unwrap_or(&0)),
-                (Piece::Blue, *funds.get(&Piece::Blue).unwrap_or(&0)),
-                (Piece::Brown, *funds.get(&Piece::Brown).unwrap_or(&0)),
-                (Piece::White, *funds.get(&Piece::White).unwrap_or(&0)),
-                (Piece::Golden, *funds.get(&Piece::Golden).unwrap_or(&0)),
-            ]),
+        let mut new_funds = Funds::default();
+        for (piece, quantity) in funds {
+            new_funds[piece] = quantity;
         }
+        new_funds
     }
 
-    pub fn new_from_list(funds: Vec<Piece>) -> Self {
-        let mut new_funds: HashMap<Piece, u8> = HashMap::from([
-            (Piece::Red, 0),
-            (Piece::Green, 0),
-            (Piece::Blue, 0),
-            (Piece::Brown, 0),
-            (Piece::White, 0),
-            (Piece::Golden, 0),
-        ]);
+    pub fn new_from_list(funds: impl IntoIterator<Item = Piece>) -> Self {
+        let mut new_funds = Funds::default();
         for p in funds {
-            new_funds.insert(p, *new_funds.get(&p).unwrap_or(&0) + 1);
+            new_funds[p] = new_funds[p].saturating_add(1);
         }
-        Self { funds: new_funds }
+        new_funds
+    }
+
+    /// The quantity of each piece, keyed like the map `Funds` used to be. Pieces other than the
+    /// original ones are only listed when there are some.
+    pub fn get_funds(&self) -> HashMap<Piece, u8> {
+        self.iter_listed().collect()
+    }
+
+    /// Pieces other than the original ones are left out when there are none, so that funds of
+    /// the original game are listed and stored as before.
+    fn iter_listed(&self) -> impl Iterator<Item = (Piece, u8)> {
+        self.iter()
+            .filter(|(piece, quantity)| piece.index() <= Piece::Golden.index() || *quantity > 0)
+    }
+
+    pub fn get(&self, piece: Piece) -> u8 {
+        self.funds[piece.index()]
+    }
+
+    pub fn set(&mut self, piece: Piece, quantity: u8) {
+        self[piece] = quantity;
+    }
+
+    /// Iterates over the built-in pieces, then the custom ones up to the last one set, always in
+    /// the order of `Piece::every`.
+    pub fn iter(&self) -> impl Iterator<Item = (Piece, u8)> {
+        let funds = self.funds;
+        Piece::every()
+            .take(self.len())
+            .map(move |piece| (piece, funds[piece.index()]))
+    }
+
+    /// Iterates over the quantities of both funds, up to the last piece set in either of them.
+    pub(crate) fn iter_with(&self, other: &Funds) -> impl Iterator<Item = (Piece, u8, u8)> {
+        let (funds, other_funds) = (self.funds, other.funds);
+        Piece::every()
+            .take(self.len().max(other.len()))
+            .map(move |piece| (piece, funds[piece.index()], other_funds[piece.index()]))
+    }
+
+    fn len(&self) -> usize {
+        usize::from(self.len)
+    }
+
+    fn used(&self) -> &[u8] {
+        &self.funds[..self.len()]
+    }
+
+    pub fn total(&self) -> u16 {
+        self.used().iter().map(|q| u16::from(*q)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.used().iter().all(|q| *q == 0)
+    }
+
+    /// Adds both funds, returning `None` if any piece would overflow.
+    pub fn checked_add(self, rhs: Funds) -> Option<Funds> {
+        let mut new_funds = self;
+        new_funds.len = self.len.max(rhs.len);
+        let len = new_funds.len();
+        for (quantity, added) in new_funds.funds[..len].iter_mut().zip(rhs.funds) {
+            *quantity = quantity.checked_add(added)?;
+        }
+        Some(new_funds)
+    }
+
+    /// Adds both funds, clamping every piece at `u8::MAX`.
+    pub fn saturating_add(self, rhs: Funds) -> Funds {
+        let mut new_funds = self;
+        new_funds.len = self.len.max(rhs.len);
+        let len = new_funds.len();
+        for (quantity, added) in new_funds.funds[..len].iter_mut().zip(rhs.funds) {
+            *quantity = quantity.saturating_add(added);
+        }
+        new_funds
+    }
+
+    /// Subtracts `rhs`, reporting every piece that is missing and by how much.
+    pub fn checked_sub(self, rhs: Funds) -> Result<Funds, FundsSubtractionError> {
+        let missing = rhs.saturating_sub(self);
+        if !missing.is_empty() {
+            return Err(FundsSubtractionError::NotEnoughFunds(missing));
+        }
+        Ok(self.saturating_sub(rhs))
+    }
+
+    /// Subtracts `rhs`, clamping every piece at zero.
+    pub fn saturating_sub(self, rhs: Funds) -> Funds {
+        let mut new_funds = self;
+        let len = self.len();
+        for (quantity, removed) in new_funds.funds[..len].iter_mut().zip(rhs.funds) {
+            *quantity = quantity.saturating_sub(removed);
+        }
+        new_funds
     }
 
     pub fn collect(collect_request: CollectRequest) -> Result<CollectSuccess, CollectError> {
-        for p in &collect_request.want_to_collect {
-            if p == &Piece::Golden {
-                return Err(CollectError::CollectedGolden);
-            }
+        let player_request_as_funds = collect_request.want_to_collect;
+        let collects_wildcard = player_request_as_funds
+            .iter()
+            .any(|(piece, quantity)| piece.is_wildcard() && quantity > 0);
+        if collects_wildcard {
+            return Err(CollectError::CollectedGolden);
         }
-        let total_amount_of_pieces = collect_request.want_to_collect.len();
+        let total_amount_of_pieces = player_request_as_funds.total();
         if total_amount_of_pieces > 3 {
             return Err(CollectError::CannotCollectMoreThanThree);
         }
 
-        if collect_request.discard.len() > 3 {
+        let discard_cards_as_funds = collect_request.discard;
+        if discard_cards_as_funds.total() > 3 {
             return Err(CollectError::CannotDiscardMoreThanThree);
         }
 
-        let player_request_as_funds = Funds::new_from_list(collect_request.want_to_collect.clone());
-        for (piece, q) in &player_request_as_funds.funds {
-            if q >= &2 && total_amount_of_pieces == 3 {
+        #[cfg(feature = "trading-posts")]
+        let allows_third_piece = collect_request.powers.piece_with_two_of_a_kind;
+        #[cfg(not(feature = "trading-posts"))]
+        let allows_third_piece = false;
+        for (piece, q) in player_request_as_funds.iter() {
+            if q >= 2 && total_amount_of_pieces == 3 && !(q == 2 && allows_third_piece) {
                 return Err(CollectError::Collected2OfTheSameWithAnother);
             }
-            let current_quantity_in_bank =
-                collect_request.bank_funds.funds.get(piece).unwrap_or(&0);
-            if q >= &2 && current_quantity_in_bank < &MIN_PILE_SIZE_TO_COLLECT_TWO_EQUALS {
+            let current_quantity_in_bank = collect_request.bank_funds[piece];
+            if q >= 2 && current_quantity_in_bank < MIN_PILE_SIZE_TO_COLLECT_TWO_EQUALS {
                 return Err(CollectError::CannotCollect2WhenResourceIsAlmostEmpty);
             }
         }
 
-        let discard_cards_as_funds = Funds::new_from_list(collect_request.discard);
-
-        let result_bank_funds = (collect_request.bank_funds + discard_cards_as_funds.clone())
-            - player_request_as_funds.clone();
-
-        if let Err(subtraction_error) = result_bank_funds {
-            return match subtraction_error {
-                FundsSubtractionError::NotEnoughFunds(piece_missing) => {
-                    Err(CollectError::NotEnoughAtTheBank(piece_missing))
+        let result_bank_funds = collect_request
+            .bank_funds
+            .checked_add(discard_cards_as_funds)
+            .ok_or(CollectError::TooManyAtTheBank)?
+            .checked_sub(player_request_as_funds)
+            .map_err(|err| match err {
+                FundsSubtractionError::NotEnoughFunds(missing) => {
+                    let (piece_missing, _) = missing
+                        .iter()
+                        .find(|(_, quantity)| *quantity > 0)
+                        .expect("Missing funds should not be empty");
+                    CollectError::NotEnoughAtTheBank(piece_missing)
                 }
-            };
-        }
+            })?;
 
-        let result_bank_funds = result_bank_funds.unwrap();
-        let new_player_funds = ((collect_request.player_funds + player_request_as_funds)
-            - discard_cards_as_funds)
+        let new_player_funds = collect_request
+            .player_funds
+            .checked_add(player_request_as_funds)
+            .ok_or(CollectError::CannotStoreMoreThan10)?
+            .checked_sub(discard_cards_as_funds)
             .map_err(|err| match err {
                 FundsSubtractionError::NotEnoughFunds(_) => CollectError::NotEnoughPiecesToDiscard,
             })?;
 
-        let total_amount_of_pieces = new_player_funds
-            .funds
-            .iter()
-            .map(|fund| *fund.1)
-            .sum::<u8>();
-        if total_amount_of_pieces > 10 {
+        if new_player_funds.total() > 10 {
             return Err(CollectError::CannotStoreMoreThan10);
         }
 
@@ -164,11 +374,51 @@ impl Funds {
     }
 }
 
+impl Index<Piece> for Funds {
+    type Output = u8;
+
+    fn index(&self, piece: Piece) -> &Self::Output {
+        &self.funds[piece.index()]
+    }
+}
+
+impl IndexMut<Piece> for Funds {
+    fn index_mut(&mut self, piece: Piece) -> &mut Self::Output {
+        self.len = self.len.max(piece.index() as u8 + 1);
+        &mut self.funds[piece.index()]
+    }
+}
+
+/// Lists the non empty piles, such as "2 Blue and 1 Red".
+impl fmt::Display for Funds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_list(f, "")
+    }
+}
+
+impl Funds {
+    /// Writes the non empty piles as "2{prefix} Blue and 1{prefix} Red".
+    pub(crate) fn write_list(&self, f: &mut fmt::Formatter<'_>, prefix: &str) -> fmt::Result {
+        let piles: Vec<(Piece, u8)> = self.iter().filter(|(_, q)| *q > 0).collect();
+        if piles.is_empty() {
+            return write!(f, "nothing");
+        }
+        for (i, (piece, quantity)) in piles.iter().enumerate() {
+            if i > 0 {
+                let separator = if i == piles.len() - 1 { " and " } else { ", " };
+                write!(f, "{}", separator)?;
+            }
+            write!(f, "{}{} {}", quantity, prefix, piece)?;
+        }
+        Ok(())
+    }
+}
+
+/// The original pieces come first, in the alphabetical order they always had, then the other
+/// pieces in the order of `Piece::every`.
 impl From<Funds> for Vec<Piece> {
     fn from(funds: Funds) -> Self {
-        let mut pieces: Vec<Piece> = vec![];
-
-        let all_pieces = vec![
+        const ORIGINAL_PIECES: [Piece; 6] = [
             Piece::Blue,
             Piece::Brown,
             Piece::Golden,
@@ -176,10 +426,13 @@ impl From<Funds> for Vec<Piece> {
             Piece::Red,
             Piece::White,
         ];
+        let mut pieces: Vec<Piece> = vec![];
 
-        for color in all_pieces {
-            let quantity = *funds.funds.get(&color).unwrap();
-            for _ in 0..quantity {
+        let other_pieces = Piece::every()
+            .take(funds.len())
+            .filter(|piece| !ORIGINAL_PIECES.contains(piece));
+        for color in ORIGINAL_PIECES.into_iter().chain(other_pieces) {
+            for _ in 0..funds[color] {
                 pieces.push(color)
             }
         }
@@ -188,53 +441,41 @@ impl From<Funds> for Vec<Piece> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FundsSubtractionError {
-    NotEnoughFunds(Piece),
+    /// How much of each piece is missing.
+    NotEnoughFunds(Funds),
+}
+
+impl fmt::Display for FundsSubtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FundsSubtractionError::NotEnoughFunds(missing) => {
+                write!(f, "Not enough pieces, missing {}", missing)
+            }
+        }
+    }
 }
 
+impl Error for FundsSubtractionError {}
+
 impl Sub<Funds> for Funds {
     type Output = Result<Self, FundsSubtractionError>;
 
     fn sub(self, rhs: Funds) -> Self::Output {
-        let mut funds_remaining = self.clone();
-
-        for cost_piece in rhs.funds {
-            let (piece, quantity) = cost_piece;
-            if !self.funds.contains_key(&piece) {
-                continue;
-            }
-            let current_amount = match self.funds.get(&piece) {
-                None => 0,
-                Some(i) => *i,
-            };
-            if quantity > current_amount {
-                return Err(FundsSubtractionError::NotEnoughFunds(piece));
-            }
-            funds_remaining
-                .funds
-                .insert(piece, current_amount - quantity);
-        }
-
-        Ok(funds_remaining)
+        self.checked_sub(rhs)
     }
 }
 
 impl Add<Funds> for Funds {
     type Output = Funds;
 
+    /// # Panics
+    ///
+    /// Panics if any piece overflows, in release builds as well. Use `Funds::checked_add` when
+    /// the quantities come from untrusted input.
     fn add(self, rhs: Funds) -> Self::Output {
-        let mut new_funds = self.clone();
-
-        for (piece, quantity) in rhs.funds {
-            let current_amount = match self.funds.get(&piece) {
-                None => 0,
-                Some(i) => *i,
-            };
-            new_funds.funds.insert(piece, current_amount + quantity);
-        }
-
-        new_funds
+        self.checked_add(rhs).expect("Funds addition overflowed")
     }
 }
 
@@ -308,6 +549,34 @@ mod tests {
         assert_eq!(response, Err(CollectError::Collected2OfTheSameWithAnother));
     }
 
+    #[test]
+    #[cfg(feature = "trading-posts")]
+    fn trading_post_power_adds_a_piece_to_two_of_the_same() {
+        let powers = PlayerPowers {
+            piece_with_two_of_a_kind: true,
+            ..PlayerPowers::default()
+        };
+        let collect_request = CollectRequest::new(
+            get_default_funds(),
+            Funds::new(0, 0, 0, 0, 0, 0),
+            vec![Piece::Blue, Piece::Blue, Piece::Red],
+            vec![],
+        )
+        .with_powers(&powers);
+        let response = Funds::collect(collect_request).unwrap();
+        assert_eq!(response.player_funds, Funds::new(1, 0, 2, 0, 0, 0));
+
+        let collect_request = CollectRequest::new(
+            get_default_funds(),
+            Funds::new(0, 0, 0, 0, 0, 0),
+            vec![Piece::Blue, Piece::Blue, Piece::Blue],
+            vec![],
+        )
+        .with_powers(&powers);
+        let response = Funds::collect(collect_request);
+        assert_eq!(response, Err(CollectError::Collected2OfTheSameWithAnother));
+    }
+
     #[test]
     fn cannot_collect_more_than_three() {
         let collect_request = CollectRequest::new(
@@ -418,7 +687,7 @@ mod tests {
     #[test]
     fn correctly_convert_from_funds_to_vec_of_pieces() {
         let funds = Funds::new(1, 2, 3, 4, 5, 6);
-        let pieces = Vec::<Piece>::from(funds.clone());
+        let pieces = Vec::<Piece>::from(funds);
 
         assert_eq!(
             pieces.iter().clone().filter(|p| *p == &Piece::Red).count(),
@@ -464,4 +733,153 @@ mod tests {
         let new_funds = Funds::new_from_list(pieces);
         assert_eq!(funds, new_funds);
     }
+
+    #[test]
+    fn converts_to_pieces_in_alphabetical_order() {
+        let funds = Funds::new(1, 1, 1, 1, 1, 1).with_pearls(1);
+        let pieces = Vec::<Piece>::from(funds);
+
+        assert_eq!(
+            pieces,
+            vec![
+                Piece::Blue,
+                Piece::Brown,
+                Piece::Golden,
+                Piece::Green,
+                Piece::Red,
+                Piece::White,
+                Piece::Pearl,
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_funds_as_a_map() {
+        let funds = Funds::new(1, 0, 3, 0, 0, 0);
+
+        let map = funds.get_funds();
+        assert_eq!(map.len(), 6);
+        assert_eq!(map[&Piece::Blue], 3);
+        assert_eq!(map[&Piece::Green], 0);
+        assert_eq!(Funds::new_from(map), funds);
+        assert_eq!(funds.with_pearls(2).get_funds()[&Piece::Pearl], 2);
+    }
+
+    #[test]
+    fn counts_from_a_list_saturate() {
+        let funds = Funds::new_from_list(vec![Piece::Red; 300]);
+        assert_eq!(funds[Piece::Red], u8::MAX);
+    }
+
+    #[test]
+    fn collecting_into_a_full_bank_fails() {
+        let collect_request = CollectRequest::new(
+            Funds::new(u8::MAX, 8, 8, 8, 8, 8),
+            Funds::new(1, 0, 0, 0, 0, 0),
+            vec![Piece::Green],
+            vec![Piece::Red],
+        );
+        let response = Funds::collect(collect_request);
+        assert_eq!(response, Err(CollectError::TooManyAtTheBank));
+
+        let collect_request = CollectRequest::new(
+            get_default_funds(),
+            Funds::new(0, u8::MAX, 0, 0, 0, 0),
+            vec![Piece::Green],
+            vec![],
+        );
+        let response = Funds::collect(collect_request);
+        assert_eq!(response, Err(CollectError::CannotStoreMoreThan10));
+    }
+
+    #[test]
+    fn subtraction_reports_every_missing_piece() {
+        let funds = Funds::new(1, 2, 0, 0, 0, 0);
+        let result = funds - Funds::new(2, 2, 3, 0, 0, 1);
+
+        let FundsSubtractionError::NotEnoughFunds(missing) = result.unwrap_err();
+        assert_eq!(missing, Funds::new(1, 0, 3, 0, 0, 1));
+
+        let result = funds - Funds::new(1, 1, 0, 0, 0, 0);
+        assert_eq!(result.unwrap(), Funds::new(0, 1, 0, 0, 0, 0));
+    }
+
+    #[test]
+    fn addition_is_checked_for_overflow() {
+        let funds = Funds::new(u8::MAX, 0, 0, 0, 0, 0);
+
+        assert_eq!(funds.checked_add(Funds::new(1, 0, 0, 0, 0, 0)), None);
+        assert_eq!(
+            funds.checked_add(Funds::new(0, 1, 0, 0, 0, 1)),
+            Some(Funds::new(u8::MAX, 1, 0, 0, 0, 1))
+        );
+    }
+
+    #[test]
+    fn pearls_are_only_stored_when_there_are_some() {
+        let funds = Funds::new(1, 0, 0, 0, 0, 0);
+        let json = serde_json::to_string(&funds).unwrap();
+        assert!(!json.contains("Pearl"));
+
+        let funds = funds.with_pearls(2);
+        let json = serde_json::to_string(&funds).unwrap();
+        assert_eq!(serde_json::from_str::<Funds>(&json).unwrap(), funds);
+    }
+
+    #[test]
+    fn iterates_in_a_fixed_order() {
+        let mut funds = Funds::new(1, 2, 3, 4, 5, 6).with_pearls(7);
+        let silver = Piece::custom(0).unwrap();
+        funds[silver] = 8;
+        let pieces: Vec<(Piece, u8)> = funds.iter().collect();
+
+        assert_eq!(
+            pieces,
+            vec![
+                (Piece::Red, 1),
+                (Piece::Green, 2),
+                (Piece::Blue, 3),
+                (Piece::Brown, 4),
+                (Piece::White, 5),
+                (Piece::Golden, 6),
+                (Piece::Pearl, 7),
+                (silver, 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_walks_the_pieces_in_use() {
+        let funds = Funds::new(1, 2, 3, 4, 5, 6);
+        assert_eq!(funds.iter().count(), Piece::ALL.len());
+
+        let mut with_custom = funds;
+        let last = Piece::custom(2).unwrap();
+        with_custom[last] = 3;
+        assert_eq!(with_custom.iter().count(), last.index() + 1);
+        assert_eq!(with_custom.total(), funds.total() + 3);
+        assert_eq!(
+            (with_custom - funds).unwrap().iter().count(),
+            last.index() + 1
+        );
+        assert_eq!((funds + with_custom).total(), funds.total() * 2 + 3);
+
+        with_custom[last] = 0;
+        assert_eq!(with_custom, funds);
+    }
+
+    #[test]
+    fn deserializes_the_map_based_format() {
+        let json = r#"{"funds":{"White":5,"Blue":3,"Red":1}}"#;
+        let funds: Funds = serde_json::from_str(json).unwrap();
+        assert_eq!(funds, Funds::new(1, 0, 3, 0, 5, 0));
+
+        let funds = Funds::new(1, 2, 3, 4, 5, 6);
+        let json = serde_json::to_string(&funds).unwrap();
+        assert_eq!(
+            json,
+            r#"{"funds":{"Red":1,"Green":2,"Blue":3,"Brown":4,"White":5,"Golden":6}}"#
+        );
+        assert_eq!(serde_json::from_str::<Funds>(&json).unwrap(), funds);
+    }
 }
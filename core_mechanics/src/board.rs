@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
 use std::slice::Iter;
 
 use serde::Deserialize;
@@ -10,17 +14,31 @@ use super::noble::NobleId;
 use super::player::Player;
 use super::player::PlayerId;
 use super::player::ReserveOperationFail;
+use super::player::ScoreBreakdown;
+use super::player::MAXIMUM_RESERVED_CARDS;
+use super::production_card::CardAbility;
 use super::production_card::CardId;
 use super::production_card::Identifiable;
 use super::production_card::ProductionCard;
+use super::rules::Rules;
+#[cfg(feature = "trading-posts")]
+use super::trading_posts::{PlayerPowers, TradingPost};
 
 use super::bank;
 use super::noble::Noble;
-use super::piece::Piece;
+use super::piece::{Piece, PieceSet};
 use super::player;
 use super::production_card;
+use super::zobrist;
 
-const WINNING_POINTS_THRESHOLD: u8 = 15;
+#[cfg(feature = "strongholds")]
+mod strongholds;
+#[cfg(feature = "strongholds")]
+pub use strongholds::{Stronghold, StrongholdFail, STRONGHOLDS_PER_PLAYER};
+
+const WINNING_POINTS_THRESHOLD: u16 = 15;
+/// Cards of each tier for sale at once in the original game.
+pub const MARKET_SLOTS_PER_TIER: usize = 4;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Winner {
@@ -28,23 +46,176 @@ pub enum Winner {
     Draw(Vec<PlayerId>),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Why the game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndReason {
+    /// A player reached the winning points and the last round was played.
+    WinningPoints,
+    /// The maximum number of rounds set by the `Rules` was played.
+    MaxRounds,
+    /// Every player passed their turn in a row.
+    Stalemate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RoundType {
     Normal,
     LastRound,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum ProductionTier {
-    One,
-    Two,
-    Three,
+/// A tier of production cards, numbered from 1 for the cheapest ones.
+///
+/// The original game has the tiers `One`, `Two` and `Three`. Games with other tiers set them up
+/// with a `MarketLayout`. Tiers are stored as `"One"`, `"Two"` and `"Three"` for the original
+/// ones, and as their number otherwise, like `"4"`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProductionTier(u8);
+
+#[allow(non_upper_case_globals)]
+impl ProductionTier {
+    pub const One: ProductionTier = ProductionTier(1);
+    pub const Two: ProductionTier = ProductionTier(2);
+    pub const Three: ProductionTier = ProductionTier(3);
+
+    /// The tiers of the original game.
+    pub const ALL: [ProductionTier; 3] = [
+        ProductionTier::One,
+        ProductionTier::Two,
+        ProductionTier::Three,
+    ];
+
+    pub const fn new(level: u8) -> Self {
+        Self(level)
+    }
+
+    pub const fn level(self) -> u8 {
+        self.0
+    }
+
+    /// The tier below this one, if any.
+    pub fn lower(self) -> Option<ProductionTier> {
+        match self.0 {
+            0 | 1 => None,
+            level => Some(ProductionTier(level - 1)),
+        }
+    }
+
+    fn name(self) -> Option<&'static str> {
+        match self {
+            ProductionTier::One => Some("One"),
+            ProductionTier::Two => Some("Two"),
+            ProductionTier::Three => Some("Three"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for ProductionTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl Serialize for ProductionTier {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.name() {
+            Some(name) => serializer.serialize_str(name),
+            None => serializer.collect_str(&self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ProductionTier {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tier = String::deserialize(deserializer)?;
+        match tier.as_str() {
+            "One" => Ok(ProductionTier::One),
+            "Two" => Ok(ProductionTier::Two),
+            "Three" => Ok(ProductionTier::Three),
+            level => level.parse().map(ProductionTier).map_err(|_| {
+                serde::de::Error::invalid_value(serde::de::Unexpected::Str(level), &"a tier")
+            }),
+        }
+    }
+}
+
+/// The tiers of a game, from the lowest, and how many cards of each tier are for sale at once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketLayout {
+    tiers: Vec<(ProductionTier, usize)>,
+}
+
+impl MarketLayout {
+    /// Tiers given more than once keep their first slot count.
+    pub fn new(tiers: Vec<(ProductionTier, usize)>) -> Self {
+        let mut seen = HashSet::new();
+        let mut tiers: Vec<_> = tiers
+            .into_iter()
+            .filter(|(tier, _)| seen.insert(*tier))
+            .collect();
+        tiers.sort_by_key(|(tier, _)| *tier);
+        Self { tiers }
+    }
+
+    pub fn tiers(&self) -> impl Iterator<Item = ProductionTier> + '_ {
+        self.tiers.iter().map(|(tier, _)| *tier)
+    }
+
+    /// Cards of this tier for sale at once, 0 for tiers that are not in the game.
+    pub fn slots(&self, tier: &ProductionTier) -> usize {
+        self.tiers
+            .iter()
+            .find(|(other, _)| other == tier)
+            .map_or(0, |(_, slots)| *slots)
+    }
+
+    pub fn contains(&self, tier: &ProductionTier) -> bool {
+        self.tiers.iter().any(|(other, _)| other == tier)
+    }
+}
+
+impl Default for MarketLayout {
+    /// The original game: three tiers of 4 cards.
+    fn default() -> Self {
+        Self::new(
+            ProductionTier::ALL
+                .into_iter()
+                .map(|tier| (tier, MARKET_SLOTS_PER_TIER))
+                .collect(),
+        )
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum ActionType {
     Normal,
     SelectNoble,
+    /// The ability of the card just acquired needs a choice, see `Action::ResolveAbility`.
+    ResolveAbility,
+    /// The buyer takes a piece thanks to a trading post, see `Action::TakeBonusPiece`.
+    #[cfg(feature = "trading-posts")]
+    TakeBonusPiece,
+}
+
+/// The choice made for a pending `CardAbility`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum AbilityChoice {
+    /// A card of the tier below, for `CardAbility::TakeLowerTierCard`.
+    TakeCard(production_card::CardId),
+    /// A noble still on the board, for `CardAbility::ReserveNoble`.
+    ReserveNoble(NobleId),
+    /// A color the player already produces, for `CardAbility::CopyColor`.
+    CopyColor(Piece),
+}
+
+/// A card ability waiting for the player to choose how it applies.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct PendingAbility {
+    pub card_id: CardId,
+    pub ability: CardAbility,
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -53,12 +224,21 @@ pub enum Action {
     CollectPieces(Vec<Piece>, Vec<Piece>),
     ReserveCardFromDeck(ProductionTier),
     ReserveCardFromBoard(production_card::CardId),
-    BuyCard(production_card::CardId),
+    /// Buys a card, paying with the given pieces, or automatically when there is no payment.
+    BuyCard(production_card::CardId, Option<bank::Funds>),
     SelectNoble(NobleId),
+    ResolveAbility(AbilityChoice),
+    #[cfg(feature = "trading-posts")]
+    TakeBonusPiece(Piece),
+    #[cfg(feature = "strongholds")]
+    PlaceStronghold(production_card::CardId),
+    #[cfg(feature = "strongholds")]
+    RemoveStronghold(production_card::CardId),
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ActionFail {
+    GameIsOver,
     CannotReserveFromEmptyDeck,
     CardNotFoundOnBoard,
     NobleNotFound,
@@ -67,908 +247,4360 @@ pub enum ActionFail {
     InvalidCollect(CollectError),
     YouCannotSelectNobleNow,
     YouNeedToSelectNoble,
+    NotYourTurn,
+    PassingNotAllowed,
+    NobleNotEligible,
+    YouNeedToResolveAbility,
+    NoAbilityToResolve,
+    InvalidAbilityChoice,
+    #[cfg(feature = "trading-posts")]
+    YouNeedToTakeBonusPiece,
+    #[cfg(feature = "trading-posts")]
+    NoBonusPieceToTake,
+    #[cfg(feature = "trading-posts")]
+    BonusPieceNotAvailable(Piece),
+    /// The bank would hold more than 255 pieces of a kind with the payment.
+    TooManyAtTheBank,
+    #[cfg(feature = "strongholds")]
+    InvalidStronghold(StrongholdFail),
+}
+
+impl fmt::Display for ActionFail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionFail::GameIsOver => write!(f, "The game is over"),
+            ActionFail::CannotReserveFromEmptyDeck => {
+                write!(f, "You cannot reserve from an empty deck")
+            }
+            ActionFail::CardNotFoundOnBoard => write!(f, "This card is not on the board"),
+            ActionFail::NobleNotFound => write!(f, "This noble is not available"),
+            ActionFail::InvalidBuyOperation(fail) => write!(f, "{}", fail),
+            ActionFail::InvalidReserve(fail) => write!(f, "{}", fail),
+            ActionFail::InvalidCollect(fail) => write!(f, "{}", fail),
+            ActionFail::YouCannotSelectNobleNow => {
+                write!(f, "You can only select a noble when one comes to visit you")
+            }
+            ActionFail::YouNeedToSelectNoble => {
+                write!(f, "You need to select a noble before doing anything else")
+            }
+            ActionFail::NotYourTurn => write!(f, "It is not your turn"),
+            ActionFail::PassingNotAllowed => {
+                write!(f, "You can only pass when there is nothing else you can do")
+            }
+            ActionFail::NobleNotEligible => {
+                write!(f, "Your production does not attract this noble")
+            }
+            ActionFail::YouNeedToResolveAbility => write!(
+                f,
+                "You need to resolve the ability of your new card before doing anything else"
+            ),
+            ActionFail::NoAbilityToResolve => write!(f, "There is no card ability to resolve"),
+            ActionFail::InvalidAbilityChoice => {
+                write!(f, "This choice does not fit the ability of your new card")
+            }
+            #[cfg(feature = "trading-posts")]
+            ActionFail::YouNeedToTakeBonusPiece => {
+                write!(f, "You need to take the piece your trading post gives you")
+            }
+            #[cfg(feature = "trading-posts")]
+            ActionFail::NoBonusPieceToTake => write!(f, "There is no bonus piece to take"),
+            #[cfg(feature = "trading-posts")]
+            ActionFail::BonusPieceNotAvailable(piece) => {
+                write!(f, "You cannot take a {} piece as a bonus", piece)
+            }
+            ActionFail::TooManyAtTheBank => {
+                write!(f, "The bank has no room for the pieces you pay")
+            }
+            #[cfg(feature = "strongholds")]
+            ActionFail::InvalidStronghold(fail) => write!(f, "{}", fail),
+        }
+    }
+}
+
+impl Error for ActionFail {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ActionFail::InvalidBuyOperation(fail) => Some(fail),
+            ActionFail::InvalidReserve(fail) => Some(fail),
+            ActionFail::InvalidCollect(fail) => Some(fail),
+            #[cfg(feature = "strongholds")]
+            ActionFail::InvalidStronghold(fail) => Some(fail),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BuyOperationFail {
     NotEnoughFunds(Funds),
     CardNotFoundOnBoard,
+    /// The payment is this many pieces short of the cost.
+    Underpaid(u8),
+    /// The payment has these pieces beyond the cost.
+    Overpaid(Funds),
+    /// The buyer lacks these pieces to make the payment.
+    PaymentNotOwned(Funds),
+    /// The card copies a color, and the buyer has no card to copy it from.
+    NoColorToCopy,
 }
 
+impl fmt::Display for BuyOperationFail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuyOperationFail::NotEnoughFunds(missing) => {
+                write!(f, "You need ")?;
+                missing.write_list(f, " more")?;
+                write!(f, " to buy this card")
+            }
+            BuyOperationFail::CardNotFoundOnBoard => {
+                write!(f, "This card is neither on sale nor reserved by you")
+            }
+            BuyOperationFail::Underpaid(missing) => {
+                write!(f, "This payment is {} pieces short of the cost", missing)
+            }
+            BuyOperationFail::Overpaid(excess) => {
+                write!(f, "This payment has {} beyond the cost", excess)
+            }
+            BuyOperationFail::PaymentNotOwned(missing) => {
+                write!(f, "You need ")?;
+                missing.write_list(f, " more")?;
+                write!(f, " to make this payment")
+            }
+            BuyOperationFail::NoColorToCopy => {
+                write!(
+                    f,
+                    "This card copies the color of one of yours, and you have none"
+                )
+            }
+        }
+    }
+}
+
+impl Error for BuyOperationFail {}
+
+/// Why a board could not be set up by `Board::try_new` or `BoardBuilder::build`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SetupError {
+    NoPlayers,
+    DuplicatePlayerId(PlayerId),
+    MissingTier(ProductionTier),
+    /// There is a deck for a tier that is not in the `MarketLayout`.
+    UnknownTier(ProductionTier),
+    DuplicateCardId(CardId),
+    DuplicateNobleId(NobleId),
+    TooManyReservedCards(PlayerId),
+    /// There are more than 255 tokens of this piece in the game.
+    TooManyTokens(Piece),
+    /// There are tokens of a piece that is not in the `PieceSet`.
+    UnknownPiece(Piece),
+    /// The player count is not supported by the chosen rules.
+    UnsupportedPlayerCount(u8),
+    /// The chosen starting player is not playing.
+    UnknownStartingPlayer(PlayerId),
+    /// Cities replace the nobles, so a board played with them has none.
+    #[cfg(feature = "cities")]
+    NoblesWithCities,
+    /// Cities decide the winner, so `Rules::disable_winning_points` is needed.
+    #[cfg(feature = "cities")]
+    WinningPointsWithCities,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::NoPlayers => write!(f, "A game needs at least one player"),
+            SetupError::DuplicatePlayerId(id) => write!(f, "There are two players with id {}", id),
+            SetupError::MissingTier(tier) => write!(f, "There is no deck for tier {:?}", tier),
+            SetupError::UnknownTier(tier) => write!(f, "Tier {:?} is not in the game", tier),
+            SetupError::DuplicateCardId(id) => write!(f, "There are two cards with id {}", id),
+            SetupError::DuplicateNobleId(id) => write!(f, "There are two nobles with id {}", id),
+            SetupError::TooManyReservedCards(id) => write!(
+                f,
+                "{} starts with more than {} reserved cards",
+                id, MAXIMUM_RESERVED_CARDS
+            ),
+            SetupError::TooManyTokens(piece) => {
+                write!(f, "There are more than 255 {} pieces in the game", piece)
+            }
+            SetupError::UnknownPiece(piece) => {
+                write!(f, "{} pieces are not used in this game", piece)
+            }
+            SetupError::UnsupportedPlayerCount(n_of_players) => {
+                write!(f, "This game cannot be played by {} players", n_of_players)
+            }
+            SetupError::UnknownStartingPlayer(id) => {
+                write!(f, "The starting player, {}, is not playing", id)
+            }
+            #[cfg(feature = "cities")]
+            SetupError::NoblesWithCities => {
+                write!(f, "A game played with cities cannot have nobles")
+            }
+            #[cfg(feature = "cities")]
+            SetupError::WinningPointsWithCities => {
+                write!(f, "A game played with cities cannot be won by points alone")
+            }
+        }
+    }
+}
+
+impl Error for SetupError {}
+
+/// A broken rule of the game found by `Board::validate`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvariantViolation {
+    /// The tokens in the bank and in the players' hands do not add up to the initial supply.
+    TokenSupplyMismatch {
+        piece: Piece,
+        expected: u16,
+        found: u16,
+    },
+    DuplicateCard(CardId),
+    CardCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    DuplicateNoble(NobleId),
+    NobleCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    MarketOverflow {
+        tier: ProductionTier,
+        cards: usize,
+        slots: usize,
+    },
+    TooManyReservedCards {
+        player: PlayerId,
+        cards: usize,
+    },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::TokenSupplyMismatch {
+                piece,
+                expected,
+                found,
+            } => write!(
+                f,
+                "There should be {} {} pieces in the game, found {}",
+                expected, piece, found
+            ),
+            InvariantViolation::DuplicateCard(id) => {
+                write!(f, "The {} is in more than one place", id)
+            }
+            InvariantViolation::CardCountMismatch { expected, found } => write!(
+                f,
+                "There should be {} cards in the game, found {}",
+                expected, found
+            ),
+            InvariantViolation::DuplicateNoble(id) => {
+                write!(f, "The {} is in more than one place", id)
+            }
+            InvariantViolation::NobleCountMismatch { expected, found } => write!(
+                f,
+                "There should be {} nobles in the game, found {}",
+                expected, found
+            ),
+            InvariantViolation::MarketOverflow { tier, cards, slots } => write!(
+                f,
+                "The market of tier {:?} has {} cards for {} slots",
+                tier, cards, slots
+            ),
+            InvariantViolation::TooManyReservedCards { player, cards } => write!(
+                f,
+                "{} has {} reserved cards, more than the {} allowed",
+                player, cards, MAXIMUM_RESERVED_CARDS
+            ),
+        }
+    }
+}
+
+impl Error for InvariantViolation {}
+
+/// Records what `Board::apply` changed, so that `Board::undo` can revert it.
+///
+/// Tokens must be given back to `Board::undo` in the reverse order they were created.
+#[derive(Debug)]
+pub struct UndoToken {
+    player_turn: usize,
+    action_needed: ActionType,
+    round_type: RoundType,
+    rounds_played: u32,
+    consecutive_passes: usize,
+    pending_ability: Option<PendingAbility>,
+    #[cfg(feature = "trading-posts")]
+    bonus_piece_due: bool,
+    bank: Funds,
+    player_funds: Funds,
+    #[cfg(feature = "trading-posts")]
+    player_powers: PlayerPowers,
+    position_hash: u64,
+    change: BoardChange,
+}
+
+#[derive(Debug)]
+enum BoardChange {
+    Nothing,
+    ReservedFromDeck(ProductionTier),
+    ReservedFromMarket(MarketSlot),
+    BoughtFromMarket(MarketSlot),
+    BoughtFromReserve(usize),
+    SelectedNoble(usize),
+    SelectedReservedNoble(usize),
+    TookFreeCard(MarketSlot),
+    ReservedNoble(usize),
+    CopiedColor {
+        index: usize,
+        previous: Piece,
+    },
+    #[cfg(feature = "strongholds")]
+    PlacedStronghold,
+    #[cfg(feature = "strongholds")]
+    RemovedStronghold(usize, Stronghold),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MarketSlot {
+    tier: ProductionTier,
+    index: usize,
+    refilled: bool,
+    /// The stronghold given back when the card left the market, and where it was listed.
+    #[cfg(feature = "strongholds")]
+    stronghold: Option<(usize, Stronghold)>,
+}
+
+/// A game in progress.
+///
+/// Two boards are equal when they describe the same position: the same tokens, the same cards in
+/// each tableau, reserve and market (in any order), the same nobles and the same player to act.
+/// The order of the cards left in the decks and the round and pass counters are not part of the
+/// position.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "SerializedBoard")]
 pub struct Board {
     players: Vec<player::Player>,
     player_turn: usize,
-    pub bank: bank::Funds,
+    starting_player: usize,
+    bank: bank::Funds,
     decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
-    cards_for_sale: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
+    cards_for_sale: HashMap<ProductionTier, Vec<Option<Identifiable<ProductionCard, CardId>>>>,
+    market_layout: MarketLayout,
+    pieces: PieceSet,
     nobles: Vec<Noble>,
+    #[cfg(feature = "trading-posts")]
+    trading_posts: Vec<TradingPost>,
     action_needed: ActionType,
     round_type: RoundType,
     winner: Option<Winner>,
+    end_reason: Option<EndReason>,
+    rules: Rules,
+    rounds_played: u32,
+    consecutive_passes: usize,
+    pending_ability: Option<PendingAbility>,
+    #[cfg(feature = "trading-posts")]
+    bonus_piece_due: bool,
+    #[cfg(feature = "strongholds")]
+    strongholds: Vec<Stronghold>,
+    token_supply: Funds,
+    total_cards: usize,
+    total_nobles: usize,
+    #[serde(serialize_with = "serialize_card_tiers")]
+    card_tiers: HashMap<CardId, ProductionTier>,
+    #[serde(skip)]
+    position_hash: u64,
 }
 
-impl Board {
-    pub fn new(
-        players: Vec<player::Player>,
-        bank: bank::Funds,
-        decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
-        nobles: Vec<Noble>,
-    ) -> Self {
-        let mut new_decks = decks;
-        let mut cards_for_sale = HashMap::new();
-        for (tier, prod_deck) in new_decks.iter_mut() {
-            let mut to_sell: Vec<Identifiable<ProductionCard, CardId>> = vec![];
-            for _ in 1..=4 {
-                if let Some(to_add) = prod_deck.pop() {
-                    to_sell.push(to_add);
-                }
-            }
-            cards_for_sale.insert(*tier, to_sell);
-        }
-        Self {
-            players,
-            player_turn: 0,
-            bank,
-            decks: new_decks,
-            cards_for_sale,
-            nobles,
-            action_needed: ActionType::Normal,
-            round_type: RoundType::Normal,
-            winner: None,
-        }
-    }
-
-    pub fn get_deck(&self, tier: &ProductionTier) -> Vec<Identifiable<ProductionCard, CardId>> {
-        self.decks.get(tier).unwrap().clone()
-    }
+/// Card ids are not strings, so the tiers are stored as a list of pairs, sorted by card id.
+fn serialize_card_tiers<S: serde::Serializer>(
+    card_tiers: &HashMap<CardId, ProductionTier>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut card_tiers: Vec<_> = card_tiers.iter().collect();
+    card_tiers.sort_by_key(|(card_id, _)| card_id.id());
+    serializer.collect_seq(card_tiers)
+}
 
-    pub fn get_cards_for_sale(
-        &self,
-        tier: &ProductionTier,
-    ) -> Vec<Identifiable<ProductionCard, CardId>> {
-        self.cards_for_sale.get(tier).unwrap().clone()
-    }
+/// `Board` as it is stored, the position hash being rebuilt on load.
+#[derive(Deserialize)]
+struct SerializedBoard {
+    players: Vec<player::Player>,
+    player_turn: usize,
+    #[serde(default)]
+    starting_player: usize,
+    bank: bank::Funds,
+    decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
+    cards_for_sale: HashMap<ProductionTier, Vec<Option<Identifiable<ProductionCard, CardId>>>>,
+    #[serde(default)]
+    market_layout: MarketLayout,
+    #[serde(default)]
+    pieces: PieceSet,
+    nobles: Vec<Noble>,
+    #[cfg(feature = "trading-posts")]
+    #[serde(default)]
+    trading_posts: Vec<TradingPost>,
+    action_needed: ActionType,
+    round_type: RoundType,
+    winner: Option<Winner>,
+    #[serde(default)]
+    end_reason: Option<EndReason>,
+    #[serde(default)]
+    rules: Rules,
+    #[serde(default)]
+    rounds_played: u32,
+    #[serde(default)]
+    consecutive_passes: usize,
+    #[serde(default)]
+    pending_ability: Option<PendingAbility>,
+    #[cfg(feature = "trading-posts")]
+    #[serde(default)]
+    bonus_piece_due: bool,
+    #[cfg(feature = "strongholds")]
+    #[serde(default)]
+    strongholds: Vec<Stronghold>,
+    token_supply: Option<Funds>,
+    total_cards: Option<usize>,
+    total_nobles: Option<usize>,
+    card_tiers: Option<Vec<(CardId, ProductionTier)>>,
+}
 
-    pub fn get_nobles(&self) -> Vec<Noble> {
-        self.nobles.clone()
+impl From<SerializedBoard> for Board {
+    /// Boards saved before the initial supply was recorded take their current contents as the
+    /// reference for `Board::validate`.
+    fn from(serialized: SerializedBoard) -> Self {
+        let mut board = Board {
+            players: serialized.players,
+            player_turn: serialized.player_turn,
+            starting_player: serialized.starting_player,
+            bank: serialized.bank,
+            decks: serialized.decks,
+            cards_for_sale: serialized.cards_for_sale,
+            market_layout: serialized.market_layout,
+            pieces: serialized.pieces,
+            nobles: serialized.nobles,
+            #[cfg(feature = "trading-posts")]
+            trading_posts: serialized.trading_posts,
+            action_needed: serialized.action_needed,
+            round_type: serialized.round_type,
+            winner: serialized.winner,
+            end_reason: serialized.end_reason,
+            rules: serialized.rules,
+            rounds_played: serialized.rounds_played,
+            consecutive_passes: serialized.consecutive_passes,
+            pending_ability: serialized.pending_ability,
+            #[cfg(feature = "trading-posts")]
+            bonus_piece_due: serialized.bonus_piece_due,
+            #[cfg(feature = "strongholds")]
+            strongholds: serialized.strongholds,
+            token_supply: Funds::default(),
+            total_cards: 0,
+            total_nobles: 0,
+            card_tiers: HashMap::new(),
+            position_hash: 0,
+        };
+        board.card_tiers = match serialized.card_tiers {
+            Some(card_tiers) => card_tiers.into_iter().collect(),
+            None => board.map_card_tiers(),
+        };
+        board.token_supply = serialized
+            .token_supply
+            .unwrap_or_else(|| board.count_tokens());
+        board.total_cards = serialized
+            .total_cards
+            .unwrap_or_else(|| board.count_cards());
+        board.total_nobles = serialized
+            .total_nobles
+            .unwrap_or_else(|| board.count_nobles());
+        board.position_hash = board.compute_position_hash();
+        board
     }
+}
 
-    fn get_winner(&self) -> Option<Winner> {
-        let max_points_player = self
-            .players
+fn same_cards(
+    cards: &[Identifiable<ProductionCard, CardId>],
+    other_cards: &[Identifiable<ProductionCard, CardId>],
+) -> bool {
+    cards.len() == other_cards.len()
+        && cards
             .iter()
-            .max_by_key(|p| p.total_victory_points())?;
+            .all(|card| other_cards.iter().any(|other| other.uid == card.uid))
+}
 
-        let max_points = max_points_player.total_victory_points();
+/// Whether two markets show the same cards, wherever they stand.
+fn same_market(
+    slots: &[Option<Identifiable<ProductionCard, CardId>>],
+    other_slots: &[Option<Identifiable<ProductionCard, CardId>>],
+) -> bool {
+    let cards = || slots.iter().flatten();
+    let other_cards = || other_slots.iter().flatten();
+    cards().count() == other_cards().count()
+        && cards().all(|card| other_cards().any(|other| other.uid == card.uid))
+}
 
-        let possible_winners: Vec<&Player> = self
-            .players
+fn same_nobles(nobles: &[Noble], other_nobles: &[Noble]) -> bool {
+    nobles.len() == other_nobles.len()
+        && nobles
             .iter()
-            .filter(|p| p.total_victory_points() == max_points)
-            .collect();
-
-        let player_with_least_amount_of_cards = possible_winners
-            .clone()
-            .into_iter()
-            .min_by_key(|p| p.production_cards.len())?;
+            .all(|noble| other_nobles.iter().any(|other| other.id == noble.id))
+}
 
-        let least_amount_of_cards = player_with_least_amount_of_cards.production_cards.len();
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        let same_players = || {
+            self.players.len() == other.players.len()
+                && self.players.iter().zip(&other.players).all(|(p, o)| {
+                    p.id == o.id
+                        && p.funds == o.funds
+                        && same_cards(&p.production_cards, &o.production_cards)
+                        && same_cards(&p.reserved_cards, &o.reserved_cards)
+                        && same_nobles(&p.nobles, &o.nobles)
+                        && same_nobles(&p.reserved_nobles, &o.reserved_nobles)
+                })
+        };
+        let same_market = || {
+            self.cards_for_sale.len() == other.cards_for_sale.len()
+                && self.cards_for_sale.iter().all(|(tier, cards)| {
+                    other
+                        .cards_for_sale
+                        .get(tier)
+                        .is_some_and(|other_cards| same_market(cards, other_cards))
+                })
+        };
 
-        let winners: Vec<PlayerId> = possible_winners
-            .into_iter()
-            .filter(|p| p.production_cards.len() == least_amount_of_cards)
-            .map(|p| p.id)
-            .collect();
+        #[cfg(feature = "strongholds")]
+        let same_strongholds = || {
+            self.strongholds.len() == other.strongholds.len()
+                && self
+                    .strongholds
+                    .iter()
+                    .all(|stronghold| other.strongholds.contains(stronghold))
+        };
+        #[cfg(not(feature = "strongholds"))]
+        let same_strongholds = || true;
+
+        #[cfg(feature = "trading-posts")]
+        let same_powers = || {
+            self.bonus_piece_due == other.bonus_piece_due
+                && self
+                    .players
+                    .iter()
+                    .zip(&other.players)
+                    .all(|(p, o)| p.powers == o.powers)
+        };
+        #[cfg(not(feature = "trading-posts"))]
+        let same_powers = || true;
+
+        self.position_hash == other.position_hash
+            && self.player_turn == other.player_turn
+            && self.starting_player == other.starting_player
+            && self.action_needed == other.action_needed
+            && self.round_type == other.round_type
+            && self.pending_ability == other.pending_ability
+            && self.bank == other.bank
+            && same_nobles(&self.nobles, &other.nobles)
+            && same_players()
+            && same_market()
+            && same_strongholds()
+            && same_powers()
+    }
+}
 
-        if winners.len() == 1 {
-            return Some(Winner::Winner(*winners.first().unwrap()));
-        }
+impl Eq for Board {}
 
-        Some(Winner::Draw(winners))
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.position_hash);
     }
+}
 
-    fn get_who_plays_next(&self) -> usize {
-        let n_of_players = self.players.len();
-        if self.player_turn == n_of_players - 1 {
-            return 0;
-        }
-        self.player_turn + 1
-    }
+/// Step by step construction of a `Board`, checked by `BoardBuilder::build`.
+#[derive(Debug, Clone, Default)]
+pub struct BoardBuilder {
+    players: Vec<player::Player>,
+    bank: bank::Funds,
+    decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
+    market_layout: MarketLayout,
+    pieces: PieceSet,
+    nobles: Vec<Noble>,
+    #[cfg(feature = "trading-posts")]
+    trading_posts: Vec<TradingPost>,
+    starting_player: Option<StartingPlayer>,
+    rules: Rules,
+}
 
-    pub fn get_players(&self) -> Iter<Player> {
-        self.players.iter()
-    }
+#[derive(Debug, Clone, Copy)]
+enum StartingPlayer {
+    Chosen(PlayerId),
+    Seeded(u64),
+}
 
-    pub fn get_card_from_board(
-        &self,
-        card_id: &CardId,
-    ) -> Option<Identifiable<ProductionCard, CardId>> {
-        for cards in self.cards_for_sale.values() {
-            for card in cards {
-                if &card.uid == card_id {
-                    return Some(card.clone());
-                }
-            }
-        }
-        None
+impl BoardBuilder {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub fn get_who_is_playing_now(&self) -> &player::Player {
-        self.players.get(self.player_turn).unwrap()
+    pub fn player(mut self, player: player::Player) -> Self {
+        self.players.push(player);
+        self
     }
 
-    fn action_buy_production_card(&self, card_id: &CardId) -> Result<Board, ActionFail> {
-        let mut new_board_state = self.clone();
-        let card = self
-            .get_card_from_board(card_id)
-            .ok_or(ActionFail::InvalidBuyOperation(
-                BuyOperationFail::CardNotFoundOnBoard,
-            ))?;
-        let mut player = self.players[self.player_turn].clone();
-
-        let card_data = card.data.clone();
-
-        let player_remaining_funds =
-            production_card::ProductionCard::buy(player.clone(), card_data.clone())
-                .map_err(ActionFail::InvalidBuyOperation)?;
-
-        let used_coins = (player.funds - player_remaining_funds.clone())
-            .expect("Player should have enough funds");
-        player.funds = player_remaining_funds;
-        player.production_cards.push(card);
-
-        for (tier, cards) in &mut new_board_state.cards_for_sale {
-            cards.retain(|c| &c.uid != card_id);
-            let deck = new_board_state.decks.get_mut(tier).unwrap();
-            if let Some(card_drawn) = deck.pop() {
-                cards.push(card_drawn);
-            }
-        }
-        new_board_state.players[self.player_turn] = player;
-
-        new_board_state.bank = new_board_state.bank + used_coins;
-
-        Ok(new_board_state)
+    pub fn players(mut self, players: Vec<player::Player>) -> Self {
+        self.players.extend(players);
+        self
     }
 
-    fn action_collect_pieces(
-        &self,
-        collect_pieces: &[Piece],
-        discard_pieces: &[Piece],
-    ) -> Result<Board, ActionFail> {
-        let current_player = self.get_who_is_playing_now();
-        let player_funds = current_player.funds.clone();
+    pub fn bank(mut self, bank: bank::Funds) -> Self {
+        self.bank = bank;
+        self
+    }
 
-        let collect_request = bank::CollectRequest::new(
-            self.bank.clone(),
-            player_funds,
-            collect_pieces.to_vec(),
-            discard_pieces.to_vec(),
-        );
-        let result = bank::Funds::collect(collect_request).map_err(ActionFail::InvalidCollect)?;
+    /// Sets the deck of a tier, the last card being the top of the deck.
+    pub fn deck(
+        mut self,
+        tier: ProductionTier,
+        cards: Vec<Identifiable<ProductionCard, CardId>>,
+    ) -> Self {
+        self.decks.insert(tier, cards);
+        self
+    }
 
-        let mut new_board_state = self.clone();
-        new_board_state.bank = result.bank_funds;
-        new_board_state.players[self.player_turn].funds = result.player_funds;
+    /// The tiers of the game and the size of their market, those of the original game otherwise.
+    pub fn market_layout(mut self, market_layout: MarketLayout) -> Self {
+        self.market_layout = market_layout;
+        self
+    }
 
-        Ok(new_board_state)
+    /// The pieces of the game, those of the original game otherwise.
+    pub fn pieces(mut self, pieces: PieceSet) -> Self {
+        self.pieces = pieces;
+        self
     }
 
-    fn action_reserve_card_from_deck(&self, tier: &ProductionTier) -> Result<Board, ActionFail> {
-        let mut new_board_state = self.clone();
-        let deck = new_board_state.decks.get_mut(tier).unwrap();
+    pub fn nobles(mut self, nobles: Vec<Noble>) -> Self {
+        self.nobles = nobles;
+        self
+    }
 
-        let card_drawn = deck.pop().ok_or(ActionFail::CannotReserveFromEmptyDeck)?;
+    /// The track of trading posts, in the order players claim them.
+    #[cfg(feature = "trading-posts")]
+    pub fn trading_posts(mut self, trading_posts: Vec<TradingPost>) -> Self {
+        self.trading_posts = trading_posts;
+        self
+    }
 
-        new_board_state.players[new_board_state.player_turn]
-            .reserved_cards
-            .push(card_drawn);
-        Ok(new_board_state)
+    /// The player who plays first, the first player otherwise.
+    pub fn starting_player(mut self, player_id: PlayerId) -> Self {
+        self.starting_player = Some(StartingPlayer::Chosen(player_id));
+        self
     }
 
-    fn action_reserve_card(&self, card_id: &CardId) -> Result<Board, ActionFail> {
-        self.reserve_card(card_id)
-            .map_err(ActionFail::InvalidReserve)
+    /// Draws the player who plays first, always the same one for the same seed.
+    pub fn random_starting_player(mut self, seed: u64) -> Self {
+        self.starting_player = Some(StartingPlayer::Seeded(seed));
+        self
     }
 
-    fn reserve_card(&self, card_id: &CardId) -> Result<Board, ReserveOperationFail> {
-        let success = player::Player::reserve_card(self, card_id)?;
-        let mut new_board = self.clone();
-        new_board.bank = success.bank_funds;
-        new_board.players[new_board.player_turn] = success.player;
-        Ok(new_board)
+    pub fn rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
     }
 
-    pub fn do_action(board: Board, action: &Action) -> Result<Board, ActionFail> {
-        match board.action_needed {
-            ActionType::Normal => {
-                if let Action::SelectNoble(_) = action {
-                    return Err(ActionFail::YouCannotSelectNobleNow);
-                }
-            }
-            ActionType::SelectNoble => {
-                if let Action::SelectNoble(_) = action {
-                } else {
-                    return Err(ActionFail::YouNeedToSelectNoble);
-                }
+    pub fn build(self) -> Result<Board, SetupError> {
+        let starting_player = self.starting_player;
+        let rules = self.rules;
+        #[cfg(feature = "trading-posts")]
+        let trading_posts = self.trading_posts;
+        let mut board = Board::try_new_with(
+            self.players,
+            self.bank,
+            self.decks,
+            self.nobles,
+            self.market_layout,
+            self.pieces,
+        )?;
+
+        let seat = match starting_player {
+            None => 0,
+            Some(StartingPlayer::Chosen(player_id)) => board
+                .players
+                .iter()
+                .position(|player| player.id == player_id)
+                .ok_or(SetupError::UnknownStartingPlayer(player_id))?,
+            Some(StartingPlayer::Seeded(seed)) => {
+                (zobrist::splitmix64(seed) % board.players.len() as u64) as usize
             }
+        };
+        board.starting_player = seat;
+        board.set_player_turn(seat);
+        board.rules = rules;
+        #[cfg(feature = "trading-posts")]
+        {
+            board.trading_posts = trading_posts;
         }
-        let mut new_board_state = board.clone();
-        let mut has_selected_noble = false;
-        match action {
-            Action::PassTheTurn => {}
-            Action::ReserveCardFromDeck(tier) => {
-                new_board_state = board.action_reserve_card_from_deck(tier)?;
+
+        Ok(board)
+    }
+}
+
+impl Board {
+    pub fn builder() -> BoardBuilder {
+        BoardBuilder::new()
+    }
+
+    /// Sets up a board, checking that it can be played. Tiers without a deck are an error.
+    pub fn try_new(
+        players: Vec<player::Player>,
+        bank: bank::Funds,
+        decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
+        nobles: Vec<Noble>,
+    ) -> Result<Self, SetupError> {
+        Self::try_new_with(
+            players,
+            bank,
+            decks,
+            nobles,
+            MarketLayout::default(),
+            PieceSet::default(),
+        )
+    }
+
+    fn try_new_with(
+        players: Vec<player::Player>,
+        bank: bank::Funds,
+        decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
+        nobles: Vec<Noble>,
+        market_layout: MarketLayout,
+        pieces: PieceSet,
+    ) -> Result<Self, SetupError> {
+        if players.is_empty() {
+            return Err(SetupError::NoPlayers);
+        }
+
+        let mut player_ids = HashSet::new();
+        for player in &players {
+            if !player_ids.insert(player.id) {
+                return Err(SetupError::DuplicatePlayerId(player.id));
             }
-            Action::CollectPieces(collect_pieces, discard_pieces) => {
-                new_board_state = board.action_collect_pieces(collect_pieces, discard_pieces)?;
+            if player.reserved_cards.len() > MAXIMUM_RESERVED_CARDS {
+                return Err(SetupError::TooManyReservedCards(player.id));
             }
-            Action::ReserveCardFromBoard(card_id) => {
-                new_board_state = board.action_reserve_card(card_id)?;
+        }
+
+        for tier in market_layout.tiers() {
+            if !decks.contains_key(&tier) {
+                return Err(SetupError::MissingTier(tier));
             }
-            Action::BuyCard(card_id) => {
-                new_board_state = board.action_buy_production_card(card_id)?;
+        }
+        if let Some(tier) = decks.keys().find(|tier| !market_layout.contains(tier)) {
+            return Err(SetupError::UnknownTier(*tier));
+        }
+
+        let mut card_ids = HashSet::new();
+        let all_cards = decks
+            .values()
+            .flatten()
+            .chain(players.iter().flat_map(|player| {
+                player
+                    .production_cards
+                    .iter()
+                    .chain(player.reserved_cards.iter())
+            }));
+        for card in all_cards {
+            if !card_ids.insert(card.uid) {
+                return Err(SetupError::DuplicateCardId(card.uid));
             }
-            Action::SelectNoble(noble_id) => {
-                new_board_state.action_needed = ActionType::Normal;
-                let nobles = new_board_state.nobles.clone();
-                let noble = nobles
+        }
+
+        let mut noble_ids = HashSet::new();
+        let all_nobles = nobles.iter().chain(
+            players
+                .iter()
+                .flat_map(|player| player.nobles.iter().chain(player.reserved_nobles.iter())),
+        );
+        for noble in all_nobles {
+            if !noble_ids.insert(noble.id) {
+                return Err(SetupError::DuplicateNobleId(noble.id));
+            }
+        }
+
+        for piece in Piece::every() {
+            let total = players.iter().try_fold(bank[piece], |total, player| {
+                total.checked_add(player.funds[piece])
+            });
+            match total {
+                None => return Err(SetupError::TooManyTokens(piece)),
+                Some(total) if total > 0 && !pieces.contains(piece) => {
+                    return Err(SetupError::UnknownPiece(piece))
+                }
+                Some(_) => {}
+            }
+        }
+
+        Ok(Self::new_with(
+            players,
+            bank,
+            decks,
+            nobles,
+            market_layout,
+            pieces,
+        ))
+    }
+
+    /// Sets up a board without checking its input, see `Board::try_new`.
+    ///
+    /// Tiers without a deck are given an empty one. A board without players panics as soon as it
+    /// is played.
+    #[deprecated(note = "use `Board::try_new` or `Board::builder`, which check the setup")]
+    pub fn new(
+        players: Vec<player::Player>,
+        bank: bank::Funds,
+        decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
+        nobles: Vec<Noble>,
+    ) -> Self {
+        Self::new_with(
+            players,
+            bank,
+            decks,
+            nobles,
+            MarketLayout::default(),
+            PieceSet::default(),
+        )
+    }
+
+    fn new_with(
+        players: Vec<player::Player>,
+        bank: bank::Funds,
+        decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>>,
+        nobles: Vec<Noble>,
+        market_layout: MarketLayout,
+        pieces: PieceSet,
+    ) -> Self {
+        let mut new_decks = decks;
+        for tier in market_layout.tiers() {
+            new_decks.entry(tier).or_default();
+        }
+        let mut cards_for_sale = HashMap::new();
+        for (tier, prod_deck) in new_decks.iter_mut() {
+            let mut to_sell: Vec<Option<Identifiable<ProductionCard, CardId>>> = vec![];
+            for _ in 0..market_layout.slots(tier) {
+                if let Some(to_add) = prod_deck.pop() {
+                    to_sell.push(Some(to_add));
+                }
+            }
+            cards_for_sale.insert(*tier, to_sell);
+        }
+
+        let mut board = Self {
+            players,
+            player_turn: 0,
+            starting_player: 0,
+            bank,
+            decks: new_decks,
+            cards_for_sale,
+            market_layout,
+            pieces,
+            nobles,
+            #[cfg(feature = "trading-posts")]
+            trading_posts: vec![],
+            action_needed: ActionType::Normal,
+            round_type: RoundType::Normal,
+            winner: None,
+            end_reason: None,
+            rules: Rules::default(),
+            rounds_played: 0,
+            consecutive_passes: 0,
+            pending_ability: None,
+            #[cfg(feature = "trading-posts")]
+            bonus_piece_due: false,
+            #[cfg(feature = "strongholds")]
+            strongholds: vec![],
+            token_supply: Funds::default(),
+            total_cards: 0,
+            total_nobles: 0,
+            card_tiers: HashMap::new(),
+            position_hash: 0,
+        };
+        board.card_tiers = board.map_card_tiers();
+        board.token_supply = board.count_tokens();
+        board.total_cards = board.count_cards();
+        board.total_nobles = board.count_nobles();
+        board.position_hash = board.compute_position_hash();
+
+        board
+    }
+
+    /// Tiers of the cards still in the decks or on the market.
+    fn map_card_tiers(&self) -> HashMap<CardId, ProductionTier> {
+        let market = self
+            .cards_for_sale
+            .iter()
+            .flat_map(|(tier, slots)| slots.iter().flatten().map(|card| (card.uid, *tier)));
+        self.decks
+            .iter()
+            .flat_map(|(tier, cards)| cards.iter().map(|card| (card.uid, *tier)))
+            .chain(market)
+            .collect()
+    }
+
+    fn count_tokens(&self) -> Funds {
+        self.players.iter().fold(self.bank, |total, player| {
+            total.saturating_add(player.funds)
+        })
+    }
+
+    fn count_cards(&self) -> usize {
+        self.all_cards().count()
+    }
+
+    fn count_nobles(&self) -> usize {
+        self.all_nobles().count()
+    }
+
+    fn all_cards(&self) -> impl Iterator<Item = &Identifiable<ProductionCard, CardId>> {
+        self.decks
+            .values()
+            .flatten()
+            .chain(self.cards_for_sale.values().flatten().flatten())
+            .chain(self.players.iter().flat_map(|player| {
+                player
+                    .production_cards
                     .iter()
-                    .find(|noble| &noble.id == noble_id)
-                    .ok_or(ActionFail::NobleNotFound)?;
-                new_board_state.nobles.retain(|noble| &noble.id != noble_id);
-                let current_player = &mut new_board_state.players[new_board_state.player_turn];
-                current_player.nobles.push(noble.clone());
-                has_selected_noble = true;
+                    .chain(player.reserved_cards.iter())
+            }))
+    }
+
+    fn all_nobles(&self) -> impl Iterator<Item = &Noble> {
+        self.nobles.iter().chain(
+            self.players
+                .iter()
+                .flat_map(|player| player.nobles.iter().chain(player.reserved_nobles.iter())),
+        )
+    }
+
+    /// Checks that no token, card or noble was created or lost, and that the market and the
+    /// reserves are within their limits.
+    ///
+    /// Debug builds run it after every `Board::do_action`.
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = vec![];
+
+        for (piece, expected) in self.token_supply.iter() {
+            let found = self.bank[piece] as u16
+                + self
+                    .players
+                    .iter()
+                    .map(|player| player.funds[piece] as u16)
+                    .sum::<u16>();
+            if found != expected as u16 {
+                violations.push(InvariantViolation::TokenSupplyMismatch {
+                    piece,
+                    expected: expected as u16,
+                    found,
+                });
             }
         }
 
-        let can_select_noble = new_board_state.can_select_noble() && !has_selected_noble;
-        if can_select_noble {
-            new_board_state.action_needed = ActionType::SelectNoble;
+        let mut seen_cards = HashSet::new();
+        for card in self.all_cards() {
+            if !seen_cards.insert(card.uid) {
+                violations.push(InvariantViolation::DuplicateCard(card.uid));
+            }
+        }
+        let found = self.count_cards();
+        if found != self.total_cards {
+            violations.push(InvariantViolation::CardCountMismatch {
+                expected: self.total_cards,
+                found,
+            });
         }
 
-        if new_board_state.has_some_player_passed_win_threshold() {
-            new_board_state.round_type = RoundType::LastRound;
+        let mut seen_nobles = HashSet::new();
+        for noble in self.all_nobles() {
+            if !seen_nobles.insert(noble.id) {
+                violations.push(InvariantViolation::DuplicateNoble(noble.id));
+            }
+        }
+        let found = self.count_nobles();
+        if found != self.total_nobles {
+            violations.push(InvariantViolation::NobleCountMismatch {
+                expected: self.total_nobles,
+                found,
+            });
         }
 
-        if !can_select_noble {
-            if board.is_last_player_turn() && new_board_state.round_type == RoundType::LastRound {
-                new_board_state.winner = board.get_winner();
+        for (tier, market) in &self.cards_for_sale {
+            let slots = self.market_layout.slots(tier);
+            let cards = market.iter().flatten().count();
+            if cards > slots {
+                violations.push(InvariantViolation::MarketOverflow {
+                    tier: *tier,
+                    cards,
+                    slots,
+                });
             }
+        }
 
-            new_board_state.player_turn = new_board_state.get_who_plays_next();
+        for player in &self.players {
+            if player.reserved_cards.len() > MAXIMUM_RESERVED_CARDS {
+                violations.push(InvariantViolation::TooManyReservedCards {
+                    player: player.id,
+                    cards: player.reserved_cards.len(),
+                });
+            }
         }
 
-        Ok(new_board_state)
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
     }
 
-    fn can_select_noble(&self) -> bool {
-        let player = self.get_who_is_playing_now();
-        let player_produces = player
-            .clone()
-            .production_cards
-            .into_iter()
-            .map(|card| card.data.produces)
-            .collect::<Vec<Piece>>();
-        let player_produces_as_funds = &Funds::new_from_list(player_produces);
+    /// A 64 bit key of the current position, kept up to date as actions are applied.
+    ///
+    /// Equal boards always have the same key, whatever order of moves led to them.
+    pub fn position_hash(&self) -> u64 {
+        self.position_hash
+    }
 
-        for noble in &self.nobles {
-            if (player_produces_as_funds.clone() - noble.cost.clone()).is_ok() {
-                return true;
+    fn compute_position_hash(&self) -> u64 {
+        let mut hash = zobrist::turn(self.player_turn)
+            ^ zobrist::action_needed(self.action_needed)
+            ^ zobrist::round(self.round_type);
+
+        for (piece, quantity) in self.bank.iter() {
+            hash ^= zobrist::bank_funds(piece, quantity);
+        }
+        for (seat, player) in self.players.iter().enumerate() {
+            for (piece, quantity) in player.funds.iter() {
+                hash ^= zobrist::player_funds(seat, piece, quantity);
+            }
+            for card in &player.production_cards {
+                hash ^= zobrist::tableau(seat, card.uid);
+                if card.data.ability == Some(CardAbility::CopyColor)
+                    && card.data.produces != Piece::Golden
+                {
+                    hash ^= zobrist::copied_color(card.uid, card.data.produces);
+                }
+            }
+            for card in &player.reserved_cards {
+                hash ^= zobrist::reserve(seat, card.uid);
+            }
+            for noble in &player.nobles {
+                hash ^= zobrist::player_noble(seat, noble.id);
+            }
+            for noble in &player.reserved_nobles {
+                hash ^= zobrist::reserved_noble(seat, noble.id);
+            }
+            #[cfg(feature = "trading-posts")]
+            {
+                hash ^= zobrist::player_powers(seat, &player.powers);
+            }
+        }
+        for (tier, cards) in &self.cards_for_sale {
+            for card in cards.iter().flatten() {
+                hash ^= zobrist::market(*tier, card.uid);
             }
         }
+        for noble in &self.nobles {
+            hash ^= zobrist::noble(noble.id);
+        }
+        #[cfg(feature = "strongholds")]
+        for stronghold in &self.strongholds {
+            hash ^= zobrist::stronghold(stronghold);
+        }
 
-        false
+        hash
     }
 
-    fn has_some_player_passed_win_threshold(&self) -> bool {
-        for p in &self.players {
-            if p.total_victory_points() >= WINNING_POINTS_THRESHOLD {
-                return true;
+    fn set_bank(&mut self, funds: Funds) {
+        for (piece, old, new) in self.bank.iter_with(&funds) {
+            if old != new {
+                self.position_hash ^=
+                    zobrist::bank_funds(piece, old) ^ zobrist::bank_funds(piece, new);
             }
         }
-        false
+        self.bank = funds;
     }
 
-    fn is_last_player_turn(&self) -> bool {
-        self.player_turn == self.players.len() - 1
+    fn set_player_funds(&mut self, seat: usize, funds: Funds) {
+        let player = &mut self.players[seat];
+        for (piece, old, new) in player.funds.iter_with(&funds) {
+            if old != new {
+                self.position_hash ^= zobrist::player_funds(seat, piece, old)
+                    ^ zobrist::player_funds(seat, piece, new);
+            }
+        }
+        player.funds = funds;
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::vec;
+    fn set_player_turn(&mut self, player_turn: usize) {
+        self.position_hash ^= zobrist::turn(self.player_turn) ^ zobrist::turn(player_turn);
+        self.player_turn = player_turn;
+    }
 
-    use bank::Funds;
-    use player::{Player, PlayerId};
-    use production_card::CardId;
+    #[cfg(feature = "trading-posts")]
+    fn set_player_powers(&mut self, seat: usize, powers: PlayerPowers) {
+        self.position_hash ^= zobrist::player_powers(seat, &self.players[seat].powers)
+            ^ zobrist::player_powers(seat, &powers);
+        self.players[seat].powers = powers;
+    }
 
-    use super::*;
+    fn set_action_needed(&mut self, action_needed: ActionType) {
+        self.position_hash ^=
+            zobrist::action_needed(self.action_needed) ^ zobrist::action_needed(action_needed);
+        self.action_needed = action_needed;
+    }
 
-    fn get_default_production_card_cost() -> Funds {
-        bank::Funds::new(1, 1, 0, 0, 0, 0)
+    fn set_round_type(&mut self, round_type: RoundType) {
+        self.position_hash ^= zobrist::round(self.round_type) ^ zobrist::round(round_type);
+        self.round_type = round_type;
     }
 
-    fn get_production_card(card_id: CardId) -> Identifiable<ProductionCard, CardId> {
-        let cost = get_default_production_card_cost();
-        let card = production_card::ProductionCard::new(cost, Piece::Red, Some(1));
-        Identifiable::new(card, card_id)
+    fn push_reserved_card(&mut self, card: Identifiable<ProductionCard, CardId>) {
+        self.position_hash ^= zobrist::reserve(self.player_turn, card.uid);
+        self.players[self.player_turn].reserved_cards.push(card);
     }
 
-    fn get_initial_player(id: PlayerId) -> player::Player {
-        let funds = bank::Funds::new(0, 0, 0, 0, 0, 0);
-        player::Player::new(id, funds, vec![], vec![])
+    #[deprecated(note = "use `Board::iter_deck`, which does not clone the deck")]
+    pub fn get_deck(&self, tier: &ProductionTier) -> Vec<Identifiable<ProductionCard, CardId>> {
+        self.iter_deck(tier).cloned().collect()
     }
 
-    fn get_player_with_winning_points(id: PlayerId, card_id: CardId, winning_points: u8) -> Player {
-        let player = get_initial_player(id);
-        let card = get_production_card(card_id.clone());
-        let card = Identifiable::new(
-            ProductionCard {
-                victory_points: Some(winning_points),
-                ..card.data
-            },
-            card_id,
-        );
-        Player {
-            production_cards: vec![card],
-            ..player
-        }
+    #[deprecated(note = "use `Board::iter_cards_for_sale`, which does not clone the market")]
+    pub fn get_cards_for_sale(
+        &self,
+        tier: &ProductionTier,
+    ) -> Vec<Identifiable<ProductionCard, CardId>> {
+        self.iter_cards_for_sale(tier).cloned().collect()
     }
 
-    fn get_initial_bank() -> bank::Funds {
-        bank::Funds::new(7, 7, 7, 7, 7, 5)
+    /// The pieces left at the bank. They are only changed through actions, which keep the
+    /// position hash up to date.
+    pub fn get_bank(&self) -> bank::Funds {
+        self.bank
     }
 
-    fn get_default_board() -> Board {
-        let p1 = get_initial_player(PlayerId::new(1));
-        let p2 = get_initial_player(PlayerId::new(2));
-        let p3 = get_initial_player(PlayerId::new(3));
-        let bank_funds = get_initial_bank();
-        let decks = HashMap::from([(
-            ProductionTier::One,
-            vec![
-                get_production_card(CardId::new(5)),
-                get_production_card(CardId::new(4)),
-                get_production_card(CardId::new(3)),
-                get_production_card(CardId::new(2)),
-                get_production_card(CardId::new(1)),
-            ],
-        )]);
-        let nobles = vec![];
-        Board::new(vec![p1, p2, p3], bank_funds, decks, nobles)
+    pub fn get_nobles(&self) -> Vec<Noble> {
+        self.nobles.clone()
     }
 
-    #[test]
-    fn auto_draw_necessary_cards() {
-        let board = get_default_board();
-        assert_eq!(
-            board
-                .cards_for_sale
-                .get(&ProductionTier::One)
-                .unwrap()
-                .len(),
-            4
-        );
-        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
+    pub fn iter_deck(
+        &self,
+        tier: &ProductionTier,
+    ) -> Iter<'_, Identifiable<ProductionCard, CardId>> {
+        self.decks.get(tier).map_or([].iter(), |deck| deck.iter())
+    }
 
-        let p1 = get_initial_player(PlayerId::new(1));
-        let p2 = get_initial_player(PlayerId::new(2));
-        let p3 = get_initial_player(PlayerId::new(3));
-        let bank_funds = get_initial_bank();
-        let decks = HashMap::from([(
-            ProductionTier::One,
-            vec![
-                get_production_card(CardId::new(5)),
-                get_production_card(CardId::new(4)),
-            ],
-        )]);
+    /// The cards on the market of a tier, skipping the empty slots.
+    pub fn iter_cards_for_sale(
+        &self,
+        tier: &ProductionTier,
+    ) -> impl Iterator<Item = &Identifiable<ProductionCard, CardId>> + '_ {
+        self.cards_for_sale
+            .get(tier)
+            .map_or([].iter(), |cards| cards.iter())
+            .flatten()
+    }
 
-        let board = Board::new(vec![p1, p2, p3], bank_funds, decks, vec![]);
-        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 0);
-        assert_eq!(
-            board
-                .cards_for_sale
-                .get(&ProductionTier::One)
-                .unwrap()
-                .len(),
-            2
+    pub fn iter_nobles(&self) -> Iter<'_, Noble> {
+        self.nobles.iter()
+    }
+
+    pub fn get_deck_size(&self, tier: &ProductionTier) -> usize {
+        self.decks.get(tier).map_or(0, Vec::len)
+    }
+
+    /// The market of a tier slot by slot, `None` where no card could be dealt. A card keeps its
+    /// slot when the cards next to it are taken.
+    pub fn get_market_slots(
+        &self,
+        tier: &ProductionTier,
+    ) -> Vec<Option<&Identifiable<ProductionCard, CardId>>> {
+        let market = self.cards_for_sale.get(tier).map_or(&[][..], Vec::as_slice);
+        (0..self.market_layout.slots(tier))
+            .map(|index| market.get(index).and_then(Option::as_ref))
+            .collect()
+    }
+
+    pub fn get_market_layout(&self) -> &MarketLayout {
+        &self.market_layout
+    }
+
+    pub fn get_pieces(&self) -> &PieceSet {
+        &self.pieces
+    }
+
+    /// Tier a card was dealt from, unknown for cards players started the game with.
+    pub fn get_card_tier(&self, card_id: &CardId) -> Option<ProductionTier> {
+        self.card_tiers.get(card_id).copied()
+    }
+
+    /// Index in `get_players` of the player who played first.
+    pub fn get_starting_player(&self) -> usize {
+        self.starting_player
+    }
+
+    /// Index in `get_players` of the player whose turn it is.
+    pub fn get_player_turn(&self) -> usize {
+        self.player_turn
+    }
+
+    pub fn get_action_needed(&self) -> ActionType {
+        self.action_needed
+    }
+
+    pub fn get_round_type(&self) -> RoundType {
+        self.round_type
+    }
+
+    /// The result of the game, once it is over.
+    pub fn get_winner(&self) -> Option<&Winner> {
+        self.winner.as_ref()
+    }
+
+    #[cfg(feature = "trading-posts")]
+    pub fn get_trading_posts(&self) -> &[TradingPost] {
+        &self.trading_posts
+    }
+
+    pub fn get_pending_ability(&self) -> Option<PendingAbility> {
+        self.pending_ability
+    }
+
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        self.end_reason
+    }
+
+    pub fn get_rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Rounds every player had a turn in, counted from the starting player.
+    pub fn get_rounds_played(&self) -> u32 {
+        self.rounds_played
+    }
+
+    pub fn get_score_breakdowns(&self) -> Vec<ScoreBreakdown> {
+        self.players.iter().map(Player::score_breakdown).collect()
+    }
+
+    fn compute_winner(&self) -> Option<Winner> {
+        let max_points_player = self
+            .players
+            .iter()
+            .max_by_key(|p| p.total_victory_points())?;
+
+        let max_points = max_points_player.total_victory_points();
+
+        let possible_winners: Vec<&Player> = self
+            .players
+            .iter()
+            .filter(|p| p.total_victory_points() == max_points)
+            .collect();
+
+        let player_with_least_amount_of_cards = possible_winners
+            .clone()
+            .into_iter()
+            .min_by_key(|p| p.production_cards.len())?;
+
+        let least_amount_of_cards = player_with_least_amount_of_cards.production_cards.len();
+
+        let winners: Vec<PlayerId> = possible_winners
+            .into_iter()
+            .filter(|p| p.production_cards.len() == least_amount_of_cards)
+            .map(|p| p.id)
+            .collect();
+
+        if winners.len() == 1 {
+            return Some(Winner::Winner(*winners.first().unwrap()));
+        }
+
+        Some(Winner::Draw(winners))
+    }
+
+    fn get_who_plays_next(&self) -> usize {
+        let n_of_players = self.players.len();
+        if self.player_turn + 1 >= n_of_players {
+            return 0;
+        }
+        self.player_turn + 1
+    }
+
+    pub fn get_players(&self) -> Iter<'_, Player> {
+        self.players.iter()
+    }
+
+    pub fn get_card_from_board(
+        &self,
+        card_id: &CardId,
+    ) -> Option<Identifiable<ProductionCard, CardId>> {
+        for cards in self.cards_for_sale.values() {
+            for card in cards.iter().flatten() {
+                if &card.uid == card_id {
+                    return Some(card.clone());
+                }
+            }
+        }
+        None
+    }
+
+    pub fn get_who_is_playing_now(&self) -> &player::Player {
+        self.players.get(self.player_turn).unwrap()
+    }
+
+    fn find_on_market(&self, card_id: &CardId) -> Option<(ProductionTier, usize)> {
+        for (tier, cards) in &self.cards_for_sale {
+            if let Some(index) = cards
+                .iter()
+                .position(|card| card.as_ref().is_some_and(|card| &card.uid == card_id))
+            {
+                return Some((*tier, index));
+            }
+        }
+        None
+    }
+
+    /// Takes a card out of the market, replacing it in the same slot by the top of its deck, or
+    /// leaving the slot empty once the deck runs out.
+    fn take_from_market(
+        &mut self,
+        tier: ProductionTier,
+        index: usize,
+    ) -> (Identifiable<ProductionCard, CardId>, MarketSlot) {
+        let market = self
+            .cards_for_sale
+            .get_mut(&tier)
+            .expect("Market tier should exist");
+        let card_drawn = self.decks.get_mut(&tier).and_then(|deck| deck.pop());
+        let refilled = card_drawn.is_some();
+        if let Some(card_drawn) = &card_drawn {
+            self.position_hash ^= zobrist::market(tier, card_drawn.uid);
+        }
+        let card =
+            mem::replace(&mut market[index], card_drawn).expect("Market slot should hold a card");
+        self.position_hash ^= zobrist::market(tier, card.uid);
+        let slot = MarketSlot {
+            tier,
+            index,
+            refilled,
+            #[cfg(feature = "strongholds")]
+            stronghold: self.release_stronghold(&card.uid),
+        };
+        (card, slot)
+    }
+
+    fn put_back_on_market(&mut self, slot: MarketSlot, card: Identifiable<ProductionCard, CardId>) {
+        let market = self
+            .cards_for_sale
+            .get_mut(&slot.tier)
+            .expect("Market tier should exist");
+        let card_drawn = market[slot.index].replace(card);
+        if slot.refilled {
+            self.decks
+                .get_mut(&slot.tier)
+                .expect("Deck should exist when the market was refilled")
+                .extend(card_drawn);
+        }
+
+        #[cfg(feature = "strongholds")]
+        if let Some((index, stronghold)) = slot.stronghold {
+            self.restore_stronghold(index, stronghold);
+        }
+    }
+
+    fn take_golden_for_reserve(&mut self) {
+        let mut player_funds = self.players[self.player_turn].funds;
+        if self.bank[Piece::Golden] > 0 {
+            if let Some(golden) = player_funds[Piece::Golden].checked_add(1) {
+                let mut bank = self.bank;
+                bank[Piece::Golden] -= 1;
+                player_funds[Piece::Golden] = golden;
+                self.set_bank(bank);
+                self.set_player_funds(self.player_turn, player_funds);
+            }
+        }
+    }
+
+    fn action_buy_production_card(
+        &mut self,
+        card_id: &CardId,
+        payment: Option<&bank::Funds>,
+    ) -> Result<BoardChange, ActionFail> {
+        let player = &self.players[self.player_turn];
+        let market_position = self.find_on_market(card_id);
+        let reserve_position = player
+            .reserved_cards
+            .iter()
+            .position(|card| &card.uid == card_id);
+
+        #[cfg(feature = "strongholds")]
+        if market_position.is_some() {
+            self.check_stronghold(card_id)?;
+        }
+
+        let card_data = match (market_position, reserve_position) {
+            (Some((tier, index)), _) => {
+                &self.cards_for_sale[&tier][index]
+                    .as_ref()
+                    .expect("Market slot should hold a card")
+                    .data
+            }
+            (None, Some(index)) => &player.reserved_cards[index].data,
+            (None, None) => {
+                return Err(ActionFail::InvalidBuyOperation(
+                    BuyOperationFail::CardNotFoundOnBoard,
+                ))
+            }
+        };
+
+        if card_data.ability == Some(CardAbility::CopyColor) && !self.has_color_to_copy() {
+            return Err(ActionFail::InvalidBuyOperation(
+                BuyOperationFail::NoColorToCopy,
+            ));
+        }
+
+        let used_coins = match payment {
+            Some(payment) => player.check_payment(card_data, *payment),
+            None => player.payment_plan(card_data),
+        }
+        .map_err(ActionFail::InvalidBuyOperation)?
+        .total();
+        let player_remaining_funds =
+            (player.funds - used_coins).expect("Player should have enough funds");
+        let bank = self
+            .bank
+            .checked_add(used_coins)
+            .ok_or(ActionFail::TooManyAtTheBank)?;
+        self.set_bank(bank);
+        self.set_player_funds(self.player_turn, player_remaining_funds);
+
+        let (card, change) = match (market_position, reserve_position) {
+            (Some((tier, index)), _) => {
+                let (card, slot) = self.take_from_market(tier, index);
+                (card, BoardChange::BoughtFromMarket(slot))
+            }
+            (None, Some(index)) => {
+                let card = self.players[self.player_turn].reserved_cards.remove(index);
+                self.position_hash ^= zobrist::reserve(self.player_turn, card.uid);
+                (card, BoardChange::BoughtFromReserve(index))
+            }
+            (None, None) => unreachable!("The card was found above"),
+        };
+        self.add_to_tableau(card);
+        #[cfg(feature = "trading-posts")]
+        {
+            self.bonus_piece_due = self.players[self.player_turn].powers.piece_after_buying;
+        }
+
+        Ok(change)
+    }
+
+    /// Gives a card to the player to act, waiting for their choice if its ability needs one.
+    fn add_to_tableau(&mut self, card: Identifiable<ProductionCard, CardId>) {
+        let pending_ability = card
+            .data
+            .ability
+            .filter(|ability| self.has_choice_for(*ability, &card.uid))
+            .map(|ability| PendingAbility {
+                card_id: card.uid,
+                ability,
+            });
+
+        self.position_hash ^= zobrist::tableau(self.player_turn, card.uid);
+        self.players[self.player_turn].production_cards.push(card);
+
+        self.pending_ability = pending_ability;
+        if pending_ability.is_some() {
+            self.set_action_needed(ActionType::ResolveAbility);
+        }
+    }
+
+    fn has_choice_for(&self, ability: CardAbility, card_id: &CardId) -> bool {
+        match ability {
+            CardAbility::DoubleBonus => false,
+            CardAbility::TakeLowerTierCard => self
+                .get_card_tier(card_id)
+                .and_then(ProductionTier::lower)
+                .is_some_and(|tier| self.iter_cards_for_sale(&tier).next().is_some()),
+            CardAbility::ReserveNoble => !self.nobles.is_empty(),
+            CardAbility::CopyColor => self.has_color_to_copy(),
+        }
+    }
+
+    fn has_color_to_copy(&self) -> bool {
+        self.get_who_is_playing_now()
+            .get_production()
+            .iter()
+            .any(|(piece, quantity)| self.pieces.is_color(piece) && quantity > 0)
+    }
+
+    fn action_resolve_ability(
+        &mut self,
+        choice: &AbilityChoice,
+    ) -> Result<BoardChange, ActionFail> {
+        let pending = self.pending_ability.ok_or(ActionFail::NoAbilityToResolve)?;
+
+        match (pending.ability, choice) {
+            (CardAbility::TakeLowerTierCard, AbilityChoice::TakeCard(card_id)) => {
+                let lower_tier = self
+                    .get_card_tier(&pending.card_id)
+                    .and_then(ProductionTier::lower);
+                let (tier, index) = self
+                    .find_on_market(card_id)
+                    .filter(|(tier, _)| Some(*tier) == lower_tier)
+                    .ok_or(ActionFail::InvalidAbilityChoice)?;
+                #[cfg(feature = "strongholds")]
+                self.check_stronghold(card_id)?;
+
+                self.finish_ability();
+                let (card, slot) = self.take_from_market(tier, index);
+                self.add_to_tableau(card);
+
+                Ok(BoardChange::TookFreeCard(slot))
+            }
+            (CardAbility::ReserveNoble, AbilityChoice::ReserveNoble(noble_id)) => {
+                let index = self
+                    .nobles
+                    .iter()
+                    .position(|noble| &noble.id == noble_id)
+                    .ok_or(ActionFail::InvalidAbilityChoice)?;
+
+                self.finish_ability();
+                let noble = self.nobles.remove(index);
+                self.position_hash ^=
+                    zobrist::noble(noble.id) ^ zobrist::reserved_noble(self.player_turn, noble.id);
+                self.players[self.player_turn].reserved_nobles.push(noble);
+
+                Ok(BoardChange::ReservedNoble(index))
+            }
+            (CardAbility::CopyColor, AbilityChoice::CopyColor(piece)) => {
+                let player = self.get_who_is_playing_now();
+                if !self.pieces.is_color(*piece) || player.get_production()[*piece] == 0 {
+                    return Err(ActionFail::InvalidAbilityChoice);
+                }
+                let index = player
+                    .production_cards
+                    .iter()
+                    .position(|card| card.uid == pending.card_id)
+                    .expect("The card with the ability should be owned");
+
+                self.finish_ability();
+                let card = &mut self.players[self.player_turn].production_cards[index];
+                let previous = mem::replace(&mut card.data.produces, *piece);
+                self.position_hash ^= zobrist::copied_color(card.uid, *piece);
+
+                Ok(BoardChange::CopiedColor { index, previous })
+            }
+            _ => Err(ActionFail::InvalidAbilityChoice),
+        }
+    }
+
+    fn finish_ability(&mut self) {
+        self.pending_ability = None;
+        self.set_action_needed(ActionType::Normal);
+    }
+
+    #[cfg(feature = "trading-posts")]
+    fn action_take_bonus_piece(&mut self, piece: &Piece) -> Result<BoardChange, ActionFail> {
+        if !self.bonus_piece_due {
+            return Err(ActionFail::NoBonusPieceToTake);
+        }
+        if !self.pieces.is_color(*piece) || self.bank[*piece] == 0 {
+            return Err(ActionFail::BonusPieceNotAvailable(*piece));
+        }
+
+        let mut bank = self.bank;
+        let mut player_funds = self.players[self.player_turn].funds;
+        bank[*piece] -= 1;
+        player_funds[*piece] += 1;
+        self.set_bank(bank);
+        self.set_player_funds(self.player_turn, player_funds);
+        self.bonus_piece_due = false;
+        self.set_action_needed(ActionType::Normal);
+
+        Ok(BoardChange::Nothing)
+    }
+
+    /// Whether the bank has a colored piece for the player to act, who holds less than 10.
+    #[cfg(feature = "trading-posts")]
+    fn can_take_bonus_piece(&self) -> bool {
+        self.get_who_is_playing_now().funds.total() < 10
+            && self
+                .bank
+                .iter()
+                .any(|(piece, quantity)| self.pieces.is_color(piece) && quantity > 0)
+    }
+
+    /// Gives the player to act every trading post they now qualify for, in track order.
+    #[cfg(feature = "trading-posts")]
+    fn claim_trading_posts(&mut self) {
+        let player = self.get_who_is_playing_now();
+        let production = player.get_production();
+        let mut powers = player.powers;
+
+        for post in self
+            .trading_posts
+            .iter()
+            .skip(usize::from(powers.posts_claimed))
+        {
+            if !post.can_be_claimed_with(&production) {
+                break;
+            }
+            powers = post.grant(powers);
+        }
+
+        if powers != player.powers {
+            self.set_player_powers(self.player_turn, powers);
+        }
+    }
+
+    fn action_pass_the_turn(&self) -> Result<BoardChange, ActionFail> {
+        if self.rules.pass_only_when_stuck && !self.is_stuck() {
+            return Err(ActionFail::PassingNotAllowed);
+        }
+
+        Ok(BoardChange::Nothing)
+    }
+
+    fn action_collect_pieces(
+        &mut self,
+        collect_pieces: &[Piece],
+        discard_pieces: &[Piece],
+    ) -> Result<BoardChange, ActionFail> {
+        let current_player = self.get_who_is_playing_now();
+
+        let collect_request = bank::CollectRequest::new(
+            self.bank,
+            current_player.funds,
+            collect_pieces.iter().copied(),
+            discard_pieces.iter().copied(),
+        );
+        #[cfg(feature = "trading-posts")]
+        let collect_request = collect_request.with_powers(&current_player.powers);
+        let result = bank::Funds::collect(collect_request).map_err(ActionFail::InvalidCollect)?;
+
+        self.set_bank(result.bank_funds);
+        self.set_player_funds(self.player_turn, result.player_funds);
+
+        Ok(BoardChange::Nothing)
+    }
+
+    fn action_reserve_card_from_deck(
+        &mut self,
+        tier: &ProductionTier,
+    ) -> Result<BoardChange, ActionFail> {
+        if self.players[self.player_turn].reserved_cards.len() >= MAXIMUM_RESERVED_CARDS {
+            return Err(ActionFail::InvalidReserve(
+                ReserveOperationFail::MaximumReservedCardsExceed,
+            ));
+        }
+
+        let card_drawn = self
+            .decks
+            .get_mut(tier)
+            .and_then(|deck| deck.pop())
+            .ok_or(ActionFail::CannotReserveFromEmptyDeck)?;
+
+        self.push_reserved_card(card_drawn);
+        self.take_golden_for_reserve();
+
+        Ok(BoardChange::ReservedFromDeck(*tier))
+    }
+
+    fn action_reserve_card(&mut self, card_id: &CardId) -> Result<BoardChange, ActionFail> {
+        let (tier, index) = self
+            .find_on_market(card_id)
+            .ok_or(ActionFail::InvalidReserve(
+                ReserveOperationFail::CardNotFound,
+            ))?;
+
+        #[cfg(feature = "strongholds")]
+        self.check_stronghold(card_id)?;
+
+        if self.players[self.player_turn].reserved_cards.len() >= MAXIMUM_RESERVED_CARDS {
+            return Err(ActionFail::InvalidReserve(
+                ReserveOperationFail::MaximumReservedCardsExceed,
+            ));
+        }
+
+        let (card, slot) = self.take_from_market(tier, index);
+        self.push_reserved_card(card);
+        self.take_golden_for_reserve();
+
+        Ok(BoardChange::ReservedFromMarket(slot))
+    }
+
+    fn action_select_noble(&mut self, noble_id: &NobleId) -> Result<BoardChange, ActionFail> {
+        let player = self.get_who_is_playing_now();
+        let production = player.get_production();
+
+        if let Some(index) = player
+            .reserved_nobles
+            .iter()
+            .position(|noble| &noble.id == noble_id)
+        {
+            if !player.reserved_nobles[index].is_attracted_by(&production) {
+                return Err(ActionFail::NobleNotEligible);
+            }
+
+            let noble = self.players[self.player_turn].reserved_nobles.remove(index);
+            self.position_hash ^= zobrist::reserved_noble(self.player_turn, noble.id)
+                ^ zobrist::player_noble(self.player_turn, noble.id);
+            self.players[self.player_turn].nobles.push(noble);
+            self.set_action_needed(ActionType::Normal);
+
+            return Ok(BoardChange::SelectedReservedNoble(index));
+        }
+
+        let index = self
+            .nobles
+            .iter()
+            .position(|noble| &noble.id == noble_id)
+            .ok_or(ActionFail::NobleNotFound)?;
+
+        if !self.nobles[index].is_attracted_by(&production) {
+            return Err(ActionFail::NobleNotEligible);
+        }
+
+        let noble = self.nobles.remove(index);
+        self.position_hash ^=
+            zobrist::noble(noble.id) ^ zobrist::player_noble(self.player_turn, noble.id);
+        self.players[self.player_turn].nobles.push(noble);
+        self.set_action_needed(ActionType::Normal);
+
+        Ok(BoardChange::SelectedNoble(index))
+    }
+
+    /// The player who must send the next action, pending steps such as selecting a noble
+    /// included.
+    pub fn player_to_act(&self) -> PlayerId {
+        self.get_who_is_playing_now().id
+    }
+
+    /// Same as `Board::do_action`, but rejects the action unless `player_id` is the player to act.
+    pub fn do_action_as(
+        board: Board,
+        player_id: PlayerId,
+        action: &Action,
+    ) -> Result<Board, ActionFail> {
+        if board.player_to_act() != player_id {
+            return Err(ActionFail::NotYourTurn);
+        }
+        Board::do_action(board, action)
+    }
+
+    /// Same as `Board::apply`, but rejects the action unless `player_id` is the player to act.
+    pub fn apply_as(
+        &mut self,
+        player_id: PlayerId,
+        action: &Action,
+    ) -> Result<UndoToken, ActionFail> {
+        if self.player_to_act() != player_id {
+            return Err(ActionFail::NotYourTurn);
+        }
+        self.apply(action)
+    }
+
+    pub fn do_action(board: Board, action: &Action) -> Result<Board, ActionFail> {
+        // Boards set up with the unchecked `Board::new` may break the invariants from the start,
+        // an action only has to keep a valid board valid.
+        #[cfg(debug_assertions)]
+        let was_valid = board.validate().is_ok();
+        let mut new_board_state = board;
+        new_board_state.apply(action)?;
+        #[cfg(debug_assertions)]
+        if was_valid {
+            assert_eq!(new_board_state.validate(), Ok(()));
+        }
+        Ok(new_board_state)
+    }
+
+    /// Applies the action in place, without cloning the board. Once the cards and nobles of the
+    /// players have been given room, by earlier actions or undone ones, it does not allocate.
+    ///
+    /// On failure the board is left untouched. On success the returned token can be given to
+    /// `Board::undo` to restore the previous state.
+    pub fn apply(&mut self, action: &Action) -> Result<UndoToken, ActionFail> {
+        if self.winner.is_some() || self.end_reason.is_some() {
+            return Err(ActionFail::GameIsOver);
+        }
+
+        match self.action_needed {
+            ActionType::Normal => match action {
+                Action::SelectNoble(_) => return Err(ActionFail::YouCannotSelectNobleNow),
+                Action::ResolveAbility(_) => return Err(ActionFail::NoAbilityToResolve),
+                #[cfg(feature = "trading-posts")]
+                Action::TakeBonusPiece(_) => return Err(ActionFail::NoBonusPieceToTake),
+                _ => {}
+            },
+            ActionType::SelectNoble => {
+                if let Action::SelectNoble(_) = action {
+                } else {
+                    return Err(ActionFail::YouNeedToSelectNoble);
+                }
+            }
+            ActionType::ResolveAbility => {
+                if let Action::ResolveAbility(_) = action {
+                } else {
+                    return Err(ActionFail::YouNeedToResolveAbility);
+                }
+            }
+            #[cfg(feature = "trading-posts")]
+            ActionType::TakeBonusPiece => {
+                if let Action::TakeBonusPiece(_) = action {
+                } else {
+                    return Err(ActionFail::YouNeedToTakeBonusPiece);
+                }
+            }
+        }
+
+        let mut undo_token = UndoToken {
+            player_turn: self.player_turn,
+            action_needed: self.action_needed,
+            round_type: self.round_type,
+            rounds_played: self.rounds_played,
+            consecutive_passes: self.consecutive_passes,
+            pending_ability: self.pending_ability,
+            #[cfg(feature = "trading-posts")]
+            bonus_piece_due: self.bonus_piece_due,
+            bank: self.bank,
+            #[cfg(feature = "trading-posts")]
+            player_powers: self.players[self.player_turn].powers,
+            player_funds: self.players[self.player_turn].funds,
+            position_hash: self.position_hash,
+            change: BoardChange::Nothing,
+        };
+
+        let mut has_selected_noble = false;
+        undo_token.change = match action {
+            Action::PassTheTurn => self.action_pass_the_turn()?,
+            Action::ReserveCardFromDeck(tier) => self.action_reserve_card_from_deck(tier)?,
+            Action::CollectPieces(collect_pieces, discard_pieces) => {
+                self.action_collect_pieces(collect_pieces, discard_pieces)?
+            }
+            Action::ReserveCardFromBoard(card_id) => self.action_reserve_card(card_id)?,
+            Action::BuyCard(card_id, payment) => {
+                self.action_buy_production_card(card_id, payment.as_ref())?
+            }
+            Action::SelectNoble(noble_id) => {
+                has_selected_noble = true;
+                self.action_select_noble(noble_id)?
+            }
+            Action::ResolveAbility(choice) => self.action_resolve_ability(choice)?,
+            #[cfg(feature = "trading-posts")]
+            Action::TakeBonusPiece(piece) => self.action_take_bonus_piece(piece)?,
+            #[cfg(feature = "strongholds")]
+            Action::PlaceStronghold(card_id) => self.action_place_stronghold(card_id)?,
+            #[cfg(feature = "strongholds")]
+            Action::RemoveStronghold(card_id) => self.action_remove_stronghold(card_id)?,
+        };
+
+        if let Action::PassTheTurn = action {
+            self.consecutive_passes += 1;
+        } else {
+            self.consecutive_passes = 0;
+        }
+
+        // The turn goes on until every card ability is resolved.
+        if self.action_needed == ActionType::ResolveAbility {
+            return Ok(undo_token);
+        }
+
+        #[cfg(feature = "trading-posts")]
+        {
+            if self.bonus_piece_due {
+                if self.can_take_bonus_piece() {
+                    self.set_action_needed(ActionType::TakeBonusPiece);
+                    return Ok(undo_token);
+                }
+                self.bonus_piece_due = false;
+            }
+
+            self.claim_trading_posts();
+        }
+
+        // Nobles visit at the end of the turn, whatever the action was, one per turn at most.
+        let can_select_noble = !has_selected_noble && self.can_select_noble();
+        if can_select_noble {
+            self.set_action_needed(ActionType::SelectNoble);
+        }
+
+        if self.has_some_player_passed_win_threshold() {
+            self.set_round_type(RoundType::LastRound);
+        }
+
+        if !can_select_noble {
+            if self.is_last_player_turn() {
+                self.rounds_played += 1;
+            }
+
+            if let Some(end_reason) = self.get_reason_to_end() {
+                self.winner = self.compute_winner();
+                self.end_reason = Some(end_reason);
+            }
+
+            self.set_player_turn(self.get_who_plays_next());
+        }
+
+        Ok(undo_token)
+    }
+
+    /// Reverts the action that produced `undo_token`.
+    pub fn undo(&mut self, undo_token: UndoToken) {
+        let seat = undo_token.player_turn;
+        match undo_token.change {
+            BoardChange::Nothing => {}
+            BoardChange::ReservedFromDeck(tier) => {
+                let card = self.players[seat]
+                    .reserved_cards
+                    .pop()
+                    .expect("The reserved card should still be in hand");
+                self.decks
+                    .get_mut(&tier)
+                    .expect("Deck should exist")
+                    .push(card);
+            }
+            BoardChange::ReservedFromMarket(slot) => {
+                let card = self.players[seat]
+                    .reserved_cards
+                    .pop()
+                    .expect("The reserved card should still be in hand");
+                self.put_back_on_market(slot, card);
+            }
+            BoardChange::BoughtFromMarket(slot) => {
+                let card = self.players[seat]
+                    .production_cards
+                    .pop()
+                    .expect("The bought card should still be owned");
+                self.put_back_on_market(slot, card);
+            }
+            BoardChange::BoughtFromReserve(index) => {
+                let card = self.players[seat]
+                    .production_cards
+                    .pop()
+                    .expect("The bought card should still be owned");
+                self.players[seat].reserved_cards.insert(index, card);
+            }
+            BoardChange::SelectedNoble(index) => {
+                let noble = self.players[seat]
+                    .nobles
+                    .pop()
+                    .expect("The selected noble should still be owned");
+                self.nobles.insert(index, noble);
+            }
+            BoardChange::SelectedReservedNoble(index) => {
+                let noble = self.players[seat]
+                    .nobles
+                    .pop()
+                    .expect("The selected noble should still be owned");
+                self.players[seat].reserved_nobles.insert(index, noble);
+            }
+            BoardChange::TookFreeCard(slot) => {
+                let card = self.players[seat]
+                    .production_cards
+                    .pop()
+                    .expect("The free card should still be owned");
+                self.put_back_on_market(slot, card);
+            }
+            BoardChange::ReservedNoble(index) => {
+                let noble = self.players[seat]
+                    .reserved_nobles
+                    .pop()
+                    .expect("The reserved noble should still be waiting");
+                self.nobles.insert(index, noble);
+            }
+            BoardChange::CopiedColor { index, previous } => {
+                self.players[seat].production_cards[index].data.produces = previous;
+            }
+            #[cfg(feature = "strongholds")]
+            BoardChange::PlacedStronghold => {
+                self.strongholds.pop();
+            }
+            #[cfg(feature = "strongholds")]
+            BoardChange::RemovedStronghold(index, stronghold) => {
+                self.restore_stronghold(index, stronghold);
+            }
+        }
+
+        self.bank = undo_token.bank;
+        self.players[seat].funds = undo_token.player_funds;
+        self.player_turn = seat;
+        self.action_needed = undo_token.action_needed;
+        self.round_type = undo_token.round_type;
+        // No action is applied once the game is over, so undoing one always resumes the game.
+        self.winner = None;
+        self.end_reason = None;
+        self.rounds_played = undo_token.rounds_played;
+        self.consecutive_passes = undo_token.consecutive_passes;
+        self.pending_ability = undo_token.pending_ability;
+        #[cfg(feature = "trading-posts")]
+        {
+            self.bonus_piece_due = undo_token.bonus_piece_due;
+            self.players[seat].powers = undo_token.player_powers;
+        }
+        self.position_hash = undo_token.position_hash;
+    }
+
+    /// Whether the player to act can neither collect, reserve nor buy, leaving passing as their
+    /// only move.
+    pub fn is_stuck(&self) -> bool {
+        let player = self.get_who_is_playing_now();
+
+        let can_collect = self
+            .bank
+            .iter()
+            .any(|(piece, quantity)| !piece.is_wildcard() && quantity > 0);
+
+        let can_reserve = player.reserved_cards.len() < MAXIMUM_RESERVED_CARDS
+            && self
+                .decks
+                .values()
+                .flatten()
+                .chain(self.cards_for_sale.values().flatten().flatten())
+                .next()
+                .is_some();
+
+        let can_buy = self
+            .cards_for_sale
+            .values()
+            .flatten()
+            .flatten()
+            .chain(player.reserved_cards.iter())
+            .any(|card| player.can_afford(&card.data));
+
+        #[cfg(feature = "strongholds")]
+        let can_reserve = can_reserve || self.can_place_stronghold();
+
+        !(can_collect || can_reserve || can_buy)
+    }
+
+    /// Checked at the end of each turn, once the player to act is done.
+    fn get_reason_to_end(&self) -> Option<EndReason> {
+        if self.is_last_player_turn() && self.round_type == RoundType::LastRound {
+            return Some(EndReason::WinningPoints);
+        }
+        if self
+            .rules
+            .max_rounds
+            .is_some_and(|max_rounds| self.rounds_played >= max_rounds)
+        {
+            return Some(EndReason::MaxRounds);
+        }
+        if self.rules.end_on_stalemate && self.consecutive_passes >= self.players.len() {
+            return Some(EndReason::Stalemate);
+        }
+        None
+    }
+
+    /// The nobles the player to act may choose from at the end of their turn, the ones they
+    /// reserved included.
+    pub fn eligible_nobles(&self) -> Vec<&Noble> {
+        let player = self.get_who_is_playing_now();
+        let production = player.get_production();
+
+        self.nobles
+            .iter()
+            .chain(player.reserved_nobles.iter())
+            .filter(|noble| noble.is_attracted_by(&production))
+            .collect()
+    }
+
+    fn can_select_noble(&self) -> bool {
+        let player = self.get_who_is_playing_now();
+        let production = player.get_production();
+
+        self.nobles
+            .iter()
+            .chain(player.reserved_nobles.iter())
+            .any(|noble| noble.is_attracted_by(&production))
+    }
+
+    fn has_some_player_passed_win_threshold(&self) -> bool {
+        if self.rules.disable_winning_points {
+            return false;
+        }
+        for p in &self.players {
+            if p.total_victory_points() >= WINNING_POINTS_THRESHOLD {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether the turn goes back to the starting player next, so that everyone played as many
+    /// turns.
+    fn is_last_player_turn(&self) -> bool {
+        self.get_who_plays_next() == self.starting_player
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+
+    use bank::Funds;
+    use player::{Player, PlayerId};
+    use production_card::CardId;
+
+    use super::*;
+    #[cfg(feature = "trading-posts")]
+    use crate::trading_posts::PostPower;
+
+    fn get_default_production_card_cost() -> Funds {
+        bank::Funds::new(1, 1, 0, 0, 0, 0)
+    }
+
+    fn get_production_card(card_id: CardId) -> Identifiable<ProductionCard, CardId> {
+        let cost = get_default_production_card_cost();
+        let card = production_card::ProductionCard::new(cost, Piece::Red, Some(1));
+        Identifiable::new(card, card_id)
+    }
+
+    fn get_initial_player(id: PlayerId) -> player::Player {
+        let funds = bank::Funds::new(0, 0, 0, 0, 0, 0);
+        player::Player::new(id, funds, vec![], vec![])
+    }
+
+    fn get_player_with_winning_points(id: PlayerId, card_id: CardId, winning_points: u8) -> Player {
+        let player = get_initial_player(id);
+        let card = get_production_card(card_id);
+        let card = Identifiable::new(
+            ProductionCard {
+                victory_points: Some(winning_points),
+                ..card.data
+            },
+            card_id,
+        );
+        Player {
+            production_cards: vec![card],
+            ..player
+        }
+    }
+
+    fn get_initial_bank() -> bank::Funds {
+        bank::Funds::new(7, 7, 7, 7, 7, 5)
+    }
+
+    fn get_default_board() -> Board {
+        let p1 = get_initial_player(PlayerId::new(1));
+        let p2 = get_initial_player(PlayerId::new(2));
+        let p3 = get_initial_player(PlayerId::new(3));
+        get_board(vec![p1, p2, p3], vec![])
+    }
+
+    #[allow(deprecated)]
+    fn get_board(players: Vec<Player>, nobles: Vec<Noble>) -> Board {
+        let bank_funds = get_initial_bank();
+        let decks = HashMap::from([(
+            ProductionTier::One,
+            vec![
+                get_production_card(CardId::new(5)),
+                get_production_card(CardId::new(4)),
+                get_production_card(CardId::new(3)),
+                get_production_card(CardId::new(2)),
+                get_production_card(CardId::new(1)),
+            ],
+        )]);
+        Board::new(players, bank_funds, decks, nobles)
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn auto_draw_necessary_cards() {
+        let board = get_default_board();
+        assert_eq!(
+            board
+                .cards_for_sale
+                .get(&ProductionTier::One)
+                .unwrap()
+                .len(),
+            4
+        );
+        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
+
+        let p1 = get_initial_player(PlayerId::new(1));
+        let p2 = get_initial_player(PlayerId::new(2));
+        let p3 = get_initial_player(PlayerId::new(3));
+        let bank_funds = get_initial_bank();
+        let decks = HashMap::from([(
+            ProductionTier::One,
+            vec![
+                get_production_card(CardId::new(5)),
+                get_production_card(CardId::new(4)),
+            ],
+        )]);
+
+        let board = Board::new(vec![p1, p2, p3], bank_funds, decks, vec![]);
+        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 0);
+        assert_eq!(
+            board
+                .cards_for_sale
+                .get(&ProductionTier::One)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn can_pass_the_turn() {
+        let board = get_default_board();
+        let action = &Action::PassTheTurn;
+
+        let current_player = 0;
+
+        let result = Board::do_action(board, action);
+        assert!(result.is_ok());
+
+        let board_after = result.unwrap();
+        assert_ne!(current_player, board_after.player_turn);
+    }
+
+    #[test]
+    fn turn_cycles_back_to_first_person() {
+        let board = get_default_board();
+        let action = &Action::PassTheTurn;
+
+        let result = Board::do_action(board, action);
+        assert!(result.is_ok());
+
+        let board = result.unwrap();
+        assert_eq!(1, board.player_turn);
+
+        let result = Board::do_action(board, action);
+        assert!(result.is_ok());
+
+        let board = result.unwrap();
+        assert_eq!(2, board.player_turn);
+
+        // Cycle back to first player
+        let result = Board::do_action(board, action);
+        assert!(result.is_ok());
+
+        let board = result.unwrap();
+        assert_eq!(0, board.player_turn);
+    }
+
+    #[test]
+    fn can_collect_up_to_three_pieces() {
+        let board = get_default_board();
+        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Blue, Piece::White], vec![]);
+
+        let result = Board::do_action(board, action);
+        assert!(result.is_ok());
+
+        let result = result.unwrap();
+        let red = result.bank[Piece::Red];
+        let blue = result.bank[Piece::Blue];
+        let white = result.bank[Piece::White];
+        assert_eq!(red, 6);
+        assert_eq!(blue, 6);
+        assert_eq!(white, 6);
+
+        let expected_player_funds = bank::Funds::new(1, 0, 1, 0, 1, 0);
+        let first_player = result.players.first();
+        assert_eq!(first_player.unwrap().funds, expected_player_funds);
+
+        let board = get_default_board();
+        let action = &Action::CollectPieces(
+            vec![Piece::Red, Piece::Blue, Piece::White, Piece::Red],
+            vec![],
+        );
+
+        let result = Board::do_action(board, action).unwrap_err();
+        assert_eq!(
+            result,
+            ActionFail::InvalidCollect(CollectError::CannotCollectMoreThanThree)
+        );
+    }
+
+    #[test]
+    fn can_reserve_card_from_deck() {
+        let board = get_default_board();
+
+        let first_player = board.players.first().unwrap();
+
+        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
+        assert_eq!(first_player.reserved_cards.len(), 0);
+        assert_eq!(board.player_turn, 0);
+
+        let action = &Action::ReserveCardFromDeck(ProductionTier::One);
+
+        let board = Board::do_action(board, action).unwrap();
+
+        // Assert that p1 has a production card reserved
+        let first_player = board.players.first().unwrap();
+
+        assert_eq!(first_player.reserved_cards.len(), 1);
+
+        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 0);
+
+        // Make sure the turn is passed to the next player
+        assert_eq!(board.player_turn, 1);
+        assert_eq!(board.round_type, RoundType::Normal);
+
+        // Assert that we cannot reserve from an empty deck
+        let action = &Action::ReserveCardFromDeck(ProductionTier::One);
+        let result = Board::do_action(board.clone(), action).unwrap_err();
+        assert_eq!(result, ActionFail::CannotReserveFromEmptyDeck);
+    }
+
+    #[test]
+    fn can_reserve_card_from_board() {
+        let board = get_default_board();
+
+        let first_player = board.players.first().unwrap();
+
+        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
+        assert_eq!(first_player.reserved_cards.len(), 0);
+        assert_eq!(first_player.funds[Piece::Golden], 0);
+        assert_eq!(board.player_turn, 0);
+
+        let action = &Action::ReserveCardFromBoard(CardId::new(1));
+
+        let result = Board::do_action(board, action).unwrap();
+
+        let player_one = result.players.first().unwrap();
+
+        assert_eq!(player_one.reserved_cards.len(), 1);
+        assert_eq!(player_one.funds[Piece::Golden], 1);
+        assert_eq!(result.player_turn, 1);
+
+        let player_reserved_card = player_one.reserved_cards.first().unwrap();
+
+        let expected_card = get_production_card(CardId::new(1));
+
+        assert_eq!(player_reserved_card.uid, expected_card.uid);
+        assert_eq!(player_reserved_card.data, expected_card.data);
+    }
+
+    #[test]
+    fn cannot_reserve_card_that_does_not_exist() {
+        let board = get_default_board();
+        let action = &Action::ReserveCardFromBoard(CardId::new(255));
+        let result = Board::do_action(board, action).unwrap_err();
+        assert_eq!(
+            result,
+            ActionFail::InvalidReserve(ReserveOperationFail::CardNotFound)
+        );
+    }
+
+    #[test]
+    fn can_buy_production_card() {
+        let board = get_default_board();
+        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
+        let action_pass = &Action::PassTheTurn;
+
+        assert_eq!(board.bank, Funds::new(7, 7, 7, 7, 7, 5));
+
+        let board = Board::do_action(board, action).unwrap();
+        assert_eq!(board.bank, Funds::new(6, 6, 6, 7, 7, 5));
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+
+        let board = Board::do_action(board, action).unwrap();
+        assert_eq!(board.bank, Funds::new(5, 5, 5, 7, 7, 5));
+
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.production_cards.len(), 0);
+        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
+        assert_eq!(
+            board
+                .cards_for_sale
+                .get(&ProductionTier::One)
+                .unwrap()
+                .len(),
+            4
+        );
+        let expected_player_funds = bank::Funds::new(2, 2, 2, 0, 0, 0);
+        assert_eq!(player_one.funds, expected_player_funds);
+
+        let action = &Action::BuyCard(CardId::new(1), None);
+        let board = Board::do_action(board, action).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.production_cards.len(), 1);
+
+        // Make sure that the money was transfered, from the player to the bank
+        let expected_player_funds = bank::Funds::new(1, 1, 2, 0, 0, 0);
+        let expected_bank_funds = bank::Funds::new(6, 6, 5, 7, 7, 5);
+        assert_eq!(player_one.funds, expected_player_funds);
+        assert_eq!(board.bank, expected_bank_funds);
+
+        // Makes sure a new card was drawn
+        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 0);
+        assert_eq!(
+            board
+                .cards_for_sale
+                .get(&ProductionTier::One)
+                .unwrap()
+                .len(),
+            4
+        );
+
+        // Makes sure the card is not on sale anymore
+        let action_fail = Board::do_action(board.clone(), action).unwrap_err();
+        assert_eq!(
+            action_fail,
+            ActionFail::InvalidBuyOperation(BuyOperationFail::CardNotFoundOnBoard)
+        );
+
+        // Make sure you used the card instead of the coins
+        // Using 1 production card (red), the player red coin should is not reduced, only 1 green
+
+        let action = &Action::BuyCard(CardId::new(2), None);
+        let board = Board::do_action(board, action).unwrap();
+
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.production_cards.len(), 2);
+
+        let expected_player_funds = bank::Funds::new(1, 0, 2, 0, 0, 0);
+        let expected_bank_funds = bank::Funds::new(6, 7, 5, 7, 7, 5);
+        assert_eq!(player_one.funds, expected_player_funds);
+        assert_eq!(board.bank, expected_bank_funds);
+    }
+
+    #[test]
+    fn cannot_buy_production_card_if_there_is_no_pieces() {
+        let board = get_default_board();
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.production_cards.len(), 0);
+
+        let action = &Action::BuyCard(CardId::new(1), None);
+
+        let result = Board::do_action(board, action).unwrap_err();
+        let expected_funds = bank::Funds::new(1, 1, 0, 0, 0, 0);
+        assert_eq!(
+            result,
+            ActionFail::InvalidBuyOperation(BuyOperationFail::NotEnoughFunds(expected_funds))
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn can_buy_card_using_golden_and_cards_production() {
+        let funds = bank::Funds::new(1, 1, 0, 0, 0, 2);
+        let red_production = production_card::ProductionCard::new(
+            get_default_production_card_cost(),
+            Piece::Red,
+            Some(1),
+        );
+        let red_card = Identifiable::new(red_production, CardId::new(100));
+        let p1 = player::Player::new(PlayerId::new(1), funds, vec![red_card], vec![]);
+        let p2 = get_initial_player(PlayerId::new(2));
+        let p3 = get_initial_player(PlayerId::new(3));
+        let bank_funds = get_initial_bank();
+
+        let blue_production = production_card::ProductionCard::new(
+            bank::Funds::new(3, 1, 0, 0, 0, 0),
+            Piece::Blue,
+            Some(1),
+        );
+        let card_to_buy = Identifiable::new(blue_production, CardId::new(101));
+
+        let decks = HashMap::from([(
+            ProductionTier::One,
+            vec![
+                get_production_card(CardId::new(5)),
+                get_production_card(CardId::new(4)),
+                get_production_card(CardId::new(3)),
+                get_production_card(CardId::new(2)),
+                get_production_card(CardId::new(1)),
+                card_to_buy,
+            ],
+        )]);
+
+        let board = Board::new(vec![p1, p2, p3], bank_funds, decks, vec![]);
+
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.production_cards.len(), 1);
+
+        let action = &Action::BuyCard(CardId::new(101), None);
+        let board = Board::do_action(board, action).unwrap();
+
+        let player_one = board.players.first().unwrap();
+        let player_expected_funds = Funds::new(0, 0, 0, 0, 0, 1);
+        assert_eq!(player_one.production_cards.len(), 2);
+        assert_eq!(player_one.funds, player_expected_funds);
+    }
+
+    #[test]
+    fn cannot_pay_into_a_full_bank() {
+        let p1 = Player::new(
+            PlayerId::new(1),
+            Funds::new(1, 1, 0, 0, 0, 0),
+            vec![],
+            vec![],
+        );
+        let mut board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![]);
+        board.set_bank(Funds::new(u8::MAX, 7, 7, 7, 7, 5));
+        let before = board.clone();
+
+        let result = board.apply(&Action::BuyCard(CardId::new(1), None));
+        assert_eq!(result.unwrap_err(), ActionFail::TooManyAtTheBank);
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn can_select_a_noble_only_after_buying() {
+        let noble_to_select = Noble::new(NobleId::new(1), bank::Funds::new(1, 0, 0, 0, 0, 0));
+        let second_noble = Noble::new(NobleId::new(2), bank::Funds::new(1, 0, 0, 0, 0, 0));
+
+        let players = vec![
+            get_initial_player(PlayerId::new(1)),
+            get_initial_player(PlayerId::new(2)),
+            get_initial_player(PlayerId::new(3)),
+        ];
+        let board = get_board(players, vec![noble_to_select.clone(), second_noble]);
+
+        // Make sure you can't select noble at any time
+        let action = &Action::SelectNoble(NobleId::new(1));
+        let action_fail = Board::do_action(board.clone(), action).unwrap_err();
+        assert_eq!(action_fail, ActionFail::YouCannotSelectNobleNow);
+
+        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
+        let action_pass = &Action::PassTheTurn;
+
+        let board = Board::do_action(board, action).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.production_cards.len(), 0);
+        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
+        assert_eq!(
+            board
+                .cards_for_sale
+                .get(&ProductionTier::One)
+                .unwrap()
+                .len(),
+            4
+        );
+
+        let action = &Action::BuyCard(CardId::new(1), None);
+
+        assert_eq!(board.action_needed, ActionType::Normal);
+        let board = Board::do_action(board, action).unwrap();
+        // Assert that the player is still playing
+        assert_eq!(board.player_turn, 0);
+        // Assert that the action needed is special: To select a noble
+        assert_eq!(board.action_needed, ActionType::SelectNoble);
+
+        // And that we can't do other actions
+        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
+        let action_fail = Board::do_action(board.clone(), action).unwrap_err();
+
+        assert_eq!(action_fail, ActionFail::YouNeedToSelectNoble);
+
+        // Make sure we still dont have any nobles
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.nobles.len(), 0);
+
+        // Make sure we can't select inexistent noble
+        let action = &Action::SelectNoble(NobleId::new(255));
+        let select_err = Board::do_action(board.clone(), action).unwrap_err();
+        assert_eq!(select_err, ActionFail::NobleNotFound);
+
+        // Assert that we can select noble
+        let action = &Action::SelectNoble(NobleId::new(1));
+        let board = Board::do_action(board, action).unwrap();
+
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.nobles.len(), 1);
+        assert_eq!(player_one.nobles.first().unwrap(), &noble_to_select);
+
+        // Assert that we cannot select noble again (even if the board would normally allow)
+        assert_eq!(board.action_needed, ActionType::Normal);
+        assert_eq!(board.player_turn, 1);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn end_round_triggered_after_hitting_15_points() {
+        let player_one = get_initial_player(PlayerId::new(1));
+        let player_one = player::Player {
+            funds: bank::Funds::new(3, 3, 2, 2, 0, 0),
+            production_cards: vec![
+                get_production_card(CardId::new(100)),
+                get_production_card(CardId::new(101)),
+                get_production_card(CardId::new(102)),
+                get_production_card(CardId::new(103)),
+                get_production_card(CardId::new(104)),
+                get_production_card(CardId::new(105)),
+                get_production_card(CardId::new(106)),
+                get_production_card(CardId::new(107)),
+                get_production_card(CardId::new(108)),
+                get_production_card(CardId::new(109)),
+                get_production_card(CardId::new(110)),
+                get_production_card(CardId::new(111)),
+            ],
+            ..player_one
+        };
+        let cost = bank::Funds::new(1, 1, 1, 0, 0, 0);
+        let card_to_buy = production_card::ProductionCard::new(cost, Piece::Blue, Some(3));
+        let card_to_buy = Identifiable::new(card_to_buy, CardId::new(112));
+
+        let decks = HashMap::from([(ProductionTier::One, vec![card_to_buy])]);
+        let board = Board::new(
+            vec![player_one, get_initial_player(PlayerId::new(2))],
+            get_initial_bank(),
+            decks,
+            vec![],
+        );
+
+        // assert that the EndGame was not triggered (to be created)
+        assert_eq!(board.round_type, RoundType::Normal);
+
+        // Buy production card 112
+        let action = Action::BuyCard(CardId::new(112), None);
+        let board = Board::do_action(board, &action).unwrap();
+
+        // assert that the EndGame was triggered
+        assert_eq!(board.round_type, RoundType::LastRound);
+        assert_eq!(board.winner, None);
+
+        // Pass the 2nd player turn, ending the game
+        let action = Action::PassTheTurn;
+        let board = Board::do_action(board, &action).unwrap();
+        assert_eq!(board.winner.unwrap(), Winner::Winner(PlayerId::new(1)));
+    }
+
+    #[test]
+    fn correctly_get_winner() {
+        let player_one = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 16);
+        let player_two = get_player_with_winning_points(PlayerId::new(2), CardId::new(101), 10);
+        let player_three = get_player_with_winning_points(PlayerId::new(3), CardId::new(102), 17);
+        let board = Board {
+            round_type: RoundType::LastRound,
+            ..get_board(vec![player_one, player_two, player_three.clone()], vec![])
+        };
+        let action_pass = &Action::PassTheTurn;
+
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+
+        assert_eq!(board.winner.unwrap(), Winner::Winner(player_three.id));
+    }
+
+    #[test]
+    fn correctly_get_winner_on_draw_in_points() {
+        let player_one = get_player_with_winning_points(PlayerId::new(2), CardId::new(101), 16);
+        let player_two = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 15);
+        let mut player_two_cards = player_two.production_cards.clone();
+        player_two_cards.push(get_production_card(CardId::new(103)));
+
+        // Player two now has 16, making it a draw with player one, but also has 2 production cards
+        let player_two = Player {
+            production_cards: player_two_cards,
+            ..player_two
+        };
+        let player_three = get_player_with_winning_points(PlayerId::new(3), CardId::new(102), 15);
+        let board = Board {
+            round_type: RoundType::LastRound,
+            ..get_board(vec![player_one.clone(), player_two, player_three], vec![])
+        };
+        let action_pass = &Action::PassTheTurn;
+
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+
+        // player one wins because he has the least amount of cards (game rule)
+        assert_eq!(board.winner.unwrap(), Winner::Winner(player_one.id));
+    }
+
+    #[test]
+    fn correctly_get_winners_on_draw() {
+        // This is *not* described in the original game rules: What if there is a draw in points, and amount of cards?
+        // We assume that the players got a draw.
+
+        let player_one = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 16);
+        let player_two = get_player_with_winning_points(PlayerId::new(2), CardId::new(101), 17);
+        let player_three = get_player_with_winning_points(PlayerId::new(3), CardId::new(102), 17);
+        let board = Board {
+            round_type: RoundType::LastRound,
+            ..get_board(
+                vec![player_one, player_two.clone(), player_three.clone()],
+                vec![],
+            )
+        };
+        let action_pass = &Action::PassTheTurn;
+
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, action_pass).unwrap();
+
+        assert_eq!(
+            board.winner.unwrap(),
+            Winner::Draw(vec![player_two.id, player_three.id])
+        );
+    }
+
+    #[test]
+    fn undo_takes_back_a_draw() {
+        let player_one = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 16);
+        let player_two = get_player_with_winning_points(PlayerId::new(2), CardId::new(101), 16);
+        let mut board = Board {
+            round_type: RoundType::LastRound,
+            ..get_board(vec![player_one, player_two], vec![])
+        };
+        let before = board.clone();
+
+        let first_pass = board.apply(&Action::PassTheTurn).unwrap();
+        let second_pass = board.apply(&Action::PassTheTurn).unwrap();
+        assert!(matches!(board.winner, Some(Winner::Draw(_))));
+
+        board.undo(second_pass);
+        assert_eq!(board.winner, None);
+        board.undo(first_pass);
+        assert_same_state(&board, &before);
+    }
+
+    fn assert_same_state(board: &Board, expected: &Board) {
+        assert_eq!(board.players, expected.players);
+        assert_eq!(board.player_turn, expected.player_turn);
+        assert_eq!(board.bank, expected.bank);
+        assert_eq!(board.decks, expected.decks);
+        assert_eq!(board.cards_for_sale, expected.cards_for_sale);
+        assert_eq!(board.nobles, expected.nobles);
+        assert_eq!(board.action_needed, expected.action_needed);
+        assert_eq!(board.round_type, expected.round_type);
+        assert_eq!(board.winner, expected.winner);
+        assert_eq!(board.end_reason, expected.end_reason);
+        assert_eq!(board.rounds_played, expected.rounds_played);
+        assert_eq!(board.consecutive_passes, expected.consecutive_passes);
+        assert_eq!(board.pending_ability, expected.pending_ability);
+        #[cfg(feature = "trading-posts")]
+        assert_eq!(board.bonus_piece_due, expected.bonus_piece_due);
+        assert_eq!(board.position_hash(), expected.position_hash());
+    }
+
+    #[allow(deprecated)]
+    fn get_board_with_two_tiers() -> Board {
+        let p1 = player::Player::new(
+            PlayerId::new(1),
+            bank::Funds::new(2, 2, 0, 0, 0, 0),
+            vec![],
+            vec![],
+        );
+        let p2 = get_initial_player(PlayerId::new(2));
+        let decks = HashMap::from([
+            (
+                ProductionTier::One,
+                vec![
+                    get_production_card(CardId::new(8)),
+                    get_production_card(CardId::new(7)),
+                    get_production_card(CardId::new(6)),
+                    get_production_card(CardId::new(5)),
+                    get_production_card(CardId::new(4)),
+                    get_production_card(CardId::new(3)),
+                    get_production_card(CardId::new(2)),
+                    get_production_card(CardId::new(1)),
+                ],
+            ),
+            (
+                ProductionTier::Two,
+                vec![
+                    get_production_card(CardId::new(15)),
+                    get_production_card(CardId::new(14)),
+                    get_production_card(CardId::new(13)),
+                    get_production_card(CardId::new(12)),
+                    get_production_card(CardId::new(11)),
+                ],
+            ),
+        ]);
+        Board::new(vec![p1, p2], get_initial_bank(), decks, vec![])
+    }
+
+    #[test]
+    fn undo_restores_the_board_after_every_action() {
+        let mut board = get_board_with_two_tiers();
+        let actions = [
+            Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]),
+            Action::ReserveCardFromDeck(ProductionTier::Two),
+            Action::ReserveCardFromBoard(CardId::new(1)),
+            Action::ReserveCardFromBoard(CardId::new(11)),
+            Action::BuyCard(CardId::new(2), None),
+            Action::BuyCard(CardId::new(11), None),
+            Action::PassTheTurn,
+        ];
+
+        let mut history = vec![];
+        for action in &actions {
+            let before = board.clone();
+            let undo_token = board.apply(action).unwrap();
+            history.push((before, undo_token));
+        }
+
+        while let Some((before, undo_token)) = history.pop() {
+            board.undo(undo_token);
+            assert_same_state(&board, &before);
+        }
+    }
+
+    #[test]
+    fn failed_action_leaves_the_board_untouched() {
+        let mut board = get_default_board();
+        let before = board.clone();
+
+        let action = &Action::BuyCard(CardId::new(1), None);
+        assert!(board.apply(action).is_err());
+        assert_same_state(&board, &before);
+    }
+
+    #[test]
+    fn buying_only_refills_the_slot_of_the_bought_card() {
+        let board = get_board_with_two_tiers();
+        let tier_one_market = board.cards_for_sale[&ProductionTier::One].clone();
+        let tier_two_market = board.cards_for_sale[&ProductionTier::Two].clone();
+
+        let action = &Action::BuyCard(CardId::new(2), None);
+        let board = Board::do_action(board, action).unwrap();
+
+        let market = &board.cards_for_sale[&ProductionTier::One];
+        assert_eq!(market.len(), MARKET_SLOTS_PER_TIER);
+        assert_eq!(market[0], tier_one_market[0]);
+        assert_eq!(market[1].as_ref().unwrap().uid, CardId::new(5));
+        assert_eq!(market[2..], tier_one_market[2..]);
+        assert_eq!(board.cards_for_sale[&ProductionTier::Two], tier_two_market);
+        assert_eq!(board.decks[&ProductionTier::Two].len(), 1);
+    }
+
+    #[test]
+    fn reserving_from_the_board_takes_the_card_off_the_market() {
+        let board = get_default_board();
+
+        let action = &Action::ReserveCardFromBoard(CardId::new(1));
+        let board = Board::do_action(board, action).unwrap();
+
+        assert_eq!(board.get_card_from_board(&CardId::new(1)), None);
+        assert_eq!(board.iter_cards_for_sale(&ProductionTier::One).count(), 4);
+        assert_eq!(board.decks[&ProductionTier::One].len(), 0);
+    }
+
+    #[test]
+    fn cards_keep_their_slot_once_the_deck_runs_out() {
+        let mut board = get_default_board();
+        board
+            .apply(&Action::ReserveCardFromBoard(CardId::new(1)))
+            .unwrap();
+        let before = board.clone();
+
+        let undo_token = board
+            .apply(&Action::ReserveCardFromBoard(CardId::new(3)))
+            .unwrap();
+
+        let slots: Vec<_> = board
+            .get_market_slots(&ProductionTier::One)
+            .iter()
+            .map(|card| card.map(|card| card.uid))
+            .collect();
+        assert_eq!(
+            slots,
+            vec![
+                Some(CardId::new(5)),
+                Some(CardId::new(2)),
+                None,
+                Some(CardId::new(4))
+            ]
+        );
+        assert_eq!(board.validate(), Ok(()));
+
+        board.undo(undo_token);
+        assert_same_state(&board, &before);
+        assert_eq!(
+            board.get_market_slots(&ProductionTier::One)[2].unwrap().uid,
+            CardId::new(3)
+        );
+    }
+
+    #[test]
+    fn can_buy_a_reserved_card() {
+        let board = get_board_with_two_tiers();
+
+        let action = &Action::ReserveCardFromBoard(CardId::new(1));
+        let board = Board::do_action(board, action).unwrap();
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+
+        let action = &Action::BuyCard(CardId::new(1), None);
+        let board = Board::do_action(board, action).unwrap();
+
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.reserved_cards.len(), 0);
+        assert_eq!(player_one.production_cards.len(), 1);
+        assert_eq!(player_one.production_cards[0].uid, CardId::new(1));
+        assert_eq!(player_one.funds, bank::Funds::new(1, 1, 0, 0, 0, 1));
+    }
+
+    #[test]
+    fn reserving_from_deck_gives_golden() {
+        let mut board = get_board_with_two_tiers();
+        let golden_in_bank = board.bank[Piece::Golden];
+
+        board
+            .apply(&Action::ReserveCardFromDeck(ProductionTier::One))
+            .unwrap();
+
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.reserved_cards.len(), 1);
+        assert_eq!(player_one.funds[Piece::Golden], 1);
+        assert_eq!(board.bank[Piece::Golden], golden_in_bank - 1);
+    }
+
+    #[test]
+    fn reserving_from_deck_respects_the_limit() {
+        let mut board = get_board_with_two_tiers();
+
+        for _ in 0..MAXIMUM_RESERVED_CARDS {
+            board
+                .apply(&Action::ReserveCardFromDeck(ProductionTier::One))
+                .unwrap();
+            board.apply(&Action::PassTheTurn).unwrap();
+        }
+        let player_one = board.players.first().unwrap();
+        assert_eq!(player_one.reserved_cards.len(), MAXIMUM_RESERVED_CARDS);
+
+        let result = board.apply(&Action::ReserveCardFromDeck(ProductionTier::Two));
+        assert_eq!(
+            result.unwrap_err(),
+            ActionFail::InvalidReserve(ReserveOperationFail::MaximumReservedCardsExceed)
+        );
+    }
+
+    fn play(board: Board, actions: &[Action]) -> Board {
+        actions.iter().fold(board, |board, action| {
+            let board = Board::do_action(board, action).unwrap();
+            assert_eq!(board.position_hash(), board.compute_position_hash());
+            board
+        })
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn position_hash_is_kept_up_to_date() {
+        let board = get_board_with_two_tiers();
+        let board = play(
+            board,
+            &[
+                Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]),
+                Action::ReserveCardFromDeck(ProductionTier::Two),
+                Action::ReserveCardFromBoard(CardId::new(1)),
+                Action::ReserveCardFromBoard(CardId::new(11)),
+                Action::BuyCard(CardId::new(2), None),
+                Action::BuyCard(CardId::new(11), None),
+            ],
+        );
+
+        let noble = Noble::new(NobleId::new(1), bank::Funds::new(1, 0, 0, 0, 0, 0));
+        let board = Board::new(
+            board.players.clone(),
+            board.bank,
+            HashMap::from([(
+                ProductionTier::One,
+                vec![get_production_card(CardId::new(20))],
+            )]),
+            vec![noble],
+        );
+        let board = play(
+            board,
+            &[
+                Action::BuyCard(CardId::new(20), None),
+                Action::SelectNoble(NobleId::new(1)),
+            ],
+        );
+        assert_eq!(board.players[0].nobles.len(), 1);
+    }
+
+    #[test]
+    fn transpositions_have_the_same_hash() {
+        let board = play(
+            get_default_board(),
+            &[
+                Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]),
+                Action::CollectPieces(vec![Piece::White, Piece::Brown], vec![]),
+                Action::PassTheTurn,
+                Action::CollectPieces(vec![Piece::White, Piece::Brown, Piece::Green], vec![]),
+            ],
+        );
+        let other_board = play(
+            get_default_board(),
+            &[
+                Action::CollectPieces(vec![Piece::White, Piece::Brown, Piece::Green], vec![]),
+                Action::CollectPieces(vec![Piece::White, Piece::Brown], vec![]),
+                Action::PassTheTurn,
+                Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]),
+            ],
+        );
+
+        assert_eq!(board.position_hash(), other_board.position_hash());
+        assert_eq!(board, other_board);
+
+        let board = play(
+            get_default_board(),
+            &[
+                Action::ReserveCardFromBoard(CardId::new(1)),
+                Action::PassTheTurn,
+                Action::PassTheTurn,
+                Action::ReserveCardFromBoard(CardId::new(2)),
+            ],
+        );
+        let other_board = play(
+            get_default_board(),
+            &[
+                Action::ReserveCardFromBoard(CardId::new(2)),
+                Action::PassTheTurn,
+                Action::PassTheTurn,
+                Action::ReserveCardFromBoard(CardId::new(1)),
+            ],
+        );
+
+        assert_eq!(board.position_hash(), other_board.position_hash());
+        assert_eq!(board, other_board);
+    }
+
+    #[test]
+    fn different_positions_are_not_equal() {
+        let board = play(
+            get_default_board(),
+            &[Action::CollectPieces(
+                vec![Piece::Red, Piece::Green],
+                vec![],
+            )],
+        );
+        let other_board = play(
+            get_default_board(),
+            &[Action::CollectPieces(vec![Piece::Red, Piece::Blue], vec![])],
+        );
+        assert_ne!(board.position_hash(), other_board.position_hash());
+        assert_ne!(board, other_board);
+
+        // Same tokens, but it is not the same player's turn
+        let other_board = play(
+            get_default_board(),
+            &[
+                Action::CollectPieces(vec![Piece::Red, Piece::Green], vec![]),
+                Action::PassTheTurn,
+            ],
+        );
+        assert_ne!(board.position_hash(), other_board.position_hash());
+        assert_ne!(board, other_board);
+    }
+
+    #[test]
+    fn position_hash_survives_serialization() {
+        let board = play(
+            get_default_board(),
+            &[Action::ReserveCardFromBoard(CardId::new(3))],
+        );
+
+        let json = serde_json::to_string(&board).unwrap();
+        let loaded: Board = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.position_hash(), board.position_hash());
+        assert_eq!(loaded, board);
+    }
+
+    fn get_board_with_four_tiers() -> Board {
+        let deck = |ids: std::ops::RangeInclusive<u32>| {
+            ids.rev()
+                .map(|id| get_production_card(CardId::new(id)))
+                .collect()
+        };
+        let tier_four = ProductionTier::new(4);
+        Board::builder()
+            .players(vec![
+                get_initial_player(PlayerId::new(1)),
+                get_initial_player(PlayerId::new(2)),
+            ])
+            .bank(get_initial_bank())
+            .market_layout(MarketLayout::new(vec![
+                (ProductionTier::One, 3),
+                (ProductionTier::Two, 3),
+                (ProductionTier::Three, 3),
+                (tier_four, 2),
+            ]))
+            .deck(ProductionTier::One, deck(1..=4))
+            .deck(ProductionTier::Two, deck(11..=13))
+            .deck(ProductionTier::Three, vec![])
+            .deck(tier_four, deck(21..=24))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn market_layout_sets_the_tiers_and_their_slots() {
+        let board = get_board_with_four_tiers();
+        let tier_four = ProductionTier::new(4);
+
+        assert_eq!(board.get_market_slots(&ProductionTier::One).len(), 3);
+        assert_eq!(board.get_deck_size(&ProductionTier::One), 1);
+        let market: Vec<_> = board
+            .get_market_slots(&tier_four)
+            .into_iter()
+            .map(|card| card.map(|card| card.uid))
+            .collect();
+        assert_eq!(market, vec![Some(CardId::new(21)), Some(CardId::new(22))]);
+        assert!(board.get_market_slots(&ProductionTier::new(5)).is_empty());
+
+        let board = play(
+            board,
+            &[
+                Action::ReserveCardFromBoard(CardId::new(22)),
+                Action::ReserveCardFromDeck(tier_four),
+            ],
+        );
+        assert_eq!(
+            board
+                .iter_cards_for_sale(&tier_four)
+                .map(|c| c.uid)
+                .collect::<Vec<_>>(),
+            vec![CardId::new(21), CardId::new(23)]
+        );
+        assert_eq!(board.players[1].reserved_cards[0].uid, CardId::new(24));
+        assert_eq!(board.get_card_tier(&CardId::new(24)), Some(tier_four));
+        assert_eq!(board.validate(), Ok(()));
+
+        let json = serde_json::to_string(&board).unwrap();
+        let loaded: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, board);
+        assert_eq!(loaded.get_market_layout(), board.get_market_layout());
+    }
+
+    #[test]
+    fn games_can_use_custom_colors() {
+        let mut pieces = PieceSet::new(true).with_color(Piece::Red);
+        let silver = pieces.add_color("Silver").unwrap();
+        let bank = Funds::new_from(HashMap::from([
+            (Piece::Red, 4),
+            (silver, 4),
+            (Piece::Golden, 5),
+        ]));
+        let cost = Funds::new_from(HashMap::from([(silver, 2)]));
+        let card = Identifiable::new(ProductionCard::new(cost, silver, Some(1)), CardId::new(1));
+        let builder = Board::builder()
+            .players(vec![
+                get_initial_player(PlayerId::new(1)),
+                get_initial_player(PlayerId::new(2)),
+            ])
+            .deck(ProductionTier::One, vec![card])
+            .deck(ProductionTier::Two, vec![])
+            .deck(ProductionTier::Three, vec![]);
+
+        let result = builder.clone().bank(bank).build();
+        assert_eq!(result.unwrap_err(), SetupError::UnknownPiece(silver));
+
+        let board = builder.bank(bank).pieces(pieces).build().unwrap();
+        let board = play(
+            board,
+            &[
+                Action::CollectPieces(vec![silver, silver], vec![]),
+                Action::CollectPieces(vec![Piece::Red], vec![]),
+                Action::BuyCard(CardId::new(1), None),
+            ],
+        );
+        assert_eq!(board.players[0].get_production()[silver], 1);
+        assert_eq!(board.bank[silver], 4);
+        assert_eq!(board.get_pieces().name(silver), "Silver");
+        assert_eq!(board.validate(), Ok(()));
+
+        let json = serde_json::to_string(&board).unwrap();
+        let loaded: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, board);
+        assert_eq!(loaded.get_pieces(), board.get_pieces());
+    }
+
+    #[test]
+    fn decks_must_match_the_market_layout() {
+        let builder = Board::builder()
+            .player(get_initial_player(PlayerId::new(1)))
+            .market_layout(MarketLayout::new(vec![(ProductionTier::One, 4)]));
+
+        let result = builder.clone().build();
+        assert_eq!(
+            result.unwrap_err(),
+            SetupError::MissingTier(ProductionTier::One)
+        );
+        let result = builder
+            .clone()
+            .deck(ProductionTier::One, vec![])
+            .deck(ProductionTier::Two, vec![])
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            SetupError::UnknownTier(ProductionTier::Two)
+        );
+        assert!(builder.deck(ProductionTier::One, vec![]).build().is_ok());
+    }
+
+    #[test]
+    fn original_tiers_are_stored_by_name() {
+        let tiers = vec![ProductionTier::Two, ProductionTier::new(4)];
+
+        let json = serde_json::to_string(&tiers).unwrap();
+        assert_eq!(json, r#"["Two","4"]"#);
+        let loaded: Vec<ProductionTier> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, tiers);
+        assert!(serde_json::from_str::<ProductionTier>(r#""Four""#).is_err());
+    }
+
+    #[test]
+    fn validate_accepts_a_game_in_progress() {
+        let board = play(
+            get_board_with_two_tiers(),
+            &[
+                Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]),
+                Action::ReserveCardFromDeck(ProductionTier::Two),
+                Action::BuyCard(CardId::new(1), None),
+                Action::ReserveCardFromBoard(CardId::new(12)),
+            ],
+        );
+        assert_eq!(board.validate(), Ok(()));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn actions_are_accepted_on_an_unchecked_board() {
+        let players = vec![
+            get_initial_player(PlayerId::new(1)),
+            get_initial_player(PlayerId::new(2)),
+        ];
+        let decks = HashMap::from([(
+            ProductionTier::One,
+            vec![
+                get_production_card(CardId::new(1)),
+                get_production_card(CardId::new(1)),
+            ],
+        )]);
+        let board = Board::new(players, get_initial_bank(), decks, vec![]);
+        assert!(board.validate().is_err());
+
+        let collect = Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
+        let board = Board::do_action(board, &collect).unwrap();
+        assert_eq!(board.players[0].funds, Funds::new(1, 1, 1, 0, 0, 0));
+    }
+
+    #[test]
+    fn validate_detects_tokens_created_or_lost() {
+        let mut board = get_default_board();
+        board.bank[Piece::Blue] -= 1;
+        board.players[1].funds[Piece::Red] += 2;
+
+        assert_eq!(
+            board.validate(),
+            Err(vec![
+                InvariantViolation::TokenSupplyMismatch {
+                    piece: Piece::Red,
+                    expected: 7,
+                    found: 9,
+                },
+                InvariantViolation::TokenSupplyMismatch {
+                    piece: Piece::Blue,
+                    expected: 7,
+                    found: 6,
+                },
+            ])
         );
     }
 
     #[test]
-    fn can_pass_the_turn() {
-        let board = get_default_board();
-        let action = &Action::PassTheTurn;
+    fn validate_detects_duplicated_and_lost_cards() {
+        let mut board = get_default_board();
+        let card = board.cards_for_sale[&ProductionTier::One][0].clone();
+        board.players[0].reserved_cards.extend(card);
 
-        let current_player = 0;
+        assert_eq!(
+            board.validate(),
+            Err(vec![
+                InvariantViolation::DuplicateCard(CardId::new(1)),
+                InvariantViolation::CardCountMismatch {
+                    expected: 5,
+                    found: 6
+                },
+            ])
+        );
 
-        let result = Board::do_action(board, action);
-        assert!(result.is_ok());
+        let mut board = get_default_board();
+        board.decks.get_mut(&ProductionTier::One).unwrap().clear();
+        assert_eq!(
+            board.validate(),
+            Err(vec![InvariantViolation::CardCountMismatch {
+                expected: 5,
+                found: 4
+            }])
+        );
+    }
 
-        let board_after = result.unwrap();
-        assert_ne!(current_player, board_after.player_turn);
+    #[test]
+    fn validate_detects_duplicated_and_lost_nobles() {
+        let noble = Noble::new(NobleId::new(1), bank::Funds::new(1, 0, 0, 0, 0, 0));
+        let players = vec![
+            get_initial_player(PlayerId::new(1)),
+            get_initial_player(PlayerId::new(2)),
+        ];
+
+        let mut board = get_board(players.clone(), vec![noble.clone()]);
+        board.players[0].nobles.push(noble);
+        assert_eq!(
+            board.validate(),
+            Err(vec![
+                InvariantViolation::DuplicateNoble(NobleId::new(1)),
+                InvariantViolation::NobleCountMismatch {
+                    expected: 1,
+                    found: 2
+                },
+            ])
+        );
+
+        let noble = Noble::new(NobleId::new(1), bank::Funds::new(1, 0, 0, 0, 0, 0));
+        let mut board = get_board(players, vec![noble]);
+        board.nobles.clear();
+        assert_eq!(
+            board.validate(),
+            Err(vec![InvariantViolation::NobleCountMismatch {
+                expected: 1,
+                found: 0
+            }])
+        );
     }
 
     #[test]
-    fn turn_cycles_back_to_first_person() {
-        let board = get_default_board();
-        let action = &Action::PassTheTurn;
+    fn validate_detects_market_and_reserve_overflow() {
+        let mut board = get_default_board();
+        let card = board.decks.get_mut(&ProductionTier::One).unwrap().pop();
+        board
+            .cards_for_sale
+            .get_mut(&ProductionTier::One)
+            .unwrap()
+            .push(card);
 
-        let result = Board::do_action(board, action);
-        assert!(result.is_ok());
+        assert_eq!(
+            board.validate(),
+            Err(vec![InvariantViolation::MarketOverflow {
+                tier: ProductionTier::One,
+                cards: 5,
+                slots: MARKET_SLOTS_PER_TIER,
+            }])
+        );
 
-        let board = result.unwrap();
-        assert_eq!(1, board.player_turn);
+        let mut board = get_default_board();
+        let market = board.cards_for_sale.get_mut(&ProductionTier::One).unwrap();
+        board.players[2]
+            .reserved_cards
+            .extend(market.drain(..).flatten());
 
-        let result = Board::do_action(board, action);
-        assert!(result.is_ok());
+        assert_eq!(
+            board.validate(),
+            Err(vec![InvariantViolation::TooManyReservedCards {
+                player: PlayerId::new(3),
+                cards: 4,
+            }])
+        );
+    }
 
-        let board = result.unwrap();
-        assert_eq!(2, board.player_turn);
+    fn get_complete_decks() -> HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>> {
+        HashMap::from([
+            (
+                ProductionTier::One,
+                vec![get_production_card(CardId::new(1))],
+            ),
+            (
+                ProductionTier::Two,
+                vec![get_production_card(CardId::new(2))],
+            ),
+            (
+                ProductionTier::Three,
+                vec![get_production_card(CardId::new(3))],
+            ),
+        ])
+    }
 
-        // Cycle back to first player
-        let result = Board::do_action(board, action);
-        assert!(result.is_ok());
+    /// A builder with one card in each tier, for boards that only differ by players, bank or
+    /// rules.
+    fn get_builder_with_complete_decks() -> BoardBuilder {
+        get_complete_decks()
+            .into_iter()
+            .fold(Board::builder(), |builder, (tier, cards)| {
+                builder.deck(tier, cards)
+            })
+    }
 
-        let board = result.unwrap();
-        assert_eq!(0, board.player_turn);
+    #[test]
+    #[allow(deprecated)]
+    fn builder_sets_up_a_board() {
+        let noble = Noble::new(NobleId::new(1), bank::Funds::new(1, 0, 0, 0, 0, 0));
+        let board = Board::builder()
+            .player(get_initial_player(PlayerId::new(1)))
+            .player(get_initial_player(PlayerId::new(2)))
+            .bank(get_initial_bank())
+            .deck(
+                ProductionTier::One,
+                vec![get_production_card(CardId::new(1))],
+            )
+            .deck(
+                ProductionTier::Two,
+                vec![get_production_card(CardId::new(2))],
+            )
+            .deck(
+                ProductionTier::Three,
+                vec![get_production_card(CardId::new(3))],
+            )
+            .nobles(vec![noble.clone()])
+            .build()
+            .unwrap();
+
+        let expected = Board::new(
+            vec![
+                get_initial_player(PlayerId::new(1)),
+                get_initial_player(PlayerId::new(2)),
+            ],
+            get_initial_bank(),
+            get_complete_decks(),
+            vec![noble],
+        );
+        assert_same_state(&board, &expected);
     }
 
     #[test]
-    fn can_collect_up_to_three_pieces() {
-        let board = get_default_board();
-        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Blue, Piece::White], vec![]);
+    fn try_new_rejects_invalid_setups() {
+        let players = || {
+            vec![
+                get_initial_player(PlayerId::new(1)),
+                get_initial_player(PlayerId::new(2)),
+            ]
+        };
+        let bank_funds = get_initial_bank();
 
-        let result = Board::do_action(board, action);
-        assert!(result.is_ok());
+        let result = Board::try_new(vec![], bank_funds, get_complete_decks(), vec![]);
+        assert_eq!(result.unwrap_err(), SetupError::NoPlayers);
 
-        let result = result.unwrap();
-        let red = result.bank.funds[&Piece::Red];
-        let blue = result.bank.funds[&Piece::Blue];
-        let white = result.bank.funds[&Piece::White];
-        assert_eq!(red, 6);
-        assert_eq!(blue, 6);
-        assert_eq!(white, 6);
+        let same_id = vec![
+            get_initial_player(PlayerId::new(1)),
+            get_initial_player(PlayerId::new(1)),
+        ];
+        let result = Board::try_new(same_id, bank_funds, get_complete_decks(), vec![]);
+        assert_eq!(
+            result.unwrap_err(),
+            SetupError::DuplicatePlayerId(PlayerId::new(1))
+        );
 
-        let expected_player_funds = bank::Funds::new(1, 0, 1, 0, 1, 0);
-        let first_player = result.players.get(0);
-        assert_eq!(first_player.unwrap().funds, expected_player_funds);
+        let mut decks = get_complete_decks();
+        decks.remove(&ProductionTier::Two);
+        let result = Board::try_new(players(), bank_funds, decks, vec![]);
+        assert_eq!(
+            result.unwrap_err(),
+            SetupError::MissingTier(ProductionTier::Two)
+        );
 
-        let board = get_default_board();
-        let action = &Action::CollectPieces(
-            vec![Piece::Red, Piece::Blue, Piece::White, Piece::Red],
-            vec![],
+        let mut decks = get_complete_decks();
+        decks
+            .get_mut(&ProductionTier::Three)
+            .unwrap()
+            .push(get_production_card(CardId::new(1)));
+        let result = Board::try_new(players(), bank_funds, decks, vec![]);
+        assert_eq!(
+            result.unwrap_err(),
+            SetupError::DuplicateCardId(CardId::new(1))
         );
 
-        let result = Board::do_action(board, action).unwrap_err();
+        let noble = Noble::new(NobleId::new(4), bank::Funds::new(1, 0, 0, 0, 0, 0));
+        let nobles = vec![noble.clone(), noble];
+        let result = Board::try_new(players(), bank_funds, get_complete_decks(), nobles);
         assert_eq!(
-            result,
-            ActionFail::InvalidCollect(CollectError::CannotCollectMoreThanThree)
+            result.unwrap_err(),
+            SetupError::DuplicateNobleId(NobleId::new(4))
         );
+
+        let mut rich_players = players();
+        rich_players[1].funds = bank::Funds::new(0, 0, 0, 0, 255, 0);
+        let result = Board::try_new(rich_players, bank_funds, get_complete_decks(), vec![]);
+        assert_eq!(result.unwrap_err(), SetupError::TooManyTokens(Piece::White));
     }
 
     #[test]
-    fn can_reserve_card_from_deck() {
+    fn missing_tiers_do_not_panic() {
         let board = get_default_board();
 
-        let first_player = board.players.get(0).unwrap();
-
-        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
-        assert_eq!(first_player.reserved_cards.len(), 0);
-        assert_eq!(board.player_turn, 0);
-
-        let action = &Action::ReserveCardFromDeck(ProductionTier::One);
-
-        let board = Board::do_action(board, action).unwrap();
-
-        // Assert that p1 has a production card reserved
-        let first_player = board.players.get(0).unwrap();
-
-        assert_eq!(first_player.reserved_cards.len(), 1);
-
-        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 0);
-
-        // Make sure the turn is passed to the next player
-        assert_eq!(board.player_turn, 1);
-        assert_eq!(board.round_type, RoundType::Normal);
+        assert_eq!(board.iter_deck(&ProductionTier::Three).count(), 0);
+        assert_eq!(board.iter_cards_for_sale(&ProductionTier::Three).count(), 0);
 
-        // Assert that we cannot reserve from an empty deck
-        let action = &Action::ReserveCardFromDeck(ProductionTier::One);
-        let result = Board::do_action(board.clone(), action).unwrap_err();
+        let action = &Action::ReserveCardFromDeck(ProductionTier::Three);
+        let result = Board::do_action(board, action).unwrap_err();
         assert_eq!(result, ActionFail::CannotReserveFromEmptyDeck);
     }
 
     #[test]
-    fn can_reserve_card_from_board() {
+    fn only_the_player_to_act_can_play() {
         let board = get_default_board();
+        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
 
-        let first_player = board.players.get(0).unwrap();
-
-        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
-        assert_eq!(first_player.reserved_cards.len(), 0);
-        assert_eq!(first_player.funds.funds.get(&Piece::Golden).unwrap(), &0);
-        assert_eq!(board.player_turn, 0);
+        let result = Board::do_action_as(board.clone(), PlayerId::new(2), action);
+        assert_eq!(result.unwrap_err(), ActionFail::NotYourTurn);
 
-        let action = &Action::ReserveCardFromBoard(CardId::new(1));
+        let result = Board::do_action_as(board.clone(), PlayerId::new(42), action);
+        assert_eq!(result.unwrap_err(), ActionFail::NotYourTurn);
 
-        let result = Board::do_action(board, action).unwrap();
+        let board = Board::do_action_as(board, PlayerId::new(1), action).unwrap();
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
 
-        let player_one = result.players.get(0).unwrap();
+        let mut board = board;
+        let result = board.apply_as(PlayerId::new(1), &Action::PassTheTurn);
+        assert_eq!(result.unwrap_err(), ActionFail::NotYourTurn);
+        board
+            .apply_as(PlayerId::new(2), &Action::PassTheTurn)
+            .unwrap();
+        assert_eq!(board.player_to_act(), PlayerId::new(3));
+    }
 
-        assert_eq!(player_one.reserved_cards.len(), 1);
-        assert_eq!(player_one.funds.funds.get(&Piece::Golden).unwrap(), &1);
-        assert_eq!(result.player_turn, 1);
+    #[test]
+    fn noble_selection_belongs_to_the_buyer() {
+        let noble = Noble::new(NobleId::new(1), bank::Funds::new(1, 0, 0, 0, 0, 0));
+        let p1 = player::Player::new(
+            PlayerId::new(1),
+            bank::Funds::new(1, 1, 0, 0, 0, 0),
+            vec![],
+            vec![],
+        );
+        let board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![noble]);
 
-        let player_reserved_card = player_one.reserved_cards.get(0).unwrap();
+        let action = &Action::BuyCard(CardId::new(1), None);
+        let board = Board::do_action_as(board, PlayerId::new(1), action).unwrap();
+        assert_eq!(board.action_needed, ActionType::SelectNoble);
+        assert_eq!(board.player_to_act(), PlayerId::new(1));
 
-        let expected_card = get_production_card(CardId::new(1));
+        let action = &Action::SelectNoble(NobleId::new(1));
+        let result = Board::do_action_as(board.clone(), PlayerId::new(2), action);
+        assert_eq!(result.unwrap_err(), ActionFail::NotYourTurn);
 
-        assert_eq!(player_reserved_card.uid, expected_card.uid);
-        assert_eq!(player_reserved_card.data, expected_card.data);
+        let board = Board::do_action_as(board, PlayerId::new(1), action).unwrap();
+        assert_eq!(board.players[0].nobles.len(), 1);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
     }
 
     #[test]
-    fn cannot_reserve_card_that_does_not_exist() {
+    fn failures_explain_themselves() {
         let board = get_default_board();
-        let action = &Action::ReserveCardFromBoard(CardId::new(255));
-        let result = Board::do_action(board, action).unwrap_err();
+        let action = &Action::BuyCard(CardId::new(1), None);
+        let fail = Board::do_action(board, action).unwrap_err();
+
         assert_eq!(
-            result,
-            ActionFail::InvalidReserve(ReserveOperationFail::CardNotFound)
+            fail.to_string(),
+            "You need 1 more Red and 1 more Green to buy this card"
+        );
+        assert_eq!(
+            fail.source().unwrap().to_string(),
+            "You need 1 more Red and 1 more Green to buy this card"
         );
-    }
-
-    #[test]
-    fn can_buy_production_card() {
-        let board = get_default_board();
-        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
-        let action_pass = &Action::PassTheTurn;
-
-        assert_eq!(board.bank, Funds::new(7, 7, 7, 7, 7, 5));
 
-        let board = Board::do_action(board, action).unwrap();
-        assert_eq!(board.bank, Funds::new(6, 6, 6, 7, 7, 5));
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
+        let fail = ActionFail::InvalidCollect(CollectError::NotEnoughAtTheBank(Piece::Blue));
+        assert_eq!(
+            fail.to_string(),
+            "There are not enough Blue pieces left at the bank"
+        );
+        assert!(fail.source().unwrap().is::<CollectError>());
 
-        let board = Board::do_action(board, action).unwrap();
-        assert_eq!(board.bank, Funds::new(5, 5, 5, 7, 7, 5));
+        let fail = ActionFail::NotYourTurn;
+        assert_eq!(fail.to_string(), "It is not your turn");
+        assert!(fail.source().is_none());
 
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
+        let fail = BuyOperationFail::NotEnoughFunds(Funds::new(0, 0, 2, 0, 0, 0));
+        assert_eq!(fail.to_string(), "You need 2 more Blue to buy this card");
+    }
 
-        let player_one = board.players.get(0).unwrap();
-        assert_eq!(player_one.production_cards.len(), 0);
-        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
-        assert_eq!(
-            board
-                .cards_for_sale
-                .get(&ProductionTier::One)
-                .unwrap()
-                .len(),
-            4
+    #[test]
+    fn can_pay_with_gold_to_keep_colored_pieces() {
+        let p1 = Player::new(
+            PlayerId::new(1),
+            Funds::new(1, 1, 0, 0, 0, 2),
+            vec![],
+            vec![],
         );
-        let expected_player_funds = bank::Funds::new(2, 2, 2, 0, 0, 0);
-        assert_eq!(player_one.funds, expected_player_funds);
+        let board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![]);
+        let bank_before = board.bank;
 
-        let action = &Action::BuyCard(CardId::new(1));
+        let payment = Funds::new(1, 0, 0, 0, 0, 1);
+        let action = &Action::BuyCard(CardId::new(1), Some(payment));
         let board = Board::do_action(board, action).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
 
-        let player_one = board.players.get(0).unwrap();
-        assert_eq!(player_one.production_cards.len(), 1);
+        let player = &board.players[0];
+        assert_eq!(player.funds, Funds::new(0, 1, 0, 0, 0, 1));
+        assert_eq!(player.production_cards.len(), 1);
+        assert_eq!(board.bank, bank_before + payment);
+    }
 
-        // Make sure that the money was transfered, from the player to the bank
-        let expected_player_funds = bank::Funds::new(1, 1, 2, 0, 0, 0);
-        let expected_bank_funds = bank::Funds::new(6, 6, 5, 7, 7, 5);
-        assert_eq!(player_one.funds, expected_player_funds);
-        assert_eq!(board.bank, expected_bank_funds);
+    #[test]
+    fn rejects_payments_that_do_not_match_the_cost() {
+        let p1 = Player::new(
+            PlayerId::new(1),
+            Funds::new(1, 1, 0, 0, 0, 2),
+            vec![],
+            vec![],
+        );
+        let board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![]);
+
+        let cases = [
+            (Funds::new(1, 0, 0, 0, 0, 0), BuyOperationFail::Underpaid(1)),
+            (
+                Funds::new(1, 1, 0, 0, 0, 1),
+                BuyOperationFail::Overpaid(Funds::new(0, 0, 0, 0, 0, 1)),
+            ),
+            (
+                Funds::new(1, 0, 0, 0, 1, 1),
+                BuyOperationFail::PaymentNotOwned(Funds::new(0, 0, 0, 0, 1, 0)),
+            ),
+        ];
+        for (payment, fail) in cases {
+            let action = &Action::BuyCard(CardId::new(1), Some(payment));
+            let result = Board::do_action(board.clone(), action);
+            assert_eq!(result.unwrap_err(), ActionFail::InvalidBuyOperation(fail));
+        }
 
-        // Makes sure a new card was drawn
-        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 0);
+        let action = &Action::BuyCard(CardId::new(1), Some(Funds::new(2, 0, 0, 0, 0, 0)));
+        let result = Board::do_action(board, action);
         assert_eq!(
-            board
-                .cards_for_sale
-                .get(&ProductionTier::One)
-                .unwrap()
-                .len(),
-            4
+            result.unwrap_err().to_string(),
+            "You need 1 more Red to make this payment"
         );
+    }
 
-        // Makes sure the card is not on sale anymore
-        let action_fail = Board::do_action(board.clone(), action).unwrap_err();
+    #[test]
+    fn exposes_board_state_without_cloning() {
+        let board = get_board_with_two_tiers();
+
+        assert_eq!(board.get_player_turn(), 0);
+        assert_eq!(board.player_to_act(), PlayerId::new(1));
+        assert_eq!(board.get_action_needed(), ActionType::Normal);
+        assert_eq!(board.get_round_type(), RoundType::Normal);
+        assert_eq!(board.get_winner(), None);
+
+        assert_eq!(board.get_deck_size(&ProductionTier::One), 4);
+        assert_eq!(board.get_deck_size(&ProductionTier::Two), 1);
+        assert_eq!(board.get_deck_size(&ProductionTier::Three), 0);
+        assert_eq!(board.iter_deck(&ProductionTier::Two).count(), 1);
+
+        let market = board.get_market_slots(&ProductionTier::One);
+        assert!(market.iter().all(Option::is_some));
+        let market: Vec<_> = board
+            .iter_cards_for_sale(&ProductionTier::One)
+            .map(|card| card.uid)
+            .collect();
         assert_eq!(
-            action_fail,
-            ActionFail::InvalidBuyOperation(BuyOperationFail::CardNotFoundOnBoard)
+            market,
+            vec![
+                CardId::new(1),
+                CardId::new(2),
+                CardId::new(3),
+                CardId::new(4)
+            ]
+        );
+        assert_eq!(
+            board.get_market_slots(&ProductionTier::Three),
+            [None, None, None, None]
         );
 
-        // Make sure you used the card instead of the coins
-        // Using 1 production card (red), the player red coin should is not reduced, only 1 green
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.get_player_turn(), 1);
+    }
 
-        let action = &Action::BuyCard(CardId::new(2));
-        let board = Board::do_action(board, action).unwrap();
+    #[test]
+    fn breaks_down_scores_by_source() {
+        let mut p1 = get_player_with_winning_points(PlayerId::new(1), CardId::new(101), 4);
+        p1.nobles
+            .push(Noble::new(NobleId::new(1), Funds::default()));
+        let p2 = get_initial_player(PlayerId::new(2));
+        let board = get_board(vec![p1, p2], vec![]);
 
-        let player_one = board.players.get(0).unwrap();
-        assert_eq!(player_one.production_cards.len(), 2);
+        let scores = board.get_score_breakdowns();
+        assert_eq!(
+            scores[0],
+            ScoreBreakdown {
+                player: PlayerId::new(1),
+                card_points: 4,
+                noble_points: 3,
+                #[cfg(feature = "trading-posts")]
+                power_points: 0,
+                total: 7,
+                production_cards: 1,
+            }
+        );
+        assert_eq!(scores[1].total, 0);
+    }
 
-        let expected_player_funds = bank::Funds::new(1, 0, 2, 0, 0, 0);
-        let expected_bank_funds = bank::Funds::new(6, 7, 5, 7, 7, 5);
-        assert_eq!(player_one.funds, expected_player_funds);
-        assert_eq!(board.bank, expected_bank_funds);
+    fn get_board_starting_with(starting_player: PlayerId) -> Board {
+        get_builder_with_complete_decks()
+            .player(get_player_with_winning_points(
+                PlayerId::new(1),
+                CardId::new(100),
+                16,
+            ))
+            .player(get_initial_player(PlayerId::new(2)))
+            .player(get_initial_player(PlayerId::new(3)))
+            .bank(get_initial_bank())
+            .starting_player(starting_player)
+            .build()
+            .unwrap()
     }
 
     #[test]
-    fn cannot_buy_production_card_if_there_is_no_pieces() {
-        let board = get_default_board();
-        let player_one = board.players.get(0).unwrap();
-        assert_eq!(player_one.production_cards.len(), 0);
-
-        let action = &Action::BuyCard(CardId::new(1));
+    fn every_player_gets_as_many_turns_whoever_starts() {
+        for seat in 0..3 {
+            let starting_player = PlayerId::new(seat as u32 + 1);
+            let mut board = get_board_starting_with(starting_player);
+            assert_eq!(board.get_starting_player(), seat);
+            assert_eq!(board.player_to_act(), starting_player);
+
+            for turn in 0..3 {
+                assert_eq!(board.get_player_turn(), (seat + turn) % 3);
+                assert_eq!(board.get_winner(), None);
+                board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+            }
 
-        let result = Board::do_action(board, action).unwrap_err();
-        let expected_funds = bank::Funds::new(1, 1, 0, 0, 0, 0);
-        assert_eq!(
-            result,
-            ActionFail::InvalidBuyOperation(BuyOperationFail::NotEnoughFunds(expected_funds))
-        );
+            assert_eq!(board.get_round_type(), RoundType::LastRound);
+            assert_eq!(board.get_winner(), Some(&Winner::Winner(PlayerId::new(1))));
+        }
     }
 
     #[test]
-    fn can_buy_card_using_golden_and_cards_production() {
-        let funds = bank::Funds::new(1, 1, 0, 0, 0, 2);
-        let red_production = production_card::ProductionCard::new(
-            get_default_production_card_cost(),
-            Piece::Red,
-            Some(1),
-        );
-        let red_card = Identifiable::new(red_production, CardId::new(100));
-        let p1 = player::Player::new(PlayerId::new(1), funds, vec![red_card], vec![]);
-        let p2 = get_initial_player(PlayerId::new(2));
-        let p3 = get_initial_player(PlayerId::new(3));
-        let bank_funds = get_initial_bank();
+    fn draws_the_starting_player_from_a_seed() {
+        let build = |seed| {
+            get_builder_with_complete_decks()
+                .player(get_initial_player(PlayerId::new(1)))
+                .player(get_initial_player(PlayerId::new(2)))
+                .player(get_initial_player(PlayerId::new(3)))
+                .random_starting_player(seed)
+                .build()
+                .unwrap()
+        };
 
-        let blue_production = production_card::ProductionCard::new(
-            bank::Funds::new(3, 1, 0, 0, 0, 0),
-            Piece::Blue,
-            Some(1),
-        );
-        let card_to_buy = Identifiable::new(blue_production, CardId::new(101));
+        assert_eq!(build(7), build(7));
+        let seats: HashSet<usize> = (0..20)
+            .map(|seed| build(seed).get_starting_player())
+            .collect();
+        assert_eq!(seats.len(), 3);
 
-        let decks = HashMap::from([(
-            ProductionTier::One,
-            vec![
-                get_production_card(CardId::new(5)),
-                get_production_card(CardId::new(4)),
-                get_production_card(CardId::new(3)),
-                get_production_card(CardId::new(2)),
-                get_production_card(CardId::new(1)),
-                card_to_buy,
-            ],
-        )]);
+        let board = build(7);
+        assert_eq!(board.get_player_turn(), board.get_starting_player());
+        assert_eq!(board.position_hash(), board.compute_position_hash());
+    }
+
+    #[test]
+    fn rejects_an_unknown_starting_player() {
+        let result = Board::builder()
+            .player(get_initial_player(PlayerId::new(1)))
+            .deck(ProductionTier::One, vec![])
+            .deck(ProductionTier::Two, vec![])
+            .deck(ProductionTier::Three, vec![])
+            .starting_player(PlayerId::new(2))
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            SetupError::UnknownStartingPlayer(PlayerId::new(2))
+        );
+    }
 
-        let board = Board::new(vec![p1, p2, p3], bank_funds, decks, vec![]);
+    fn get_board_with_rules(rules: Rules) -> Board {
+        get_builder_with_complete_decks()
+            .player(get_initial_player(PlayerId::new(1)))
+            .player(get_player_with_winning_points(
+                PlayerId::new(2),
+                CardId::new(100),
+                2,
+            ))
+            .player(get_initial_player(PlayerId::new(3)))
+            .bank(get_initial_bank())
+            .rules(rules)
+            .build()
+            .unwrap()
+    }
 
-        let player_one = board.players.get(0).unwrap();
-        assert_eq!(player_one.production_cards.len(), 1);
+    #[test]
+    fn ends_the_game_after_the_maximum_rounds() {
+        let rules = Rules {
+            max_rounds: Some(2),
+            ..Rules::default()
+        };
+        let mut board = get_board_with_rules(rules);
+        let collect = Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
 
-        let action = &Action::BuyCard(CardId::new(101));
-        let board = Board::do_action(board, action).unwrap();
+        for _ in 0..5 {
+            board = Board::do_action(board, &collect).unwrap();
+            assert_eq!(board.get_winner(), None);
+        }
+        assert_eq!(board.get_rounds_played(), 1);
 
-        let player_one = board.players.get(0).unwrap();
-        let player_expected_funds = Funds::new(0, 0, 0, 0, 0, 1);
-        assert_eq!(player_one.production_cards.len(), 2);
-        assert_eq!(player_one.funds, player_expected_funds);
+        let board = Board::do_action(board, &collect).unwrap();
+        assert_eq!(board.get_rounds_played(), 2);
+        assert_eq!(board.get_end_reason(), Some(EndReason::MaxRounds));
+        assert_eq!(board.get_winner(), Some(&Winner::Winner(PlayerId::new(2))));
     }
 
     #[test]
-    fn can_select_a_noble_only_after_buying() {
-        let board = get_default_board();
+    fn ends_the_game_when_everyone_passes_in_a_row() {
+        let rules = Rules {
+            end_on_stalemate: true,
+            ..Rules::default()
+        };
+        let board = get_board_with_rules(rules);
+        let collect = Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
+
+        let board = play(board, &[Action::PassTheTurn, Action::PassTheTurn, collect]);
+        assert_eq!(board.get_end_reason(), None);
 
-        let noble_to_select = Noble {
-            id: NobleId::new(1),
-            cost: bank::Funds::new(1, 0, 0, 0, 0, 0),
+        let mut board = play(board, &[Action::PassTheTurn, Action::PassTheTurn]);
+        assert_eq!(board.get_end_reason(), None);
+
+        let expected = board.clone();
+        let undo_token = board.apply(&Action::PassTheTurn).unwrap();
+        assert_eq!(board.get_end_reason(), Some(EndReason::Stalemate));
+        assert_eq!(board.get_winner(), Some(&Winner::Winner(PlayerId::new(2))));
+
+        board.undo(undo_token);
+        assert_same_state(&board, &expected);
+    }
+
+    #[test]
+    fn no_action_is_accepted_once_the_game_is_over() {
+        let player_one = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 16);
+        let player_two = get_player_with_winning_points(PlayerId::new(2), CardId::new(101), 15);
+        let mut board = get_board(vec![player_one, player_two], vec![]);
+        board.set_round_type(RoundType::LastRound);
+        let won = play(board, &[Action::PassTheTurn, Action::PassTheTurn]);
+
+        let collect = || Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
+        let rules = Rules {
+            max_rounds: Some(2),
+            ..Rules::default()
         };
+        let collects: Vec<Action> = (0..6).map(|_| collect()).collect();
+        let out_of_rounds = play(get_board_with_rules(rules), &collects);
 
-        let second_noble = Noble {
-            id: NobleId::new(2),
-            cost: bank::Funds::new(1, 0, 0, 0, 0, 0),
+        let rules = Rules {
+            end_on_stalemate: true,
+            ..Rules::default()
         };
+        let stalemate = play(
+            get_board_with_rules(rules),
+            &[
+                Action::PassTheTurn,
+                Action::PassTheTurn,
+                Action::PassTheTurn,
+            ],
+        );
 
-        let board = Board {
-            nobles: vec![noble_to_select.clone(), second_noble.clone()],
-            ..board
+        for (mut board, end_reason) in [
+            (won, EndReason::WinningPoints),
+            (out_of_rounds, EndReason::MaxRounds),
+            (stalemate, EndReason::Stalemate),
+        ] {
+            assert_eq!(board.get_end_reason(), Some(end_reason));
+            let before = board.clone();
+            for action in [Action::PassTheTurn, collect()] {
+                assert_eq!(board.apply(&action).unwrap_err(), ActionFail::GameIsOver);
+            }
+            assert_same_state(&board, &before);
+        }
+    }
+
+    #[test]
+    fn passing_never_ends_the_game_by_default() {
+        let board = get_board_with_rules(Rules::default());
+        let passes: Vec<Action> = (0..9).map(|_| Action::PassTheTurn).collect();
+        let board = play(board, &passes);
+
+        assert_eq!(board.get_rounds_played(), 3);
+        assert_eq!(board.get_end_reason(), None);
+        assert_eq!(board.get_winner(), None);
+    }
+
+    fn get_board_for_passing(bank: Funds, reserved_cards: u8, funds: Funds) -> Board {
+        let reserved_cards = (1..=reserved_cards)
+            .map(|id| get_production_card(CardId::new(200 + u32::from(id))))
+            .collect();
+        let p1 = Player::new(PlayerId::new(1), funds, vec![], reserved_cards);
+        let rules = Rules {
+            pass_only_when_stuck: true,
+            ..Rules::default()
         };
+        get_builder_with_complete_decks()
+            .player(p1)
+            .player(get_initial_player(PlayerId::new(2)))
+            .bank(bank)
+            .rules(rules)
+            .build()
+            .unwrap()
+    }
 
-        // Make sure you can't select noble at any time
-        let action = &Action::SelectNoble(NobleId::new(1));
-        let action_fail = Board::do_action(board.clone(), action).unwrap_err();
-        assert_eq!(action_fail, ActionFail::YouCannotSelectNobleNow);
+    #[test]
+    fn can_pass_with_an_empty_bank_and_a_full_reserve_hand() {
+        let board = get_board_for_passing(Funds::default(), 3, Funds::default());
+        assert!(board.is_stuck());
 
-        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
-        let action_pass = &Action::PassTheTurn;
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.get_player_turn(), 1);
+    }
 
-        let board = Board::do_action(board, action).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
+    #[test]
+    fn cannot_pass_while_there_is_something_else_to_do() {
+        let only_golden = Funds::new(0, 0, 0, 0, 0, 5);
+        let cases = [
+            // A piece to collect.
+            get_board_for_passing(Funds::new(0, 0, 1, 0, 0, 0), 3, Funds::default()),
+            // Room to reserve, golden pieces do not count as something to collect.
+            get_board_for_passing(only_golden, 2, Funds::default()),
+            // A reserved card to buy.
+            get_board_for_passing(Funds::default(), 3, Funds::new(1, 1, 0, 0, 0, 0)),
+        ];
+
+        for board in cases {
+            assert!(!board.is_stuck());
+            let result = Board::do_action(board, &Action::PassTheTurn);
+            assert_eq!(result.unwrap_err(), ActionFail::PassingNotAllowed);
+        }
+    }
 
-        let player_one = board.players.get(0).unwrap();
-        assert_eq!(player_one.production_cards.len(), 0);
-        assert_eq!(board.decks.get(&ProductionTier::One).unwrap().len(), 1);
-        assert_eq!(
-            board
-                .cards_for_sale
-                .get(&ProductionTier::One)
-                .unwrap()
-                .len(),
-            4
-        );
+    #[test]
+    fn passing_is_allowed_by_default() {
+        let board = get_default_board();
+        assert!(!board.is_stuck());
+        assert!(Board::do_action(board, &Action::PassTheTurn).is_ok());
+    }
+
+    fn get_player_producing(id: PlayerId, production: &[Piece]) -> Player {
+        let production_cards = production
+            .iter()
+            .enumerate()
+            .map(|(i, piece)| {
+                let card = ProductionCard::new(Funds::default(), *piece, None);
+                Identifiable::new(card, CardId::new(100 * id.id() + i as u32))
+            })
+            .collect();
+        Player::new(id, Funds::default(), production_cards, vec![])
+    }
+
+    #[test]
+    fn nobles_visit_at_the_end_of_any_turn() {
+        let noble = Noble::new(NobleId::new(1), Funds::new(2, 0, 0, 0, 0, 0));
+        let p1 = get_player_producing(PlayerId::new(1), &[Piece::Red, Piece::Red]);
+        let board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![noble]);
+
+        let collect = Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
+        let board = Board::do_action(board, &collect).unwrap();
+        assert_eq!(board.action_needed, ActionType::SelectNoble);
+        assert_eq!(board.player_to_act(), PlayerId::new(1));
 
-        let action = &Action::BuyCard(CardId::new(1));
+        let board = Board::do_action(board, &Action::SelectNoble(NobleId::new(1))).unwrap();
+        assert_eq!(board.players[0].nobles.len(), 1);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+    }
 
+    #[test]
+    fn lists_every_eligible_noble_but_grants_one_per_turn() {
+        let nobles = vec![
+            Noble::new(NobleId::new(1), Funds::new(1, 0, 0, 0, 0, 0)),
+            Noble::new(NobleId::new(2), Funds::new(0, 1, 0, 0, 0, 0)),
+            Noble::new(NobleId::new(3), Funds::new(0, 0, 1, 0, 0, 0)),
+        ];
+        let p1 = get_player_producing(PlayerId::new(1), &[Piece::Red, Piece::Green]);
+        let board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], nobles);
+
+        let eligible: Vec<NobleId> = board.eligible_nobles().iter().map(|n| n.id).collect();
+        assert_eq!(eligible, vec![NobleId::new(1), NobleId::new(2)]);
+
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        let action = &Action::SelectNoble(NobleId::new(3));
+        let result = Board::do_action(board.clone(), action);
+        assert_eq!(result.unwrap_err(), ActionFail::NobleNotEligible);
+
+        let board = Board::do_action(board, &Action::SelectNoble(NobleId::new(2))).unwrap();
         assert_eq!(board.action_needed, ActionType::Normal);
-        let board = Board::do_action(board, action).unwrap();
-        // Assert that the player is still playing
-        assert_eq!(board.player_turn, 0);
-        // Assert that the action needed is special: To select a noble
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+
+        // The other noble waits for the next turn of the same player.
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.action_needed, ActionType::Normal);
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
         assert_eq!(board.action_needed, ActionType::SelectNoble);
+        let eligible: Vec<NobleId> = board.eligible_nobles().iter().map(|n| n.id).collect();
+        assert_eq!(eligible, vec![NobleId::new(1)]);
+    }
 
-        // And that we can't do other actions
-        let action = &Action::CollectPieces(vec![Piece::Red, Piece::Green, Piece::Blue], vec![]);
-        let action_fail = Board::do_action(board.clone(), action).unwrap_err();
+    #[test]
+    fn no_noble_phase_without_eligible_nobles() {
+        let noble = Noble::new(NobleId::new(1), Funds::new(2, 0, 0, 0, 0, 0));
+        let p1 = get_player_producing(PlayerId::new(1), &[Piece::Red, Piece::Green]);
+        let board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![noble]);
 
-        assert_eq!(action_fail, ActionFail::YouNeedToSelectNoble);
+        assert!(board.eligible_nobles().is_empty());
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.action_needed, ActionType::Normal);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+    }
 
-        // Make sure we still dont have any nobles
-        let player_one = board.players.get(0).unwrap();
-        assert_eq!(player_one.nobles.len(), 0);
+    #[test]
+    fn golden_in_a_noble_cost_takes_any_surplus_production() {
+        let noble = Noble::new(NobleId::new(1), Funds::new(1, 0, 0, 0, 0, 1));
+        let p1 = get_player_producing(PlayerId::new(1), &[Piece::Red]);
+        let p2 = get_player_producing(PlayerId::new(2), &[Piece::Red, Piece::White]);
+        let board = get_board(vec![p1, p2], vec![noble]);
+
+        assert!(board.eligible_nobles().is_empty());
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.eligible_nobles().len(), 1);
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.action_needed, ActionType::SelectNoble);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+    }
 
-        // Make sure we can't select inexistent noble
-        let action = &Action::SelectNoble(NobleId::new(255));
-        let select_err = Board::do_action(board.clone(), action).unwrap_err();
-        assert_eq!(select_err, ActionFail::NobleNotFound);
+    fn get_card_with_ability(
+        id: u32,
+        produces: Piece,
+        ability: CardAbility,
+    ) -> Identifiable<ProductionCard, CardId> {
+        let card = ProductionCard::new(Funds::default(), produces, None).with_ability(ability);
+        Identifiable::new(card, CardId::new(id))
+    }
 
-        // Assert that we can select noble
-        let action = &Action::SelectNoble(NobleId::new(1));
-        let board = Board::do_action(board, action).unwrap();
+    /// Tier one sells cards 1 to 4, the last one copying a color, and tier two sells cards 11
+    /// to 14 with the given abilities.
+    fn get_board_with_abilities(
+        players: Vec<Player>,
+        abilities: &[CardAbility],
+        nobles: Vec<Noble>,
+    ) -> Board {
+        let tier_one = vec![
+            get_production_card(CardId::new(5)),
+            get_card_with_ability(4, Piece::Golden, CardAbility::CopyColor),
+            get_production_card(CardId::new(3)),
+            get_production_card(CardId::new(2)),
+            get_production_card(CardId::new(1)),
+        ];
+        let tier_two = abilities
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, ability)| get_card_with_ability(11 + i as u32, Piece::Blue, *ability))
+            .collect();
 
-        let player_one = board.players.get(0).unwrap();
-        assert_eq!(player_one.nobles.len(), 1);
-        assert_eq!(player_one.nobles.get(0).unwrap(), &noble_to_select);
+        Board::builder()
+            .players(players)
+            .bank(get_initial_bank())
+            .deck(ProductionTier::One, tier_one)
+            .deck(ProductionTier::Two, tier_two)
+            .deck(ProductionTier::Three, vec![])
+            .nobles(nobles)
+            .build()
+            .unwrap()
+    }
 
-        // Assert that we cannot select noble again (even if the board would normally allow)
+    #[test]
+    fn double_bonus_cards_produce_two_pieces() {
+        let p1 = get_initial_player(PlayerId::new(1));
+        let board = get_board_with_abilities(
+            vec![p1, get_initial_player(PlayerId::new(2))],
+            &[CardAbility::DoubleBonus],
+            vec![],
+        );
+
+        let board = Board::do_action(board, &Action::BuyCard(CardId::new(11), None)).unwrap();
+
+        assert_eq!(
+            board.players[0].get_production(),
+            Funds::new(0, 0, 2, 0, 0, 0)
+        );
         assert_eq!(board.action_needed, ActionType::Normal);
-        assert_eq!(board.player_turn, 1);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
     }
 
     #[test]
-    fn end_round_triggered_after_hitting_15_points() {
-        let player_one = get_initial_player(PlayerId::new(1));
-        let player_one = player::Player {
-            funds: bank::Funds::new(3, 3, 2, 2, 0, 0),
-            production_cards: vec![
-                get_production_card(CardId::new(100)),
-                get_production_card(CardId::new(101)),
-                get_production_card(CardId::new(102)),
-                get_production_card(CardId::new(103)),
-                get_production_card(CardId::new(104)),
-                get_production_card(CardId::new(105)),
-                get_production_card(CardId::new(106)),
-                get_production_card(CardId::new(107)),
-                get_production_card(CardId::new(108)),
-                get_production_card(CardId::new(109)),
-                get_production_card(CardId::new(110)),
-                get_production_card(CardId::new(111)),
-            ],
-            ..player_one
-        };
-        let cost = bank::Funds::new(1, 1, 1, 0, 0, 0);
-        let card_to_buy = production_card::ProductionCard::new(cost, Piece::Blue, Some(3));
-        let card_to_buy = Identifiable::new(card_to_buy, CardId::new(112));
+    fn take_lower_tier_card_waits_for_a_card_of_the_tier_below() {
+        let p1 = get_initial_player(PlayerId::new(1));
+        let board = get_board_with_abilities(
+            vec![p1, get_initial_player(PlayerId::new(2))],
+            &[CardAbility::TakeLowerTierCard; 2],
+            vec![],
+        );
 
-        let mut cards_for_sale: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>> =
-            HashMap::new();
-        cards_for_sale.insert(ProductionTier::One, vec![card_to_buy]);
-        let board = get_default_board();
-        let board = Board {
-            players: vec![player_one, get_initial_player(PlayerId::new(2))],
-            cards_for_sale,
-            ..board
-        };
+        let board = Board::do_action(board, &Action::BuyCard(CardId::new(11), None)).unwrap();
+        assert_eq!(board.action_needed, ActionType::ResolveAbility);
+        assert_eq!(board.player_to_act(), PlayerId::new(1));
+        assert_eq!(
+            board.get_pending_ability(),
+            Some(PendingAbility {
+                card_id: CardId::new(11),
+                ability: CardAbility::TakeLowerTierCard,
+            })
+        );
 
-        // assert that the EndGame was not triggered (to be created)
-        assert_eq!(board.round_type, RoundType::Normal);
+        let result = Board::do_action(board.clone(), &Action::PassTheTurn);
+        assert_eq!(result.unwrap_err(), ActionFail::YouNeedToResolveAbility);
+        let take_tier_two = &Action::ResolveAbility(AbilityChoice::TakeCard(CardId::new(12)));
+        let result = Board::do_action(board.clone(), take_tier_two);
+        assert_eq!(result.unwrap_err(), ActionFail::InvalidAbilityChoice);
+        let reserve_noble = &Action::ResolveAbility(AbilityChoice::ReserveNoble(NobleId::new(1)));
+        let result = Board::do_action(board.clone(), reserve_noble);
+        assert_eq!(result.unwrap_err(), ActionFail::InvalidAbilityChoice);
+
+        let take = &Action::ResolveAbility(AbilityChoice::TakeCard(CardId::new(2)));
+        let board = Board::do_action(board, take).unwrap();
+        let owned: Vec<CardId> = board.players[0]
+            .production_cards
+            .iter()
+            .map(|card| card.uid)
+            .collect();
+        assert_eq!(owned, vec![CardId::new(11), CardId::new(2)]);
+        assert_eq!(
+            board.get_market_slots(&ProductionTier::One)[1].unwrap().uid,
+            CardId::new(5)
+        );
+        assert_eq!(board.get_pending_ability(), None);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+        assert_eq!(board.validate(), Ok(()));
+    }
 
-        // Buy production card 112
-        let action = Action::BuyCard(CardId::new(112));
-        let board = Board::do_action(board, &action).unwrap();
+    #[test]
+    fn abilities_of_free_cards_chain_and_undo() {
+        let p1 = get_initial_player(PlayerId::new(1));
+        let mut board = get_board_with_abilities(
+            vec![p1, get_initial_player(PlayerId::new(2))],
+            &[CardAbility::TakeLowerTierCard],
+            vec![],
+        );
+        let actions = [
+            Action::BuyCard(CardId::new(11), None),
+            Action::ResolveAbility(AbilityChoice::TakeCard(CardId::new(4))),
+            Action::ResolveAbility(AbilityChoice::CopyColor(Piece::Blue)),
+        ];
+
+        let mut history = vec![];
+        for action in &actions {
+            let before = board.clone();
+            let undo_token = board.apply(action).unwrap();
+            history.push((before, undo_token));
+        }
+        assert_eq!(
+            board.players[0].get_production(),
+            Funds::new(0, 0, 2, 0, 0, 0)
+        );
+        assert_eq!(board.position_hash(), board.compute_position_hash());
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
 
-        // assert that the EndGame was triggered
-        assert_eq!(board.round_type, RoundType::LastRound);
-        assert_eq!(board.winner, None);
+        while let Some((before, undo_token)) = history.pop() {
+            board.undo(undo_token);
+            assert_same_state(&board, &before);
+        }
+    }
 
-        // Pass the 2nd player turn, ending the game
-        let action = Action::PassTheTurn;
-        let board = Board::do_action(board, &action).unwrap();
-        assert_eq!(board.winner.unwrap(), Winner::Winner(PlayerId::new(1)));
+    #[test]
+    fn take_lower_tier_card_is_skipped_without_a_lower_market() {
+        let p1 = get_initial_player(PlayerId::new(1));
+        let p2 = get_initial_player(PlayerId::new(2));
+        let card = get_card_with_ability(11, Piece::Blue, CardAbility::TakeLowerTierCard);
+        let board = Board::builder()
+            .players(vec![p1, p2])
+            .deck(ProductionTier::One, vec![])
+            .deck(ProductionTier::Two, vec![card])
+            .deck(ProductionTier::Three, vec![])
+            .build()
+            .unwrap();
+        let board = Board::do_action(board, &Action::BuyCard(CardId::new(11), None)).unwrap();
+        assert_eq!(board.action_needed, ActionType::Normal);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
     }
 
     #[test]
-    fn correctly_get_winner() {
-        let board = get_default_board();
-        let player_one = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 16);
-        let player_two = get_player_with_winning_points(PlayerId::new(2), CardId::new(101), 10);
-        let player_three = get_player_with_winning_points(PlayerId::new(3), CardId::new(102), 17);
-        let board = Board {
-            players: vec![player_one, player_two, player_three.clone()],
-            round_type: RoundType::LastRound,
-            ..board
-        };
-        let action_pass = &Action::PassTheTurn;
+    fn reserved_nobles_only_visit_their_owner() {
+        let nobles = vec![
+            Noble::new(NobleId::new(1), Funds::new(0, 0, 2, 0, 0, 0)),
+            Noble::new(NobleId::new(2), Funds::new(0, 0, 0, 4, 0, 0)),
+        ];
+        let p1 = get_initial_player(PlayerId::new(1));
+        let p2 = get_player_producing(PlayerId::new(2), &[Piece::Blue, Piece::Blue]);
+        let board = get_board_with_abilities(
+            vec![p1, p2],
+            &[CardAbility::ReserveNoble, CardAbility::DoubleBonus],
+            nobles,
+        );
 
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
+        let board = Board::do_action(board, &Action::BuyCard(CardId::new(11), None)).unwrap();
+        assert_eq!(board.action_needed, ActionType::ResolveAbility);
+        let reserve = &Action::ResolveAbility(AbilityChoice::ReserveNoble(NobleId::new(1)));
+        let board = Board::do_action(board, reserve).unwrap();
+        assert_eq!(board.players[0].reserved_nobles[0].id, NobleId::new(1));
+        assert_eq!(board.nobles.len(), 1);
+        assert_eq!(board.position_hash(), board.compute_position_hash());
+        assert_eq!(board.validate(), Ok(()));
 
-        assert_eq!(board.winner.unwrap(), Winner::Winner(player_three.id));
+        // The second player would attract the reserved noble, but only sees the one left.
+        assert!(board.eligible_nobles().is_empty());
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+
+        let board = Board::do_action(board, &Action::BuyCard(CardId::new(12), None)).unwrap();
+        assert_eq!(board.action_needed, ActionType::SelectNoble);
+        let board = Board::do_action(board, &Action::SelectNoble(NobleId::new(1))).unwrap();
+        assert!(board.players[0].reserved_nobles.is_empty());
+        assert_eq!(board.players[0].nobles[0].id, NobleId::new(1));
+        assert_eq!(board.position_hash(), board.compute_position_hash());
     }
 
     #[test]
-    fn correctly_get_winner_on_draw_in_points() {
-        let board = get_default_board();
+    fn copy_color_takes_a_color_the_player_produces() {
+        let p1 = get_player_producing(PlayerId::new(1), &[Piece::Red]);
+        let board = get_board_with_abilities(
+            vec![p1, get_initial_player(PlayerId::new(2))],
+            &[CardAbility::CopyColor],
+            vec![],
+        );
 
-        let player_one = get_player_with_winning_points(PlayerId::new(2), CardId::new(101), 16);
-        let player_two = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 15);
-        let mut player_two_cards = player_two.production_cards.clone();
-        player_two_cards.push(get_production_card(CardId::new(103)));
+        let board = Board::do_action(board, &Action::BuyCard(CardId::new(11), None)).unwrap();
+        assert_eq!(board.action_needed, ActionType::ResolveAbility);
+        for piece in [Piece::Green, Piece::Golden] {
+            let copy = &Action::ResolveAbility(AbilityChoice::CopyColor(piece));
+            let result = Board::do_action(board.clone(), copy);
+            assert_eq!(result.unwrap_err(), ActionFail::InvalidAbilityChoice);
+        }
 
-        // Player two now has 16, making it a draw with player one, but also has 2 production cards
-        let player_two = Player {
-            production_cards: player_two_cards,
-            ..player_two
-        };
-        let player_three = get_player_with_winning_points(PlayerId::new(3), CardId::new(102), 15);
-        let board = Board {
-            players: vec![player_one.clone(), player_two, player_three],
-            round_type: RoundType::LastRound,
-            ..board
-        };
-        let action_pass = &Action::PassTheTurn;
+        let copy = &Action::ResolveAbility(AbilityChoice::CopyColor(Piece::Red));
+        let board = Board::do_action(board, copy).unwrap();
+        assert_eq!(
+            board.players[0].get_production(),
+            Funds::new(2, 0, 0, 0, 0, 0)
+        );
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+    }
 
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
+    #[test]
+    fn cannot_buy_a_copy_color_card_without_a_color_to_copy() {
+        let p1 = get_initial_player(PlayerId::new(1));
+        let board = get_board_with_abilities(
+            vec![p1, get_initial_player(PlayerId::new(2))],
+            &[CardAbility::CopyColor],
+            vec![],
+        );
 
-        // player one wins because he has the least amount of cards (game rule)
-        assert_eq!(board.winner.unwrap(), Winner::Winner(player_one.id));
+        let result = Board::do_action(board.clone(), &Action::BuyCard(CardId::new(11), None));
+        assert_eq!(
+            result.unwrap_err(),
+            ActionFail::InvalidBuyOperation(BuyOperationFail::NoColorToCopy)
+        );
+        let copy = &Action::ResolveAbility(AbilityChoice::CopyColor(Piece::Red));
+        let result = Board::do_action(board, copy);
+        assert_eq!(result.unwrap_err(), ActionFail::NoAbilityToResolve);
     }
 
     #[test]
-    fn correctly_get_winners_on_draw() {
-        // This is *not* described in the original game rules: What if there is a draw in points, and amount of cards?
-        // We assume that the players got a draw.
+    #[cfg(feature = "trading-posts")]
+    fn trading_posts_are_claimed_in_track_order() {
+        let trading_posts = vec![
+            TradingPost::new(Funds::new(1, 0, 0, 0, 0, 0), PostPower::BonusPoints(2)),
+            TradingPost::new(Funds::new(0, 1, 0, 0, 0, 0), PostPower::DoubleGolden),
+            TradingPost::new(Funds::new(2, 0, 0, 0, 0, 0), PostPower::PointsPerPost(1)),
+        ];
+        let p1 = get_player_producing(PlayerId::new(1), &[Piece::Red, Piece::Red]);
+        let mut board = Board::builder()
+            .players(vec![p1, get_initial_player(PlayerId::new(2))])
+            .deck(ProductionTier::One, vec![])
+            .deck(ProductionTier::Two, vec![])
+            .deck(ProductionTier::Three, vec![])
+            .trading_posts(trading_posts)
+            .build()
+            .unwrap();
+        let before = board.clone();
+
+        let undo_token = board.apply(&Action::PassTheTurn).unwrap();
+        let powers = board.players[0].powers;
+        assert_eq!(powers.posts_claimed, 1);
+        assert!(!powers.double_golden);
+        assert_eq!(board.players[0].total_victory_points(), 2);
+        assert_eq!(board.position_hash(), board.compute_position_hash());
+        assert_ne!(board, before);
+
+        board.undo(undo_token);
+        assert_same_state(&board, &before);
+    }
 
-        let board = get_default_board();
-        let player_one = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 16);
-        let player_two = get_player_with_winning_points(PlayerId::new(2), CardId::new(101), 17);
-        let player_three = get_player_with_winning_points(PlayerId::new(3), CardId::new(102), 17);
-        let board = Board {
-            players: vec![player_one, player_two.clone(), player_three.clone()],
-            round_type: RoundType::LastRound,
-            ..board
-        };
-        let action_pass = &Action::PassTheTurn;
+    #[test]
+    #[cfg(feature = "trading-posts")]
+    fn trading_post_gives_a_piece_after_buying() {
+        let mut p1 = get_player_with_winning_points(PlayerId::new(1), CardId::new(100), 0);
+        p1.funds = Funds::new(1, 1, 0, 0, 0, 0);
+        p1.powers.piece_after_buying = true;
+        let mut board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![]);
+        let actions = [
+            Action::BuyCard(CardId::new(1), None),
+            Action::TakeBonusPiece(Piece::Blue),
+        ];
+
+        let mut history = vec![];
+        for action in &actions {
+            let before = board.clone();
+            let undo_token = board.apply(action).unwrap();
+            history.push((before, undo_token));
+
+            if board.action_needed == ActionType::TakeBonusPiece {
+                assert_eq!(board.player_to_act(), PlayerId::new(1));
+                let result = Board::do_action(board.clone(), &Action::PassTheTurn);
+                assert_eq!(result.unwrap_err(), ActionFail::YouNeedToTakeBonusPiece);
+                let result =
+                    Board::do_action(board.clone(), &Action::TakeBonusPiece(Piece::Golden));
+                assert_eq!(
+                    result.unwrap_err(),
+                    ActionFail::BonusPieceNotAvailable(Piece::Golden)
+                );
+            }
+        }
+        // The red piece is kept, the card in the tableau produces it.
+        assert_eq!(board.players[0].funds, Funds::new(1, 0, 1, 0, 0, 0));
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+        assert_eq!(board.validate(), Ok(()));
 
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
-        let board = Board::do_action(board, action_pass).unwrap();
+        let result = Board::do_action(board.clone(), &Action::TakeBonusPiece(Piece::Blue));
+        assert_eq!(result.unwrap_err(), ActionFail::NoBonusPieceToTake);
 
-        assert_eq!(
-            board.winner.unwrap(),
-            Winner::Draw(vec![player_two.id, player_three.id])
-        );
+        while let Some((before, undo_token)) = history.pop() {
+            board.undo(undo_token);
+            assert_same_state(&board, &before);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "trading-posts")]
+    fn trading_post_powers_apply_to_collecting_and_buying() {
+        let mut p1 = get_initial_player(PlayerId::new(1));
+        p1.powers.piece_with_two_of_a_kind = true;
+        p1.powers.double_golden = true;
+        p1.funds = Funds::new(0, 0, 0, 0, 0, 1);
+        let board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![]);
+
+        let collect = Action::CollectPieces(vec![Piece::Blue, Piece::Blue, Piece::Red], vec![]);
+        let board = Board::do_action(board, &collect).unwrap();
+        assert_eq!(board.players[0].funds, Funds::new(1, 0, 2, 0, 0, 1));
+
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        // The card costs a red and a green: the red piece and a golden one standing for two.
+        let board = Board::do_action(board, &Action::BuyCard(CardId::new(1), None)).unwrap();
+        assert_eq!(board.players[0].funds, Funds::new(0, 0, 2, 0, 0, 0));
+    }
+
+    #[test]
+    #[cfg(feature = "trading-posts")]
+    fn trading_post_points_count_towards_the_win() {
+        let mut p1 = get_initial_player(PlayerId::new(1));
+        p1.powers.bonus_points = 5;
+        p1.powers.points_per_post = 2;
+        p1.powers.posts_claimed = 5;
+        let board = get_board(vec![p1, get_initial_player(PlayerId::new(2))], vec![]);
+
+        assert_eq!(board.get_score_breakdowns()[0].power_points, 15);
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.round_type, RoundType::LastRound);
+        let board = Board::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.winner, Some(Winner::Winner(PlayerId::new(1))));
     }
 }
@@ -0,0 +1,307 @@
+//! The Strongholds expansion: players put strongholds on market cards to keep them for later.
+//!
+//! A card with a stronghold can only be bought or reserved by the owner of the stronghold, who
+//! gets it back when the card leaves the market. Placing or removing a stronghold takes the
+//! turn. Each card holds a single stronghold here, the printed game lets players stack them and
+//! take over the cards of others.
+//!
+//! The `strongholds` feature compiles the expansion in, and `Rules::strongholds` puts it in play
+//! for a given game: a build with the feature still hosts games without strongholds, whose
+//! `Action::PlaceStronghold` is refused with `StrongholdFail::NotInPlay`.
+
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ActionFail, Board, BoardChange};
+use crate::player::PlayerId;
+use crate::production_card::CardId;
+use crate::zobrist;
+
+pub const STRONGHOLDS_PER_PLAYER: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stronghold {
+    pub card_id: CardId,
+    pub owner: PlayerId,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrongholdFail {
+    NotInPlay,
+    CardNotOnMarket,
+    NoStrongholdLeft,
+    HeldBy(PlayerId),
+    NoStrongholdToRemove,
+}
+
+impl fmt::Display for StrongholdFail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrongholdFail::NotInPlay => write!(f, "Strongholds are not used in this game"),
+            StrongholdFail::CardNotOnMarket => {
+                write!(f, "Strongholds can only be put on cards of the market")
+            }
+            StrongholdFail::NoStrongholdLeft => write!(
+                f,
+                "All your {} strongholds are already on the market",
+                STRONGHOLDS_PER_PLAYER
+            ),
+            StrongholdFail::HeldBy(owner) => {
+                write!(f, "This card is kept by a stronghold of {}", owner)
+            }
+            StrongholdFail::NoStrongholdToRemove => {
+                write!(f, "You have no stronghold on this card")
+            }
+        }
+    }
+}
+
+impl Error for StrongholdFail {}
+
+impl Board {
+    pub fn get_strongholds(&self) -> &[Stronghold] {
+        &self.strongholds
+    }
+
+    pub fn get_stronghold_on(&self, card_id: &CardId) -> Option<&Stronghold> {
+        self.strongholds
+            .iter()
+            .find(|stronghold| &stronghold.card_id == card_id)
+    }
+
+    pub fn get_strongholds_left(&self, player_id: PlayerId) -> usize {
+        let placed = self
+            .strongholds
+            .iter()
+            .filter(|stronghold| stronghold.owner == player_id)
+            .count();
+        STRONGHOLDS_PER_PLAYER.saturating_sub(placed)
+    }
+
+    /// Fails when a stronghold of another player keeps the card.
+    pub(super) fn check_stronghold(&self, card_id: &CardId) -> Result<(), ActionFail> {
+        match self.get_stronghold_on(card_id) {
+            Some(stronghold) if stronghold.owner != self.player_to_act() => Err(
+                ActionFail::InvalidStronghold(StrongholdFail::HeldBy(stronghold.owner)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn can_place_stronghold(&self) -> bool {
+        self.rules.strongholds
+            && self.get_strongholds_left(self.player_to_act()) > 0
+            && self
+                .cards_for_sale
+                .values()
+                .flatten()
+                .flatten()
+                .any(|card| self.get_stronghold_on(&card.uid).is_none())
+    }
+
+    /// Gives the stronghold on a card leaving the market back to its owner.
+    pub(super) fn release_stronghold(&mut self, card_id: &CardId) -> Option<(usize, Stronghold)> {
+        let index = self
+            .strongholds
+            .iter()
+            .position(|stronghold| &stronghold.card_id == card_id)?;
+        let stronghold = self.strongholds.remove(index);
+        self.position_hash ^= zobrist::stronghold(&stronghold);
+        Some((index, stronghold))
+    }
+
+    pub(super) fn restore_stronghold(&mut self, index: usize, stronghold: Stronghold) {
+        self.strongholds.insert(index, stronghold);
+    }
+
+    pub(super) fn action_place_stronghold(
+        &mut self,
+        card_id: &CardId,
+    ) -> Result<BoardChange, ActionFail> {
+        if !self.rules.strongholds {
+            return Err(ActionFail::InvalidStronghold(StrongholdFail::NotInPlay));
+        }
+        if self.find_on_market(card_id).is_none() {
+            return Err(ActionFail::InvalidStronghold(
+                StrongholdFail::CardNotOnMarket,
+            ));
+        }
+        if let Some(stronghold) = self.get_stronghold_on(card_id) {
+            return Err(ActionFail::InvalidStronghold(StrongholdFail::HeldBy(
+                stronghold.owner,
+            )));
+        }
+        let owner = self.player_to_act();
+        if self.get_strongholds_left(owner) == 0 {
+            return Err(ActionFail::InvalidStronghold(
+                StrongholdFail::NoStrongholdLeft,
+            ));
+        }
+
+        let stronghold = Stronghold {
+            card_id: *card_id,
+            owner,
+        };
+        self.position_hash ^= zobrist::stronghold(&stronghold);
+        self.strongholds.push(stronghold);
+
+        Ok(BoardChange::PlacedStronghold)
+    }
+
+    pub(super) fn action_remove_stronghold(
+        &mut self,
+        card_id: &CardId,
+    ) -> Result<BoardChange, ActionFail> {
+        let is_own = self
+            .get_stronghold_on(card_id)
+            .is_some_and(|stronghold| stronghold.owner == self.player_to_act());
+        if !is_own {
+            return Err(ActionFail::InvalidStronghold(
+                StrongholdFail::NoStrongholdToRemove,
+            ));
+        }
+
+        let (index, stronghold) = self
+            .release_stronghold(card_id)
+            .expect("The stronghold was found above");
+
+        Ok(BoardChange::RemovedStronghold(index, stronghold))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bank::Funds;
+    use crate::board::{Action, ProductionTier};
+    use crate::piece::Piece;
+    use crate::player::Player;
+    use crate::production_card::{Identifiable, ProductionCard};
+    use crate::rules::Rules;
+
+    fn get_board() -> Board {
+        let players = (1..=2)
+            .map(|id| Player::new(PlayerId::new(id), Funds::default(), vec![], vec![]))
+            .collect();
+        let deck = (1..=6)
+            .rev()
+            .map(|id| {
+                let card = ProductionCard::new(Funds::default(), Piece::Red, None);
+                Identifiable::new(card, CardId::new(id))
+            })
+            .collect();
+        Board::builder()
+            .players(players)
+            .bank(Funds::new(7, 7, 7, 7, 7, 5))
+            .deck(ProductionTier::One, deck)
+            .deck(ProductionTier::Two, vec![])
+            .deck(ProductionTier::Three, vec![])
+            .rules(Rules {
+                strongholds: true,
+                ..Rules::default()
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn play(board: Board, actions: &[Action]) -> Board {
+        actions.iter().fold(board, |board, action| {
+            Board::do_action(board, action).unwrap()
+        })
+    }
+
+    #[test]
+    fn strongholds_keep_cards_for_their_owner() {
+        let board = play(get_board(), &[Action::PlaceStronghold(CardId::new(1))]);
+        assert_eq!(board.get_strongholds_left(PlayerId::new(1)), 2);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+
+        let held = ActionFail::InvalidStronghold(StrongholdFail::HeldBy(PlayerId::new(1)));
+        for action in [
+            Action::BuyCard(CardId::new(1), None),
+            Action::ReserveCardFromBoard(CardId::new(1)),
+            Action::PlaceStronghold(CardId::new(1)),
+        ] {
+            let result = Board::do_action(board.clone(), &action);
+            assert_eq!(result.unwrap_err(), held);
+        }
+
+        let board = play(
+            board,
+            &[Action::PassTheTurn, Action::BuyCard(CardId::new(1), None)],
+        );
+        assert!(board.get_strongholds().is_empty());
+        assert_eq!(board.get_strongholds_left(PlayerId::new(1)), 3);
+        assert_eq!(board.validate(), Ok(()));
+    }
+
+    #[test]
+    fn players_have_a_limited_number_of_strongholds() {
+        let place = |id| Action::PlaceStronghold(CardId::new(id));
+        let board = play(
+            get_board(),
+            &[
+                place(1),
+                Action::PassTheTurn,
+                place(2),
+                Action::PassTheTurn,
+                place(3),
+                Action::PassTheTurn,
+            ],
+        );
+        let result = Board::do_action(board.clone(), &place(4));
+        assert_eq!(
+            result.unwrap_err(),
+            ActionFail::InvalidStronghold(StrongholdFail::NoStrongholdLeft)
+        );
+
+        let board = play(board, &[Action::RemoveStronghold(CardId::new(2))]);
+        assert_eq!(board.get_strongholds_left(PlayerId::new(1)), 1);
+        let result = Board::do_action(board, &Action::RemoveStronghold(CardId::new(1)));
+        assert_eq!(
+            result.unwrap_err(),
+            ActionFail::InvalidStronghold(StrongholdFail::NoStrongholdToRemove)
+        );
+    }
+
+    #[test]
+    fn strongholds_are_only_in_play_when_the_rules_say_so() {
+        let board = Board {
+            rules: Rules::default(),
+            ..get_board()
+        };
+        let result = Board::do_action(board, &Action::PlaceStronghold(CardId::new(1)));
+        assert_eq!(
+            result.unwrap_err(),
+            ActionFail::InvalidStronghold(StrongholdFail::NotInPlay)
+        );
+    }
+
+    #[test]
+    fn undo_restores_strongholds() {
+        let mut board = play(get_board(), &[Action::PlaceStronghold(CardId::new(2))]);
+        let actions = [
+            Action::PlaceStronghold(CardId::new(3)),
+            Action::BuyCard(CardId::new(2), None),
+            Action::RemoveStronghold(CardId::new(3)),
+        ];
+
+        let mut history = vec![];
+        for action in &actions {
+            let before = board.clone();
+            let undo_token = board.apply(action).unwrap();
+            assert_eq!(board.position_hash(), board.compute_position_hash());
+            history.push((before, undo_token));
+        }
+
+        while let Some((before, undo_token)) = history.pop() {
+            board.undo(undo_token);
+            assert_eq!(board.get_strongholds(), before.get_strongholds());
+            assert_eq!(board.position_hash(), before.position_hash());
+            assert_eq!(board, before);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Every card of the game, with an id that does not depend on how the decks were shuffled.
+//!
+//! Canonical ids follow the order of the cards in this catalog: 1 to 40 for the first tier of the
+//! original game, 41 to 70 for the second and 71 to 90 for the third. The cards standing in for
+//! the Orient expansion, see `orient::get_stand_in_orient_cards`, start at 101.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::bank::Funds;
+use crate::board::ProductionTier;
+use crate::piece::Piece;
+use crate::production_card::{CanonicalId, ProductionCard};
+
+/// Canonical id of the first card of `orient::get_stand_in_orient_cards`.
+pub const ORIENT_FIRST_ID: u16 = 101;
+
+/// The cards of the original game by tier, each with its canonical id.
+pub fn get_original_cards() -> HashMap<ProductionTier, Vec<ProductionCard>> {
+    numbered(
+        1,
+        [
+            (ProductionTier::One, get_tier_one_cards()),
+            (ProductionTier::Two, get_tier_two_cards()),
+            (ProductionTier::Three, get_tier_three_cards()),
+        ],
+    )
+}
+
+/// The card with this canonical id, and its tier.
+pub fn card(id: CanonicalId) -> Option<(ProductionTier, ProductionCard)> {
+    static CATALOG: OnceLock<HashMap<CanonicalId, (ProductionTier, ProductionCard)>> =
+        OnceLock::new();
+    CATALOG.get_or_init(index_catalog).get(&id).cloned()
+}
+
+/// Every card of the catalog, keyed by its canonical id.
+fn index_catalog() -> HashMap<CanonicalId, (ProductionTier, ProductionCard)> {
+    let original = get_original_cards();
+    #[cfg(feature = "orient")]
+    let expansions = crate::orient::get_stand_in_orient_cards();
+    #[cfg(not(feature = "orient"))]
+    let expansions = HashMap::new();
+
+    original
+        .into_iter()
+        .chain(expansions)
+        .flat_map(|(tier, cards)| cards.into_iter().map(move |card| (tier, card)))
+        .filter_map(|(tier, card)| card.canonical_id.map(|id| (id, (tier, card))))
+        .collect()
+}
+
+/// Gives the cards their canonical ids, from `first_id` on, tier by tier.
+pub(crate) fn numbered<const N: usize>(
+    first_id: u16,
+    tiers: [(ProductionTier, Vec<ProductionCard>); N],
+) -> HashMap<ProductionTier, Vec<ProductionCard>> {
+    let mut next_id = first_id;
+    tiers
+        .into_iter()
+        .map(|(tier, cards)| {
+            let cards = cards
+                .into_iter()
+                .map(|card| {
+                    next_id += 1;
+                    card.with_canonical_id(CanonicalId::new(next_id - 1))
+                })
+                .collect();
+            (tier, cards)
+        })
+        .collect()
+}
+
+fn get_tier_one_cards() -> Vec<ProductionCard> {
+    vec![
+        ProductionCard::new(Funds::new(0, 0, 2, 0, 2, 0), Piece::Green, None),
+        ProductionCard::new(Funds::new(1, 0, 1, 2, 1, 0), Piece::Green, None),
+        ProductionCard::new(Funds::new(1, 0, 1, 1, 1, 0), Piece::Green, None),
+        ProductionCard::new(Funds::new(0, 3, 0, 0, 0, 0), Piece::Brown, None),
+        ProductionCard::new(Funds::new(0, 0, 4, 0, 0, 0), Piece::Brown, Some(1)),
+        ProductionCard::new(Funds::new(1, 1, 2, 0, 1, 0), Piece::Brown, None),
+        ProductionCard::new(Funds::new(1, 3, 1, 0, 0, 0), Piece::Blue, None),
+        ProductionCard::new(Funds::new(2, 1, 0, 1, 1, 0), Piece::Blue, None),
+        ProductionCard::new(Funds::new(0, 2, 0, 2, 0, 0), Piece::Blue, None),
+        ProductionCard::new(Funds::new(0, 0, 2, 2, 0, 0), Piece::White, None),
+        ProductionCard::new(Funds::new(0, 0, 0, 0, 3, 0), Piece::Red, None),
+        ProductionCard::new(Funds::new(0, 0, 0, 4, 0, 0), Piece::Green, Some(1)),
+        ProductionCard::new(Funds::new(0, 1, 3, 0, 1, 0), Piece::Green, None),
+        ProductionCard::new(Funds::new(2, 0, 1, 2, 0, 0), Piece::Green, None),
+        ProductionCard::new(Funds::new(1, 0, 0, 3, 1, 0), Piece::Red, None),
+        ProductionCard::new(Funds::new(0, 0, 0, 0, 4, 0), Piece::Red, Some(1)),
+        ProductionCard::new(Funds::new(0, 0, 3, 0, 0, 0), Piece::White, None),
+        ProductionCard::new(Funds::new(2, 2, 0, 0, 0, 0), Piece::Brown, None),
+        ProductionCard::new(Funds::new(3, 1, 0, 1, 0, 0), Piece::Brown, None),
+        ProductionCard::new(Funds::new(0, 2, 0, 0, 2, 0), Piece::Brown, None),
+        ProductionCard::new(Funds::new(1, 1, 0, 1, 1, 0), Piece::Blue, None),
+        ProductionCard::new(Funds::new(4, 0, 0, 0, 0, 0), Piece::Blue, Some(1)),
+        ProductionCard::new(Funds::new(0, 1, 0, 2, 2, 0), Piece::Red, None),
+        ProductionCard::new(Funds::new(2, 0, 0, 0, 2, 0), Piece::Red, None),
+        ProductionCard::new(Funds::new(0, 1, 2, 0, 0, 0), Piece::Red, None),
+        ProductionCard::new(Funds::new(1, 0, 2, 0, 2, 0), Piece::Brown, None),
+        ProductionCard::new(Funds::new(2, 2, 0, 0, 1, 0), Piece::Blue, None),
+        ProductionCard::new(Funds::new(0, 0, 0, 3, 0, 0), Piece::Blue, None),
+        ProductionCard::new(Funds::new(0, 0, 2, 1, 2, 0), Piece::White, None),
+        ProductionCard::new(Funds::new(1, 1, 1, 1, 0, 0), Piece::White, None),
+        ProductionCard::new(Funds::new(0, 0, 0, 2, 1, 0), Piece::Blue, None),
+        ProductionCard::new(Funds::new(1, 1, 1, 0, 1, 0), Piece::Brown, None),
+        ProductionCard::new(Funds::new(2, 0, 2, 0, 0, 0), Piece::Green, None),
+        ProductionCard::new(Funds::new(3, 0, 0, 0, 0, 0), Piece::Green, None),
+        ProductionCard::new(Funds::new(1, 2, 1, 1, 0, 0), Piece::White, None),
+        ProductionCard::new(Funds::new(2, 0, 0, 1, 0, 0), Piece::White, None),
+        ProductionCard::new(Funds::new(0, 0, 1, 1, 3, 0), Piece::White, None),
+        ProductionCard::new(Funds::new(0, 4, 0, 0, 0, 0), Piece::White, Some(1)),
+        ProductionCard::new(Funds::new(0, 1, 1, 1, 2, 0), Piece::Red, None),
+        ProductionCard::new(Funds::new(0, 1, 1, 1, 1, 0), Piece::Red, None),
+    ]
+}
+fn get_tier_two_cards() -> Vec<ProductionCard> {
+    vec![
+        ProductionCard::new(Funds::new(0, 3, 0, 2, 3, 0), Piece::Brown, Some(1)),
+        ProductionCard::new(Funds::new(3, 2, 0, 0, 3, 0), Piece::Green, Some(1)),
+        ProductionCard::new(Funds::new(2, 0, 3, 3, 0, 0), Piece::Red, Some(1)),
+        ProductionCard::new(Funds::new(0, 0, 6, 0, 0, 0), Piece::Blue, Some(3)),
+        ProductionCard::new(Funds::new(1, 0, 0, 4, 2, 0), Piece::Blue, Some(2)),
+        ProductionCard::new(Funds::new(3, 0, 3, 0, 2, 0), Piece::White, Some(1)),
+        ProductionCard::new(Funds::new(0, 0, 2, 1, 4, 0), Piece::Green, Some(2)),
+        ProductionCard::new(Funds::new(0, 0, 5, 0, 0, 0), Piece::Blue, Some(2)),
+        ProductionCard::new(Funds::new(0, 0, 0, 0, 5, 0), Piece::Brown, Some(2)),
+        ProductionCard::new(Funds::new(2, 0, 0, 3, 2, 0), Piece::Red, Some(1)),
+        ProductionCard::new(Funds::new(0, 0, 0, 0, 6, 0), Piece::White, Some(3)),
+        ProductionCard::new(Funds::new(0, 2, 4, 0, 1, 0), Piece::Red, Some(2)),
+        ProductionCard::new(Funds::new(5, 0, 0, 0, 0, 0), Piece::White, Some(2)),
+        ProductionCard::new(Funds::new(0, 6, 0, 0, 0, 0), Piece::Green, Some(3)),
+        ProductionCard::new(Funds::new(0, 5, 0, 0, 0, 0), Piece::Green, Some(2)),
+        ProductionCard::new(Funds::new(0, 0, 0, 5, 0, 0), Piece::Red, Some(2)),
+        ProductionCard::new(Funds::new(0, 2, 2, 0, 3, 0), Piece::Brown, Some(1)),
+        ProductionCard::new(Funds::new(0, 0, 0, 6, 0, 0), Piece::Brown, Some(3)),
+        ProductionCard::new(Funds::new(3, 5, 0, 0, 0, 0), Piece::Brown, Some(2)),
+        ProductionCard::new(Funds::new(0, 3, 5, 0, 0, 0), Piece::Green, Some(2)),
+        ProductionCard::new(Funds::new(0, 3, 2, 3, 0, 0), Piece::Blue, Some(1)),
+        ProductionCard::new(Funds::new(2, 2, 2, 0, 0, 0), Piece::Blue, Some(1)),
+        ProductionCard::new(Funds::new(0, 0, 3, 0, 5, 0), Piece::Blue, Some(2)),
+        ProductionCard::new(Funds::new(0, 0, 3, 2, 2, 0), Piece::Green, Some(1)),
+        ProductionCard::new(Funds::new(5, 0, 0, 3, 0, 0), Piece::White, Some(2)),
+        ProductionCard::new(Funds::new(4, 1, 0, 2, 0, 0), Piece::White, Some(2)),
+        ProductionCard::new(Funds::new(2, 4, 0, 1, 0, 0), Piece::Brown, Some(2)),
+        ProductionCard::new(Funds::new(2, 3, 0, 2, 0, 0), Piece::White, Some(1)),
+        ProductionCard::new(Funds::new(6, 0, 0, 0, 0, 0), Piece::Red, Some(3)),
+        ProductionCard::new(Funds::new(0, 0, 0, 5, 3, 0), Piece::Red, Some(2)),
+    ]
+}
+fn get_tier_three_cards() -> Vec<ProductionCard> {
+    vec![
+        ProductionCard::new(Funds::new(3, 0, 3, 3, 5, 0), Piece::Green, Some(3)),
+        ProductionCard::new(Funds::new(3, 3, 0, 5, 3, 0), Piece::Blue, Some(3)),
+        ProductionCard::new(Funds::new(0, 3, 6, 0, 3, 0), Piece::Green, Some(4)),
+        ProductionCard::new(Funds::new(0, 0, 0, 7, 3, 0), Piece::White, Some(5)),
+        ProductionCard::new(Funds::new(7, 0, 0, 0, 0, 0), Piece::Brown, Some(4)),
+        ProductionCard::new(Funds::new(6, 3, 0, 3, 0, 0), Piece::Brown, Some(4)),
+        ProductionCard::new(Funds::new(0, 0, 3, 3, 6, 0), Piece::Blue, Some(4)),
+        ProductionCard::new(Funds::new(0, 7, 0, 0, 0, 0), Piece::Red, Some(4)),
+        ProductionCard::new(Funds::new(0, 3, 5, 3, 3, 0), Piece::Red, Some(3)),
+        ProductionCard::new(Funds::new(3, 6, 3, 0, 0, 0), Piece::Red, Some(4)),
+        ProductionCard::new(Funds::new(3, 0, 0, 6, 3, 0), Piece::White, Some(4)),
+        ProductionCard::new(Funds::new(3, 5, 3, 0, 3, 0), Piece::Brown, Some(3)),
+        ProductionCard::new(Funds::new(0, 0, 3, 0, 7, 0), Piece::Blue, Some(5)),
+        ProductionCard::new(Funds::new(3, 7, 0, 0, 0, 0), Piece::Red, Some(5)),
+        ProductionCard::new(Funds::new(0, 3, 7, 0, 0, 0), Piece::Green, Some(5)),
+        ProductionCard::new(Funds::new(0, 0, 0, 7, 0, 0), Piece::White, Some(4)),
+        ProductionCard::new(Funds::new(0, 0, 7, 0, 0, 0), Piece::Green, Some(4)),
+        ProductionCard::new(Funds::new(5, 3, 3, 3, 0, 0), Piece::White, Some(3)),
+        ProductionCard::new(Funds::new(0, 0, 0, 0, 7, 0), Piece::Blue, Some(4)),
+        ProductionCard::new(Funds::new(7, 0, 0, 3, 0, 0), Piece::Brown, Some(5)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_ids_follow_the_catalog() {
+        let cards = get_original_cards();
+        assert_eq!(cards[&ProductionTier::One].len(), 40);
+        assert_eq!(
+            cards[&ProductionTier::Three][0].canonical_id,
+            Some(CanonicalId::new(71))
+        );
+
+        let (tier, card) = card(CanonicalId::new(74)).unwrap();
+        assert_eq!(tier, ProductionTier::Three);
+        assert_eq!(card.produces, Piece::White);
+        assert_eq!(card.victory_points, Some(5));
+        assert_eq!(card.cost, Funds::new(0, 0, 0, 7, 3, 0));
+        assert_eq!(card, get_original_cards()[&ProductionTier::Three][3]);
+    }
+
+    #[test]
+    fn unknown_ids_are_not_in_the_catalog() {
+        for id in [0, 91, ORIENT_FIRST_ID - 1, u16::MAX] {
+            assert!(card(CanonicalId::new(id)).is_none(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn tiers_change_at_their_first_id() {
+        let cards = get_original_cards();
+        for (id, tier, index) in [
+            (1, ProductionTier::One, 0),
+            (40, ProductionTier::One, 39),
+            (41, ProductionTier::Two, 0),
+            (70, ProductionTier::Two, 29),
+            (71, ProductionTier::Three, 0),
+            (90, ProductionTier::Three, 19),
+        ] {
+            let (found_tier, found) = card(CanonicalId::new(id)).unwrap();
+            assert_eq!(found_tier, tier, "{}", id);
+            assert_eq!(found, cards[&tier][index], "{}", id);
+        }
+    }
+}
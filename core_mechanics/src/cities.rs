@@ -0,0 +1,508 @@
+//! The Cities expansion: city tiles replace the nobles and decide who wins.
+//!
+//! A player qualifies for a city when, at the end of their turn, they have its points and its
+//! production. The round is then played to its end, so that everyone had as many turns, and the
+//! qualified player with the most points wins, the one with fewest production cards on a tie.
+//!
+//! `get_cities_board` deals the tiles of `get_cities`, `try_get_cities_board_with` other ones.
+
+use rand::seq::SliceRandom;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bank::Funds;
+use crate::board::{Action, ActionFail, Board, SetupError, Winner};
+use crate::original_game::get_original_game_builder;
+use crate::player::{Player, PlayerId};
+use crate::rules::Rules;
+
+pub const CITIES_PER_GAME: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CityId {
+    id: u32,
+}
+
+impl CityId {
+    pub fn new(id: u32) -> Self {
+        Self { id }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl fmt::Display for CityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "city {}", self.id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct City {
+    pub id: CityId,
+    pub points: u8,
+    /// Production of specific colors.
+    pub production: Funds,
+    /// Production of a single color of the player's choice, other than the ones in
+    /// `production`.
+    pub any_single_color: u8,
+}
+
+impl City {
+    pub fn new(id: CityId, points: u8, production: Funds, any_single_color: u8) -> Self {
+        Self {
+            id,
+            points,
+            production,
+            any_single_color,
+        }
+    }
+
+    pub fn is_qualified(&self, player: &Player) -> bool {
+        if player.total_victory_points() < u16::from(self.points) {
+            return false;
+        }
+
+        let production = player.get_production();
+        if (production - self.production).is_err() {
+            return false;
+        }
+
+        self.any_single_color == 0
+            || production.iter().any(|(piece, quantity)| {
+                !piece.is_wildcard()
+                    && self.production[piece] == 0
+                    && quantity >= self.any_single_color
+            })
+    }
+}
+
+/// A `Board` played with cities instead of nobles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CitiesBoard {
+    board: Board,
+    cities: Vec<City>,
+    qualified: Vec<(PlayerId, CityId)>,
+    winner: Option<Winner>,
+}
+
+impl CitiesBoard {
+    /// The board should have no nobles, and `Rules::disable_winning_points` set.
+    pub fn new(board: Board, cities: Vec<City>) -> Result<Self, SetupError> {
+        if board.iter_nobles().next().is_some() {
+            return Err(SetupError::NoblesWithCities);
+        }
+        if !board.get_rules().disable_winning_points {
+            return Err(SetupError::WinningPointsWithCities);
+        }
+
+        Ok(Self {
+            board,
+            cities,
+            qualified: vec![],
+            winner: None,
+        })
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_cities(&self) -> &[City] {
+        &self.cities
+    }
+
+    /// Players who qualified for a city, in the order they did, with the first city they
+    /// qualified for.
+    pub fn get_qualified(&self) -> &[(PlayerId, CityId)] {
+        &self.qualified
+    }
+
+    /// Known at the end of the round a player qualified for a city. When the board ends before,
+    /// on its maximum rounds or a stalemate, the qualified players win, or the board's winner
+    /// when nobody qualified.
+    pub fn get_winner(&self) -> Option<&Winner> {
+        self.winner.as_ref()
+    }
+
+    pub fn do_action(cities_board: CitiesBoard, action: &Action) -> Result<Self, ActionFail> {
+        if cities_board.winner.is_some() {
+            return Err(ActionFail::GameIsOver);
+        }
+
+        let mut cities_board = cities_board;
+        let seat = cities_board.board.get_player_turn();
+
+        cities_board.board = Board::do_action(cities_board.board, action)?;
+
+        let turn_is_over = cities_board.board.get_player_turn() != seat;
+        if !turn_is_over {
+            return Ok(cities_board);
+        }
+
+        let player = cities_board
+            .board
+            .get_players()
+            .nth(seat)
+            .expect("The player who just played should exist");
+        let already_qualified = cities_board
+            .qualified
+            .iter()
+            .any(|(player_id, _)| *player_id == player.id);
+        if !already_qualified {
+            if let Some(city) = cities_board.cities.iter().find(|c| c.is_qualified(player)) {
+                cities_board.qualified.push((player.id, city.id));
+            }
+        }
+
+        let round_is_over =
+            cities_board.board.get_player_turn() == cities_board.board.get_starting_player();
+        let board_is_over = cities_board.board.get_end_reason().is_some();
+        if (round_is_over && !cities_board.qualified.is_empty()) || board_is_over {
+            cities_board.winner = cities_board
+                .get_winner_among_qualified()
+                .or_else(|| cities_board.board.get_winner().cloned());
+        }
+
+        Ok(cities_board)
+    }
+
+    fn get_winner_among_qualified(&self) -> Option<Winner> {
+        let qualified: Vec<&Player> = self
+            .board
+            .get_players()
+            .filter(|p| self.qualified.iter().any(|(id, _)| *id == p.id))
+            .collect();
+
+        let max_points = qualified.iter().map(|p| p.total_victory_points()).max()?;
+        let possible_winners: Vec<&Player> = qualified
+            .into_iter()
+            .filter(|p| p.total_victory_points() == max_points)
+            .collect();
+
+        let least_amount_of_cards = possible_winners
+            .iter()
+            .map(|p| p.production_cards.len())
+            .min()?;
+        let winners: Vec<PlayerId> = possible_winners
+            .into_iter()
+            .filter(|p| p.production_cards.len() == least_amount_of_cards)
+            .map(|p| p.id)
+            .collect();
+
+        if winners.len() == 1 {
+            return Some(Winner::Winner(winners[0]));
+        }
+
+        Some(Winner::Draw(winners))
+    }
+}
+
+/// # Panics
+///
+/// Panics if `n_of_players` is not between 2 and 4, see `try_get_cities_board`.
+pub fn get_cities_board(n_of_players: u8) -> CitiesBoard {
+    try_get_cities_board(n_of_players).unwrap_or_else(|_| {
+        panic!(
+            "The Cities expansion is only defined for 2 to 4 players. '{:?}' given",
+            n_of_players
+        )
+    })
+}
+
+/// The original game, with `CITIES_PER_GAME` random cities in place of the nobles.
+pub fn try_get_cities_board(n_of_players: u8) -> Result<CitiesBoard, SetupError> {
+    try_get_cities_board_with(n_of_players, &get_cities())
+}
+
+/// Same as `try_get_cities_board`, with the cities picked among `cities`.
+pub fn try_get_cities_board_with(
+    n_of_players: u8,
+    cities: &[City],
+) -> Result<CitiesBoard, SetupError> {
+    let rules = Rules {
+        pass_only_when_stuck: true,
+        disable_winning_points: true,
+        ..Rules::default()
+    };
+    let board = get_original_game_builder(n_of_players)?
+        .rules(rules)
+        .build()?;
+
+    let rng = &mut rand::thread_rng();
+    let cities = cities
+        .choose_multiple(rng, CITIES_PER_GAME)
+        .cloned()
+        .collect();
+
+    CitiesBoard::new(board, cities)
+}
+
+/// The city tiles, both sides of each printed tile. The requirements follow the printed tiles but
+/// were transcribed without a copy of the game at hand, and are still to be checked against it.
+pub fn get_cities() -> Vec<City> {
+    vec![
+        City::new(CityId::new(1), 13, Funds::new(0, 0, 0, 0, 0, 0), 0),
+        City::new(CityId::new(2), 11, Funds::new(3, 3, 3, 0, 0, 0), 0),
+        City::new(CityId::new(3), 11, Funds::new(0, 0, 3, 3, 3, 0), 0),
+        City::new(CityId::new(4), 11, Funds::new(3, 0, 0, 3, 3, 0), 0),
+        City::new(CityId::new(5), 12, Funds::new(0, 0, 0, 0, 0, 0), 5),
+        City::new(CityId::new(6), 12, Funds::new(2, 0, 0, 0, 0, 0), 4),
+        City::new(CityId::new(7), 12, Funds::new(0, 2, 0, 0, 0, 0), 4),
+        City::new(CityId::new(8), 12, Funds::new(0, 0, 2, 0, 0, 0), 4),
+        City::new(CityId::new(9), 12, Funds::new(0, 0, 0, 2, 0, 0), 4),
+        City::new(CityId::new(10), 12, Funds::new(0, 0, 0, 0, 2, 0), 4),
+        City::new(CityId::new(11), 11, Funds::new(1, 1, 1, 1, 1, 0), 3),
+        City::new(CityId::new(12), 15, Funds::new(0, 0, 0, 0, 0, 0), 0),
+        City::new(CityId::new(13), 13, Funds::new(0, 0, 0, 0, 0, 0), 3),
+        City::new(CityId::new(14), 14, Funds::new(1, 1, 1, 1, 1, 0), 0),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    use crate::board::{EndReason, ProductionTier};
+    use crate::noble::{Noble, NobleId};
+    use crate::piece::Piece;
+    use crate::production_card::{CardId, Identifiable, ProductionCard};
+
+    fn get_player(id: u32, production: &[(Piece, u8)]) -> Player {
+        let production_cards = production
+            .iter()
+            .enumerate()
+            .map(|(i, (piece, points))| {
+                let card = ProductionCard::new(Funds::default(), *piece, Some(*points));
+                Identifiable::new(card, CardId::new(id * 50 + i as u32))
+            })
+            .collect();
+        Player::new(
+            PlayerId::new(id),
+            Funds::default(),
+            production_cards,
+            vec![],
+        )
+    }
+
+    fn get_cities_test_board(players: Vec<Player>, cities: Vec<City>) -> CitiesBoard {
+        let rules = Rules {
+            disable_winning_points: true,
+            ..Rules::default()
+        };
+        let board = Board::builder()
+            .players(players)
+            .bank(Funds::new(7, 7, 7, 7, 7, 5))
+            .deck(ProductionTier::One, vec![])
+            .deck(ProductionTier::Two, vec![])
+            .deck(ProductionTier::Three, vec![])
+            .rules(rules)
+            .build()
+            .unwrap();
+        CitiesBoard::new(board, cities).unwrap()
+    }
+
+    #[test]
+    fn city_requires_points_and_production() {
+        let city = City::new(CityId::new(1), 10, Funds::new(2, 0, 0, 0, 0, 0), 0);
+
+        let player = get_player(1, &[(Piece::Red, 5), (Piece::Red, 5)]);
+        assert!(city.is_qualified(&player));
+
+        let player = get_player(1, &[(Piece::Red, 5), (Piece::Blue, 5)]);
+        assert!(!city.is_qualified(&player));
+
+        let player = get_player(1, &[(Piece::Red, 5), (Piece::Red, 4)]);
+        assert!(!city.is_qualified(&player));
+    }
+
+    #[test]
+    fn any_single_color_must_be_another_color() {
+        let city = City::new(CityId::new(1), 0, Funds::new(1, 0, 0, 0, 0, 0), 2);
+
+        let player = get_player(1, &[(Piece::Red, 0), (Piece::Red, 0), (Piece::Red, 0)]);
+        assert!(!city.is_qualified(&player));
+
+        let player = get_player(1, &[(Piece::Red, 0), (Piece::Blue, 0), (Piece::Green, 0)]);
+        assert!(!city.is_qualified(&player));
+
+        let player = get_player(1, &[(Piece::Red, 0), (Piece::White, 0), (Piece::White, 0)]);
+        assert!(city.is_qualified(&player));
+    }
+
+    #[test]
+    fn the_round_ends_before_the_winner_is_known() {
+        let city = City::new(CityId::new(1), 10, Funds::default(), 0);
+        let players = vec![
+            get_player(1, &[(Piece::Red, 10)]),
+            get_player(2, &[]),
+            get_player(3, &[]),
+        ];
+        let board = get_cities_test_board(players, vec![city]);
+
+        let board = CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.get_qualified(), &[(PlayerId::new(1), CityId::new(1))]);
+        assert_eq!(board.get_winner(), None);
+
+        let board = CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.get_winner(), None);
+        let board = CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap();
+        assert_eq!(board.get_winner(), Some(&Winner::Winner(PlayerId::new(1))));
+    }
+
+    #[test]
+    fn players_qualifying_in_the_same_round_are_tie_broken() {
+        let city = City::new(CityId::new(1), 10, Funds::default(), 0);
+        let players = vec![
+            get_player(1, &[(Piece::Red, 5), (Piece::Red, 6)]),
+            get_player(2, &[(Piece::Blue, 11)]),
+            get_player(3, &[(Piece::Blue, 12)]),
+        ];
+        let board = get_cities_test_board(players, vec![city]);
+
+        let board = (0..3).fold(board, |board, _| {
+            CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap()
+        });
+        assert_eq!(board.get_qualified().len(), 3);
+        assert_eq!(board.get_winner(), Some(&Winner::Winner(PlayerId::new(3))));
+
+        let city = City::new(CityId::new(1), 10, Funds::default(), 0);
+        let players = vec![
+            get_player(1, &[(Piece::Red, 5), (Piece::Red, 6)]),
+            get_player(2, &[(Piece::Blue, 11)]),
+            get_player(3, &[]),
+        ];
+        let board = get_cities_test_board(players, vec![city]);
+
+        let board = (0..3).fold(board, |board, _| {
+            CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap()
+        });
+        assert_eq!(board.get_winner(), Some(&Winner::Winner(PlayerId::new(2))));
+    }
+
+    #[test]
+    fn winning_points_alone_do_not_end_the_game() {
+        let city = City::new(CityId::new(1), 20, Funds::default(), 0);
+        let players = vec![get_player(1, &[(Piece::Red, 16)]), get_player(2, &[])];
+        let board = get_cities_test_board(players, vec![city]);
+
+        let board = (0..4).fold(board, |board, _| {
+            CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap()
+        });
+        assert_eq!(board.get_board().get_winner(), None);
+        assert_eq!(board.get_winner(), None);
+    }
+
+    #[test]
+    fn no_action_is_accepted_once_a_city_is_won() {
+        let city = City::new(CityId::new(1), 10, Funds::default(), 0);
+        let players = vec![get_player(1, &[(Piece::Red, 10)]), get_player(2, &[])];
+        let board = get_cities_test_board(players, vec![city]);
+
+        let board = (0..2).fold(board, |board, _| {
+            CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap()
+        });
+        assert_eq!(board.get_winner(), Some(&Winner::Winner(PlayerId::new(1))));
+        assert_eq!(
+            CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap_err(),
+            ActionFail::GameIsOver
+        );
+    }
+
+    #[test]
+    fn a_board_ending_without_a_city_still_has_a_winner() {
+        let city = City::new(CityId::new(1), 20, Funds::default(), 0);
+        let players = vec![get_player(1, &[(Piece::Red, 3)]), get_player(2, &[])];
+        let rules = Rules {
+            disable_winning_points: true,
+            end_on_stalemate: true,
+            ..Rules::default()
+        };
+        let board = Board::builder()
+            .players(players)
+            .bank(Funds::new(7, 7, 7, 7, 7, 5))
+            .deck(ProductionTier::One, vec![])
+            .deck(ProductionTier::Two, vec![])
+            .deck(ProductionTier::Three, vec![])
+            .rules(rules)
+            .build()
+            .unwrap();
+        let board = CitiesBoard::new(board, vec![city]).unwrap();
+
+        let board = (0..2).fold(board, |board, _| {
+            CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap()
+        });
+        assert_eq!(
+            board.get_board().get_end_reason(),
+            Some(EndReason::Stalemate)
+        );
+        assert!(board.get_qualified().is_empty());
+        assert_eq!(board.get_winner(), Some(&Winner::Winner(PlayerId::new(1))));
+        assert_eq!(
+            CitiesBoard::do_action(board, &Action::PassTheTurn).unwrap_err(),
+            ActionFail::GameIsOver
+        );
+    }
+
+    #[test]
+    fn cities_need_a_board_without_nobles_nor_winning_points() {
+        let players = vec![get_player(1, &[]), get_player(2, &[])];
+        let builder = Board::builder()
+            .players(players)
+            .bank(Funds::new(7, 7, 7, 7, 7, 5))
+            .deck(ProductionTier::One, vec![])
+            .deck(ProductionTier::Two, vec![])
+            .deck(ProductionTier::Three, vec![]);
+
+        let board = builder.clone().build().unwrap();
+        assert_eq!(
+            CitiesBoard::new(board, vec![]).unwrap_err(),
+            SetupError::WinningPointsWithCities
+        );
+
+        let rules = Rules {
+            disable_winning_points: true,
+            ..Rules::default()
+        };
+        let noble = Noble::new(NobleId::new(1), Funds::default());
+        let board = builder.rules(rules).nobles(vec![noble]).build().unwrap();
+        assert_eq!(
+            CitiesBoard::new(board, vec![]).unwrap_err(),
+            SetupError::NoblesWithCities
+        );
+    }
+
+    #[test]
+    fn cities_board_is_defined_for_2_to_4_players() {
+        let cities: Vec<City> = (1..=5)
+            .map(|id| City::new(CityId::new(id), 15, Funds::default(), 0))
+            .collect();
+        for n_of_players in 2..=4 {
+            let board = try_get_cities_board_with(n_of_players, &cities).unwrap();
+            assert_eq!(board.get_cities().len(), CITIES_PER_GAME);
+            assert_eq!(board.get_board().iter_nobles().count(), 0);
+            assert_eq!(board.get_board().validate(), Ok(()));
+        }
+
+        assert_eq!(
+            try_get_cities_board_with(5, &cities).unwrap_err(),
+            SetupError::UnsupportedPlayerCount(5)
+        );
+    }
+
+    #[test]
+    fn cities_board_deals_the_city_tiles_by_default() {
+        let tiles = get_cities();
+        let ids: HashSet<CityId> = tiles.iter().map(|city| city.id).collect();
+        assert_eq!(ids.len(), tiles.len());
+
+        let board = get_cities_board(3);
+        assert_eq!(board.get_cities().len(), CITIES_PER_GAME);
+        assert!(board.get_cities().iter().all(|city| tiles.contains(city)));
+    }
+}
@@ -0,0 +1,1384 @@
+//! Splendor Duel, the two player variant.
+//!
+//! Tokens are laid out on a 5x5 board and taken in lines, privileges let a player take single
+//! tokens, crowns on cards bring royal cards, and a player wins at the end of their turn with 20
+//! points, 10 crowns or 10 points in cards of a single color. Brown stands for the black gems of
+//! the printed game.
+//!
+//! The board is refilled from the bag in a pseudo random order drawn from the seed given to
+//! `DuelBoard::try_new`, so that a game can be replayed.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bank::Funds;
+use crate::board::{BuyOperationFail, ProductionTier, SetupError};
+use crate::noble::NobleId;
+use crate::piece::Piece;
+use crate::player::{PlayerId, MAXIMUM_RESERVED_CARDS};
+use crate::production_card::{CardId, Identifiable, ProductionCard};
+use crate::zobrist::splitmix64;
+
+pub const GRID_SIZE: usize = 5;
+pub const MAXIMUM_TOKENS: u16 = 10;
+pub const MAXIMUM_PRIVILEGES: u8 = 3;
+pub const WINNING_POINTS: u16 = 20;
+pub const WINNING_CROWNS: u16 = 10;
+pub const WINNING_COLOR_POINTS: u16 = 10;
+/// Crowns a player needs for their first and second royal card.
+pub const ROYAL_CROWNS: [u16; 2] = [3, 6];
+
+/// Every token of the game: 4 of each color, 2 pearls and 3 golden pieces.
+pub const DUEL_TOKENS: Funds = Funds::new(4, 4, 4, 4, 4, 3).with_pearls(2);
+
+/// The order in which the board is filled, spiralling out of its center.
+const SPIRAL: [(usize, usize); GRID_SIZE * GRID_SIZE] = [
+    (2, 2),
+    (3, 2),
+    (3, 1),
+    (2, 1),
+    (1, 1),
+    (1, 2),
+    (1, 3),
+    (2, 3),
+    (3, 3),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+];
+
+pub fn pyramid_slots(tier: ProductionTier) -> usize {
+    match tier {
+        ProductionTier::One => 5,
+        ProductionTier::Two => 4,
+        ProductionTier::Three => 3,
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Cell {
+    pub row: usize,
+    pub column: usize,
+}
+
+impl Cell {
+    pub fn new(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cell ({}, {})", self.row, self.column)
+    }
+}
+
+/// Effects of Duel cards and royal cards, resolved when they are acquired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuelAbility {
+    /// The player plays another turn right away.
+    PlayAgain,
+    /// The card produces the color of a card the player owns, chosen when it is bought. Until
+    /// then it is listed as producing `Piece::Golden`.
+    CopyColor,
+    /// The player takes a token of the card's color from the board.
+    TakeToken,
+    /// The player takes a privilege.
+    TakePrivilege,
+    /// The player takes a token, but a golden one, from their opponent.
+    StealToken,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuelCard {
+    pub card: ProductionCard,
+    pub crowns: u8,
+    pub ability: Option<DuelAbility>,
+}
+
+impl DuelCard {
+    pub fn new(card: ProductionCard, crowns: u8, ability: Option<DuelAbility>) -> Self {
+        Self {
+            card,
+            crowns,
+            ability,
+        }
+    }
+}
+
+/// Taken when a player reaches 3 and then 6 crowns. Royal cards use noble ids, as they take the
+/// place of the nobles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoyalCard {
+    pub id: NobleId,
+    pub victory_points: u8,
+    pub ability: Option<DuelAbility>,
+}
+
+impl RoyalCard {
+    pub fn new(id: NobleId, victory_points: u8, ability: Option<DuelAbility>) -> Self {
+        Self {
+            id,
+            victory_points,
+            ability,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuelPlayer {
+    pub id: PlayerId,
+    pub tokens: Funds,
+    pub cards: Vec<Identifiable<DuelCard, CardId>>,
+    pub reserved_cards: Vec<Identifiable<DuelCard, CardId>>,
+    pub royals: Vec<RoyalCard>,
+    pub privileges: u8,
+}
+
+impl DuelPlayer {
+    pub fn new(id: PlayerId) -> Self {
+        Self {
+            id,
+            tokens: Funds::default(),
+            cards: vec![],
+            reserved_cards: vec![],
+            royals: vec![],
+            privileges: 0,
+        }
+    }
+
+    pub fn get_production(&self) -> Funds {
+        let mut funds = Funds::default();
+        for card in &self.cards {
+            funds[card.data.card.produces] += card.data.card.bonus();
+        }
+        funds
+    }
+
+    pub fn total_victory_points(&self) -> u16 {
+        let card_points = self
+            .cards
+            .iter()
+            .filter_map(|card| card.data.card.victory_points);
+        let royal_points = self.royals.iter().map(|royal| royal.victory_points);
+        saturating_sum(card_points.chain(royal_points))
+    }
+
+    pub fn crowns(&self) -> u16 {
+        saturating_sum(self.cards.iter().map(|card| card.data.crowns))
+    }
+
+    /// Points of the cards producing this color.
+    pub fn color_points(&self, piece: Piece) -> u16 {
+        saturating_sum(
+            self.cards
+                .iter()
+                .filter(|card| card.data.card.produces == piece)
+                .filter_map(|card| card.data.card.victory_points),
+        )
+    }
+
+    fn has_color_to_copy(&self) -> bool {
+        Piece::COLORS
+            .iter()
+            .any(|piece| self.get_production()[*piece] > 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VictoryCondition {
+    Points,
+    Crowns,
+    ColorPoints(Piece),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuelWinner {
+    pub player: PlayerId,
+    pub condition: VictoryCondition,
+}
+
+/// What the player to act has to do next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuelActionType {
+    /// Optional actions, then one of taking tokens, reserving or buying.
+    Normal,
+    /// The ability of the card just acquired needs a choice.
+    ResolveAbility(DuelAbility),
+    SelectRoyal,
+    /// The player holds more than 10 tokens and gives the extra ones back to the bag.
+    Discard,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuelAction {
+    /// Optional: spends a privilege on a token of the board, but a golden one.
+    UsePrivilege(Cell),
+    /// Optional: refills the board from the bag, giving a privilege to the opponent.
+    ReplenishBoard,
+    /// Up to 3 tokens, but golden ones, in a line with no gap.
+    TakeTokens(Vec<Cell>),
+    /// Takes a golden token and reserves a card of the pyramid.
+    ReserveCard(Cell, CardId),
+    /// Takes a golden token and reserves the top card of a deck.
+    ReserveFromDeck(Cell, ProductionTier),
+    /// Buys a card of the pyramid or a reserved one.
+    BuyCard(CardId),
+    /// Resolves `DuelAbility::TakeToken`.
+    TakeToken(Cell),
+    /// Resolves `DuelAbility::StealToken`.
+    StealToken(Piece),
+    /// Resolves `DuelAbility::CopyColor`.
+    CopyColor(Piece),
+    SelectRoyal(NobleId),
+    Discard(Funds),
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuelActionFail {
+    GameIsOver,
+    /// The action does not fit what the player has to do, see `DuelBoard::get_action_needed`.
+    NotExpectedNow(DuelActionType),
+    NoPrivilege,
+    EmptyBag,
+    CellOutOfBoard(Cell),
+    EmptyCell(Cell),
+    /// Golden tokens are only taken when reserving.
+    CannotTakeGolden,
+    NotInALine,
+    NoGoldenToken(Cell),
+    MaximumReservedCards,
+    CardNotFound,
+    EmptyDeck(ProductionTier),
+    InvalidBuyOperation(BuyOperationFail),
+    InvalidAbilityChoice,
+    RoyalNotFound,
+    /// The player needs to give back exactly this many tokens.
+    MustDiscard(u16),
+}
+
+impl fmt::Display for DuelActionFail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuelActionFail::GameIsOver => write!(f, "The game is over"),
+            DuelActionFail::NotExpectedNow(action_needed) => {
+                write!(f, "This is not possible now, expected {:?}", action_needed)
+            }
+            DuelActionFail::NoPrivilege => write!(f, "You have no privilege to use"),
+            DuelActionFail::EmptyBag => write!(f, "There is nothing in the bag"),
+            DuelActionFail::CellOutOfBoard(cell) => write!(f, "There is no {}", cell),
+            DuelActionFail::EmptyCell(cell) => write!(f, "There is no token on {}", cell),
+            DuelActionFail::CannotTakeGolden => {
+                write!(f, "You can only take a Golden token by reserving a card")
+            }
+            DuelActionFail::NotInALine => write!(
+                f,
+                "You can take up to 3 tokens next to each other, in a row, column or diagonal"
+            ),
+            DuelActionFail::NoGoldenToken(cell) => {
+                write!(f, "There is no Golden token on {}", cell)
+            }
+            DuelActionFail::MaximumReservedCards => write!(
+                f,
+                "You cannot reserve more than {} cards",
+                MAXIMUM_RESERVED_CARDS
+            ),
+            DuelActionFail::CardNotFound => write!(f, "This card is not available to you"),
+            DuelActionFail::EmptyDeck(tier) => write!(f, "The deck of tier {:?} is empty", tier),
+            DuelActionFail::InvalidBuyOperation(fail) => write!(f, "{}", fail),
+            DuelActionFail::InvalidAbilityChoice => {
+                write!(f, "This choice does not fit the ability to resolve")
+            }
+            DuelActionFail::RoyalNotFound => write!(f, "This royal card is not available"),
+            DuelActionFail::MustDiscard(quantity) => {
+                write!(f, "You need to give back exactly {} tokens", quantity)
+            }
+        }
+    }
+}
+
+impl Error for DuelActionFail {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DuelActionFail::InvalidBuyOperation(fail) => Some(fail),
+            _ => None,
+        }
+    }
+}
+
+/// A Duel game in progress.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuelBoard {
+    grid: [[Option<Piece>; GRID_SIZE]; GRID_SIZE],
+    bag: Funds,
+    players: [DuelPlayer; 2],
+    player_turn: usize,
+    privileges: u8,
+    decks: HashMap<ProductionTier, Vec<Identifiable<DuelCard, CardId>>>,
+    pyramid: HashMap<ProductionTier, Vec<Identifiable<DuelCard, CardId>>>,
+    royals: Vec<RoyalCard>,
+    action_needed: DuelActionType,
+    /// The card whose ability waits for a choice, in the tableau of the player to act.
+    pending_card: Option<CardId>,
+    play_again: bool,
+    winner: Option<DuelWinner>,
+    seed: u64,
+}
+
+impl DuelBoard {
+    /// Sets up a game between players 1 and 2, player 1 starting and player 2 getting a
+    /// privilege. The last card of each deck is the top of the deck.
+    pub fn try_new(
+        decks: HashMap<ProductionTier, Vec<Identifiable<DuelCard, CardId>>>,
+        royals: Vec<RoyalCard>,
+        seed: u64,
+    ) -> Result<Self, SetupError> {
+        let mut card_ids = HashSet::new();
+        for tier in ProductionTier::ALL {
+            let deck = decks.get(&tier).ok_or(SetupError::MissingTier(tier))?;
+            for card in deck {
+                if !card_ids.insert(card.uid) {
+                    return Err(SetupError::DuplicateCardId(card.uid));
+                }
+            }
+        }
+        let mut royal_ids = HashSet::new();
+        for royal in &royals {
+            if !royal_ids.insert(royal.id) {
+                return Err(SetupError::DuplicateNobleId(royal.id));
+            }
+        }
+
+        let mut board = Self {
+            grid: [[None; GRID_SIZE]; GRID_SIZE],
+            bag: DUEL_TOKENS,
+            players: [
+                DuelPlayer::new(PlayerId::new(1)),
+                DuelPlayer::new(PlayerId::new(2)),
+            ],
+            player_turn: 0,
+            privileges: MAXIMUM_PRIVILEGES,
+            decks,
+            pyramid: HashMap::new(),
+            royals,
+            action_needed: DuelActionType::Normal,
+            pending_card: None,
+            play_again: false,
+            winner: None,
+            seed,
+        };
+        for tier in ProductionTier::ALL {
+            let deck = board.decks.get_mut(&tier).expect("Every tier was checked");
+            let keep = deck.len().saturating_sub(pyramid_slots(tier));
+            let mut cards = deck.split_off(keep);
+            cards.reverse();
+            board.pyramid.insert(tier, cards);
+        }
+        board.fill_grid();
+        board.give_privilege(1);
+
+        Ok(board)
+    }
+
+    pub fn do_action(board: DuelBoard, action: &DuelAction) -> Result<DuelBoard, DuelActionFail> {
+        let mut board = board;
+        board.apply(action)?;
+        Ok(board)
+    }
+
+    /// Applies the action in place. On failure the board is left untouched.
+    pub fn apply(&mut self, action: &DuelAction) -> Result<(), DuelActionFail> {
+        if self.winner.is_some() {
+            return Err(DuelActionFail::GameIsOver);
+        }
+
+        let expected = match (self.action_needed, action) {
+            (DuelActionType::Normal, DuelAction::UsePrivilege(_))
+            | (DuelActionType::Normal, DuelAction::ReplenishBoard)
+            | (DuelActionType::Normal, DuelAction::TakeTokens(_))
+            | (DuelActionType::Normal, DuelAction::ReserveCard(_, _))
+            | (DuelActionType::Normal, DuelAction::ReserveFromDeck(_, _))
+            | (DuelActionType::Normal, DuelAction::BuyCard(_))
+            | (DuelActionType::SelectRoyal, DuelAction::SelectRoyal(_))
+            | (DuelActionType::Discard, DuelAction::Discard(_)) => true,
+            (DuelActionType::ResolveAbility(ability), _) => matches!(
+                (ability, action),
+                (DuelAbility::TakeToken, DuelAction::TakeToken(_))
+                    | (DuelAbility::StealToken, DuelAction::StealToken(_))
+                    | (DuelAbility::CopyColor, DuelAction::CopyColor(_))
+            ),
+            _ => false,
+        };
+        if !expected {
+            return Err(DuelActionFail::NotExpectedNow(self.action_needed));
+        }
+
+        match action {
+            DuelAction::UsePrivilege(cell) => return self.action_use_privilege(cell),
+            DuelAction::ReplenishBoard => return self.action_replenish_board(),
+            DuelAction::TakeTokens(cells) => self.action_take_tokens(cells)?,
+            DuelAction::ReserveCard(cell, card_id) => self.action_reserve_card(cell, card_id)?,
+            DuelAction::ReserveFromDeck(cell, tier) => self.action_reserve_from_deck(cell, tier)?,
+            DuelAction::BuyCard(card_id) => self.action_buy_card(card_id)?,
+            DuelAction::TakeToken(cell) => self.action_take_token(cell)?,
+            DuelAction::StealToken(piece) => self.action_steal_token(*piece)?,
+            DuelAction::CopyColor(piece) => self.action_copy_color(*piece)?,
+            DuelAction::SelectRoyal(royal_id) => self.action_select_royal(royal_id)?,
+            DuelAction::Discard(tokens) => self.action_discard(tokens)?,
+        }
+
+        self.finish_turn();
+        Ok(())
+    }
+
+    pub fn get_grid(&self) -> &[[Option<Piece>; GRID_SIZE]; GRID_SIZE] {
+        &self.grid
+    }
+
+    pub fn get_bag(&self) -> Funds {
+        self.bag
+    }
+
+    pub fn get_players(&self) -> &[DuelPlayer; 2] {
+        &self.players
+    }
+
+    pub fn get_who_is_playing_now(&self) -> &DuelPlayer {
+        &self.players[self.player_turn]
+    }
+
+    /// Privileges left in the supply.
+    pub fn get_privileges(&self) -> u8 {
+        self.privileges
+    }
+
+    pub fn get_pyramid(&self, tier: ProductionTier) -> &[Identifiable<DuelCard, CardId>] {
+        self.pyramid.get(&tier).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn get_deck_size(&self, tier: ProductionTier) -> usize {
+        self.decks.get(&tier).map_or(0, Vec::len)
+    }
+
+    pub fn get_royals(&self) -> &[RoyalCard] {
+        &self.royals
+    }
+
+    pub fn get_action_needed(&self) -> DuelActionType {
+        self.action_needed
+    }
+
+    pub fn get_winner(&self) -> Option<DuelWinner> {
+        self.winner
+    }
+
+    fn opponent(&self) -> usize {
+        1 - self.player_turn
+    }
+
+    fn token_at(&self, cell: &Cell) -> Result<Piece, DuelActionFail> {
+        if cell.row >= GRID_SIZE || cell.column >= GRID_SIZE {
+            return Err(DuelActionFail::CellOutOfBoard(*cell));
+        }
+        self.grid[cell.row][cell.column].ok_or(DuelActionFail::EmptyCell(*cell))
+    }
+
+    fn take_from_grid(&mut self, cell: &Cell) -> Piece {
+        let piece = self.grid[cell.row][cell.column]
+            .take()
+            .expect("The cell was checked");
+        self.players[self.player_turn].tokens[piece] += 1;
+        piece
+    }
+
+    /// Draws a token from the bag, pseudo randomly.
+    fn draw_from_bag(&mut self) -> Option<Piece> {
+        let total = self.bag.total();
+        if total == 0 {
+            return None;
+        }
+        self.seed = splitmix64(self.seed);
+        let mut draw = (self.seed % u64::from(total)) as u16;
+        for (piece, quantity) in self.bag.iter() {
+            if draw < u16::from(quantity) {
+                self.bag[piece] -= 1;
+                return Some(piece);
+            }
+            draw -= u16::from(quantity);
+        }
+        unreachable!("The draw is below the total of the bag")
+    }
+
+    fn fill_grid(&mut self) {
+        for (row, column) in SPIRAL {
+            if self.grid[row][column].is_none() {
+                self.grid[row][column] = self.draw_from_bag();
+            }
+        }
+    }
+
+    /// Gives a privilege from the supply, or from the other player once the supply is empty.
+    fn give_privilege(&mut self, seat: usize) {
+        if self.players[seat].privileges == MAXIMUM_PRIVILEGES {
+            return;
+        }
+        if self.privileges > 0 {
+            self.privileges -= 1;
+            self.players[seat].privileges += 1;
+        } else if self.players[1 - seat].privileges > 0 {
+            self.players[1 - seat].privileges -= 1;
+            self.players[seat].privileges += 1;
+        }
+    }
+
+    fn action_use_privilege(&mut self, cell: &Cell) -> Result<(), DuelActionFail> {
+        if self.players[self.player_turn].privileges == 0 {
+            return Err(DuelActionFail::NoPrivilege);
+        }
+        if self.token_at(cell)? == Piece::Golden {
+            return Err(DuelActionFail::CannotTakeGolden);
+        }
+
+        self.take_from_grid(cell);
+        self.players[self.player_turn].privileges -= 1;
+        self.privileges += 1;
+
+        Ok(())
+    }
+
+    fn action_replenish_board(&mut self) -> Result<(), DuelActionFail> {
+        if self.bag.is_empty() {
+            return Err(DuelActionFail::EmptyBag);
+        }
+
+        self.fill_grid();
+        self.give_privilege(self.opponent());
+
+        Ok(())
+    }
+
+    fn action_take_tokens(&mut self, cells: &[Cell]) -> Result<(), DuelActionFail> {
+        if cells.is_empty() || cells.len() > 3 {
+            return Err(DuelActionFail::NotInALine);
+        }
+        let mut pieces = Funds::default();
+        for cell in cells {
+            let piece = self.token_at(cell)?;
+            if piece == Piece::Golden {
+                return Err(DuelActionFail::CannotTakeGolden);
+            }
+            pieces[piece] += 1;
+        }
+        if !is_line(cells) {
+            return Err(DuelActionFail::NotInALine);
+        }
+
+        for cell in cells {
+            self.take_from_grid(cell);
+        }
+        let gives_privilege =
+            pieces[Piece::Pearl] == 2 || pieces.iter().any(|(_, quantity)| quantity == 3);
+        if gives_privilege {
+            self.give_privilege(self.opponent());
+        }
+
+        Ok(())
+    }
+
+    fn check_golden_token(&self, cell: &Cell) -> Result<(), DuelActionFail> {
+        if self.token_at(cell)? != Piece::Golden {
+            return Err(DuelActionFail::NoGoldenToken(*cell));
+        }
+        if self.players[self.player_turn].reserved_cards.len() >= MAXIMUM_RESERVED_CARDS {
+            return Err(DuelActionFail::MaximumReservedCards);
+        }
+        Ok(())
+    }
+
+    fn find_in_pyramid(&self, card_id: &CardId) -> Option<(ProductionTier, usize)> {
+        ProductionTier::ALL.into_iter().find_map(|tier| {
+            self.get_pyramid(tier)
+                .iter()
+                .position(|card| &card.uid == card_id)
+                .map(|index| (tier, index))
+        })
+    }
+
+    /// Takes a card out of the pyramid, putting the top card of its deck in its place.
+    fn take_from_pyramid(
+        &mut self,
+        tier: ProductionTier,
+        index: usize,
+    ) -> Identifiable<DuelCard, CardId> {
+        let pyramid = self.pyramid.get_mut(&tier).expect("The tier was found");
+        match self.decks.get_mut(&tier).and_then(Vec::pop) {
+            Some(card_drawn) => std::mem::replace(&mut pyramid[index], card_drawn),
+            None => pyramid.remove(index),
+        }
+    }
+
+    fn action_reserve_card(&mut self, cell: &Cell, card_id: &CardId) -> Result<(), DuelActionFail> {
+        self.check_golden_token(cell)?;
+        let (tier, index) = self
+            .find_in_pyramid(card_id)
+            .ok_or(DuelActionFail::CardNotFound)?;
+
+        self.take_from_grid(cell);
+        let card = self.take_from_pyramid(tier, index);
+        self.players[self.player_turn].reserved_cards.push(card);
+
+        Ok(())
+    }
+
+    fn action_reserve_from_deck(
+        &mut self,
+        cell: &Cell,
+        tier: &ProductionTier,
+    ) -> Result<(), DuelActionFail> {
+        self.check_golden_token(cell)?;
+        let card = self
+            .decks
+            .get_mut(tier)
+            .and_then(Vec::pop)
+            .ok_or(DuelActionFail::EmptyDeck(*tier))?;
+
+        self.take_from_grid(cell);
+        self.players[self.player_turn].reserved_cards.push(card);
+
+        Ok(())
+    }
+
+    fn action_buy_card(&mut self, card_id: &CardId) -> Result<(), DuelActionFail> {
+        let player = &self.players[self.player_turn];
+        let pyramid_position = self.find_in_pyramid(card_id);
+        let reserve_position = player
+            .reserved_cards
+            .iter()
+            .position(|card| &card.uid == card_id);
+
+        let card = match (pyramid_position, reserve_position) {
+            (Some((tier, index)), _) => &self.pyramid[&tier][index].data,
+            (None, Some(index)) => &player.reserved_cards[index].data,
+            (None, None) => return Err(DuelActionFail::CardNotFound),
+        };
+        if card.ability == Some(DuelAbility::CopyColor) && !player.has_color_to_copy() {
+            return Err(DuelActionFail::InvalidBuyOperation(
+                BuyOperationFail::NoColorToCopy,
+            ));
+        }
+        let payment = card
+            .card
+            .payment_plan(player.tokens, player.get_production())
+            .map_err(DuelActionFail::InvalidBuyOperation)?
+            .total();
+
+        let card = match (pyramid_position, reserve_position) {
+            (Some((tier, index)), _) => self.take_from_pyramid(tier, index),
+            (None, Some(index)) => self.players[self.player_turn].reserved_cards.remove(index),
+            (None, None) => unreachable!("The card was found above"),
+        };
+        let player = &mut self.players[self.player_turn];
+        player.tokens = (player.tokens - payment).expect("The payment was planned");
+        self.bag = self.bag + payment;
+
+        let ability = card.data.ability;
+        let card_id = card.uid;
+        self.players[self.player_turn].cards.push(card);
+        self.trigger_ability(ability, Some(card_id));
+
+        Ok(())
+    }
+
+    /// Applies the ability of a card or royal just acquired, waiting for a choice when needed.
+    fn trigger_ability(&mut self, ability: Option<DuelAbility>, card_id: Option<CardId>) {
+        let Some(ability) = ability else {
+            return;
+        };
+        let needs_choice = match ability {
+            DuelAbility::PlayAgain => {
+                self.play_again = true;
+                false
+            }
+            DuelAbility::TakePrivilege => {
+                self.give_privilege(self.player_turn);
+                false
+            }
+            DuelAbility::CopyColor => card_id.is_some(),
+            DuelAbility::TakeToken => card_id.is_some_and(|card_id| {
+                let color = self.card_color(&card_id);
+                self.grid.iter().flatten().any(|cell| *cell == Some(color))
+            }),
+            DuelAbility::StealToken => {
+                let opponent = &self.players[self.opponent()];
+                opponent
+                    .tokens
+                    .iter()
+                    .any(|(piece, quantity)| piece != Piece::Golden && quantity > 0)
+            }
+        };
+
+        if needs_choice {
+            self.pending_card = card_id;
+            self.action_needed = DuelActionType::ResolveAbility(ability);
+        }
+    }
+
+    fn card_color(&self, card_id: &CardId) -> Piece {
+        self.players[self.player_turn]
+            .cards
+            .iter()
+            .find(|card| &card.uid == card_id)
+            .map(|card| card.data.card.produces)
+            .expect("The card with the ability should be owned")
+    }
+
+    fn finish_ability(&mut self) {
+        self.pending_card = None;
+        self.action_needed = DuelActionType::Normal;
+    }
+
+    fn action_take_token(&mut self, cell: &Cell) -> Result<(), DuelActionFail> {
+        let card_id = self.pending_card.expect("A card ability is pending");
+        if self.token_at(cell)? != self.card_color(&card_id) {
+            return Err(DuelActionFail::InvalidAbilityChoice);
+        }
+
+        self.take_from_grid(cell);
+        self.finish_ability();
+
+        Ok(())
+    }
+
+    fn action_steal_token(&mut self, piece: Piece) -> Result<(), DuelActionFail> {
+        let opponent = self.opponent();
+        if piece == Piece::Golden || self.players[opponent].tokens[piece] == 0 {
+            return Err(DuelActionFail::InvalidAbilityChoice);
+        }
+
+        self.players[opponent].tokens[piece] -= 1;
+        self.players[self.player_turn].tokens[piece] += 1;
+        self.finish_ability();
+
+        Ok(())
+    }
+
+    fn action_copy_color(&mut self, piece: Piece) -> Result<(), DuelActionFail> {
+        let card_id = self.pending_card.expect("A card ability is pending");
+        let player = &mut self.players[self.player_turn];
+        if !Piece::COLORS.contains(&piece) || player.get_production()[piece] == 0 {
+            return Err(DuelActionFail::InvalidAbilityChoice);
+        }
+
+        let card = player
+            .cards
+            .iter_mut()
+            .find(|card| card.uid == card_id)
+            .expect("The card with the ability should be owned");
+        card.data.card.produces = piece;
+        self.finish_ability();
+
+        Ok(())
+    }
+
+    fn royals_due(&self) -> bool {
+        let player = &self.players[self.player_turn];
+        let earned = ROYAL_CROWNS
+            .iter()
+            .filter(|crowns| player.crowns() >= **crowns)
+            .count();
+        earned > player.royals.len() && !self.royals.is_empty()
+    }
+
+    fn action_select_royal(&mut self, royal_id: &NobleId) -> Result<(), DuelActionFail> {
+        let index = self
+            .royals
+            .iter()
+            .position(|royal| &royal.id == royal_id)
+            .ok_or(DuelActionFail::RoyalNotFound)?;
+
+        let royal = self.royals.remove(index);
+        let ability = royal.ability;
+        self.players[self.player_turn].royals.push(royal);
+        self.action_needed = DuelActionType::Normal;
+        self.trigger_ability(ability, None);
+
+        Ok(())
+    }
+
+    fn action_discard(&mut self, tokens: &Funds) -> Result<(), DuelActionFail> {
+        let player = &self.players[self.player_turn];
+        let extra = player.tokens.total().saturating_sub(MAXIMUM_TOKENS);
+        if tokens.total() != extra {
+            return Err(DuelActionFail::MustDiscard(extra));
+        }
+        let remaining =
+            (player.tokens - *tokens).map_err(|_| DuelActionFail::MustDiscard(extra))?;
+
+        self.players[self.player_turn].tokens = remaining;
+        self.bag = self.bag + *tokens;
+        self.action_needed = DuelActionType::Normal;
+
+        Ok(())
+    }
+
+    /// Moves on to whatever the turn still needs, ending it once everything is settled.
+    fn finish_turn(&mut self) {
+        if self.action_needed != DuelActionType::Normal {
+            return;
+        }
+        if self.royals_due() {
+            self.action_needed = DuelActionType::SelectRoyal;
+            return;
+        }
+        if self.players[self.player_turn].tokens.total() > MAXIMUM_TOKENS {
+            self.action_needed = DuelActionType::Discard;
+            return;
+        }
+
+        self.winner = self.compute_winner();
+        if self.play_again {
+            self.play_again = false;
+        } else {
+            self.player_turn = self.opponent();
+        }
+    }
+
+    fn compute_winner(&self) -> Option<DuelWinner> {
+        let player = &self.players[self.player_turn];
+        let condition = if player.total_victory_points() >= WINNING_POINTS {
+            VictoryCondition::Points
+        } else if player.crowns() >= WINNING_CROWNS {
+            VictoryCondition::Crowns
+        } else {
+            let color = Piece::COLORS
+                .into_iter()
+                .find(|piece| player.color_points(*piece) >= WINNING_COLOR_POINTS)?;
+            VictoryCondition::ColorPoints(color)
+        };
+
+        Some(DuelWinner {
+            player: player.id,
+            condition,
+        })
+    }
+}
+
+fn saturating_sum(values: impl Iterator<Item = u8>) -> u16 {
+    values.fold(0, |total, value| total.saturating_add(u16::from(value)))
+}
+
+/// Whether the cells are next to each other along a row, a column or a diagonal.
+fn is_line(cells: &[Cell]) -> bool {
+    let mut cells = cells.to_vec();
+    cells.sort_by_key(|cell| (cell.row, cell.column));
+    if cells.windows(2).any(|pair| pair[0] == pair[1]) {
+        return false;
+    }
+
+    let step = |from: &Cell, to: &Cell| {
+        (
+            to.row as isize - from.row as isize,
+            to.column as isize - from.column as isize,
+        )
+    };
+    let Some(direction) = cells.get(1).map(|second| step(&cells[0], second)) else {
+        return true;
+    };
+    let (rows, columns) = direction;
+    rows.abs() <= 1
+        && columns.abs() <= 1
+        && cells
+            .windows(2)
+            .all(|pair| step(&pair[0], &pair[1]) == direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_card(
+        id: u32,
+        cost: Funds,
+        produces: Piece,
+        victory_points: u8,
+        crowns: u8,
+        ability: Option<DuelAbility>,
+    ) -> Identifiable<DuelCard, CardId> {
+        let card = ProductionCard::new(cost, produces, Some(victory_points));
+        Identifiable {
+            uid: CardId::new(id),
+            data: DuelCard::new(card, crowns, ability),
+        }
+    }
+
+    /// Free cards, so that tests can buy whatever they need. Tier one sells cards 1 to 5.
+    fn get_board_with(cards: Vec<Identifiable<DuelCard, CardId>>) -> DuelBoard {
+        let mut tier_one: Vec<_> = (1..=5)
+            .rev()
+            .map(|id| get_card(id, Funds::default(), Piece::Red, 0, 0, None))
+            .collect();
+        tier_one.splice(0..0, cards);
+        let decks = HashMap::from([
+            (ProductionTier::One, tier_one),
+            (ProductionTier::Two, vec![]),
+            (ProductionTier::Three, vec![]),
+        ]);
+        let royals = vec![
+            RoyalCard::new(NobleId::new(1), 3, None),
+            RoyalCard::new(NobleId::new(2), 2, Some(DuelAbility::TakePrivilege)),
+        ];
+        DuelBoard::try_new(decks, royals, 7).unwrap()
+    }
+
+    fn get_board() -> DuelBoard {
+        get_board_with(vec![])
+    }
+
+    fn play(board: DuelBoard, actions: &[DuelAction]) -> DuelBoard {
+        actions.iter().fold(board, |board, action| {
+            DuelBoard::do_action(board, action).unwrap()
+        })
+    }
+
+    fn find(board: &DuelBoard, piece: Piece) -> Cell {
+        (0..GRID_SIZE)
+            .flat_map(|row| (0..GRID_SIZE).map(move |column| Cell::new(row, column)))
+            .find(|cell| board.grid[cell.row][cell.column] == Some(piece))
+            .expect("The piece should be on the board")
+    }
+
+    /// A board whose tokens are laid out by hand, the bag holding the rest.
+    fn with_grid(board: DuelBoard, rows: [[Piece; GRID_SIZE]; GRID_SIZE]) -> DuelBoard {
+        let mut grid = [[None; GRID_SIZE]; GRID_SIZE];
+        let mut bag = DUEL_TOKENS;
+        for (row, pieces) in rows.iter().enumerate() {
+            for (column, piece) in pieces.iter().enumerate() {
+                grid[row][column] = Some(*piece);
+                bag[*piece] -= 1;
+            }
+        }
+        DuelBoard { grid, bag, ..board }
+    }
+
+    fn get_laid_out_board() -> DuelBoard {
+        let [red, green, blue, brown, white] = Piece::COLORS;
+        let (golden, pearl) = (Piece::Golden, Piece::Pearl);
+        with_grid(
+            get_board(),
+            [
+                [red, red, red, green, golden],
+                [green, blue, pearl, brown, white],
+                [blue, pearl, golden, white, brown],
+                [red, green, blue, brown, white],
+                [golden, green, blue, brown, white],
+            ],
+        )
+    }
+
+    #[test]
+    fn sets_up_the_board_from_the_bag() {
+        let board = get_board();
+
+        let mut on_board = Funds::default();
+        for piece in board.grid.iter().flatten() {
+            on_board[piece.expect("Every cell is filled")] += 1;
+        }
+        assert_eq!(on_board, DUEL_TOKENS);
+        assert!(board.bag.is_empty());
+        assert_eq!(board.get_pyramid(ProductionTier::One).len(), 5);
+        assert_eq!(
+            board.get_pyramid(ProductionTier::One)[0].uid,
+            CardId::new(1)
+        );
+        assert_eq!(board.players[1].privileges, 1);
+        assert_eq!(board.privileges, 2);
+
+        let cells: HashSet<(usize, usize)> = SPIRAL.into_iter().collect();
+        assert_eq!(cells.len(), GRID_SIZE * GRID_SIZE);
+    }
+
+    #[test]
+    fn tokens_are_taken_in_lines() {
+        let board = get_laid_out_board();
+        let take = |cells: Vec<(usize, usize)>| {
+            let cells = cells.into_iter().map(|(r, c)| Cell::new(r, c)).collect();
+            DuelBoard::do_action(board.clone(), &DuelAction::TakeTokens(cells))
+        };
+
+        assert!(take(vec![(1, 1), (3, 3), (2, 2)]).is_err());
+        assert!(take(vec![(3, 1), (1, 3), (2, 2)]).is_err());
+        assert!(take(vec![(3, 0), (3, 1), (3, 2)]).is_ok());
+        assert!(take(vec![(1, 3), (2, 3), (3, 3)]).is_ok());
+        assert!(take(vec![(3, 1), (2, 2)]).is_err());
+        assert!(take(vec![(2, 3), (3, 2)]).is_ok());
+        assert_eq!(
+            take(vec![(3, 0), (3, 2)]).unwrap_err(),
+            DuelActionFail::NotInALine
+        );
+        assert_eq!(
+            take(vec![(3, 0), (3, 0)]).unwrap_err(),
+            DuelActionFail::NotInALine
+        );
+        assert_eq!(
+            take(vec![(2, 1), (2, 2), (2, 3)]).unwrap_err(),
+            DuelActionFail::CannotTakeGolden
+        );
+
+        let board = take(vec![(3, 0), (3, 1), (3, 2)]).unwrap();
+        assert_eq!(board.players[0].tokens, Funds::new(1, 1, 1, 0, 0, 0));
+        assert_eq!(board.grid[3][0], None);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn three_of_a_color_or_two_pearls_give_a_privilege_to_the_opponent() {
+        let board = get_laid_out_board();
+
+        let row = vec![Cell::new(0, 0), Cell::new(0, 1), Cell::new(0, 2)];
+        let board = play(board, &[DuelAction::TakeTokens(row)]);
+        assert_eq!(board.players[1].privileges, 2);
+
+        let pearls = vec![Cell::new(1, 2), Cell::new(2, 1)];
+        let board = play(board, &[DuelAction::TakeTokens(pearls)]);
+        assert_eq!(board.players[0].privileges, 1);
+        assert_eq!(board.privileges, 0);
+
+        // With the supply empty, privileges are taken from the other player.
+        let column = vec![Cell::new(2, 4), Cell::new(3, 4), Cell::new(4, 4)];
+        let board = play(board, &[DuelAction::TakeTokens(column)]);
+        assert_eq!(board.players[1].tokens[Piece::Pearl], 2);
+        assert_eq!(board.players[0].privileges, 1);
+        let board = play(board, &[DuelAction::TakeTokens(vec![Cell::new(3, 3)])]);
+        assert_eq!(board.players[0].privileges, 1);
+        assert_eq!(board.players[1].privileges, 2);
+    }
+
+    #[test]
+    fn privileges_and_replenishing_come_before_the_main_action() {
+        let board = get_laid_out_board();
+        let board = play(board, &[DuelAction::TakeTokens(vec![Cell::new(3, 0)])]);
+
+        let result =
+            DuelBoard::do_action(board.clone(), &DuelAction::UsePrivilege(Cell::new(2, 2)));
+        assert_eq!(result.unwrap_err(), DuelActionFail::CannotTakeGolden);
+        let board = play(board, &[DuelAction::UsePrivilege(Cell::new(0, 0))]);
+        assert_eq!(board.players[1].privileges, 0);
+        assert_eq!(board.privileges, 3);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+        let result =
+            DuelBoard::do_action(board.clone(), &DuelAction::UsePrivilege(Cell::new(0, 1)));
+        assert_eq!(result.unwrap_err(), DuelActionFail::NoPrivilege);
+
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::ReplenishBoard);
+        assert_eq!(result.unwrap_err(), DuelActionFail::EmptyBag);
+
+        // As if the first player had paid with their token.
+        let mut board = board;
+        board.players[0].tokens = Funds::default();
+        board.bag = Funds::new(1, 0, 0, 0, 0, 0);
+        let board = play(board, &[DuelAction::ReplenishBoard]);
+        assert_eq!(board.grid[3][0], Some(Piece::Red));
+        assert_eq!(board.grid[0][0], None);
+        assert!(board.bag.is_empty());
+        assert_eq!(board.players[0].privileges, 1);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn reserving_takes_a_golden_token() {
+        let board = get_laid_out_board();
+
+        let result = DuelBoard::do_action(
+            board.clone(),
+            &DuelAction::ReserveCard(Cell::new(0, 0), CardId::new(1)),
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            DuelActionFail::NoGoldenToken(Cell::new(0, 0))
+        );
+
+        let board = play(
+            board,
+            &[DuelAction::ReserveCard(Cell::new(2, 2), CardId::new(1))],
+        );
+        assert_eq!(board.players[0].tokens[Piece::Golden], 1);
+        assert_eq!(board.players[0].reserved_cards[0].uid, CardId::new(1));
+        assert_eq!(board.get_pyramid(ProductionTier::One).len(), 4);
+    }
+
+    #[test]
+    fn cards_cost_pearls_and_gold_stands_for_them() {
+        let cost = Funds::new(1, 0, 0, 0, 0, 0).with_pearls(1);
+        let card = get_card(9, cost, Piece::Blue, 1, 0, None);
+        let board = get_board_with(vec![card]);
+        let board = DuelBoard {
+            pyramid: HashMap::from([(
+                ProductionTier::One,
+                vec![get_card(9, cost, Piece::Blue, 1, 0, None)],
+            )]),
+            ..board
+        };
+        let mut board = board;
+        board.players[0].tokens = Funds::new(1, 0, 0, 0, 0, 0);
+
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::BuyCard(CardId::new(9)));
+        assert_eq!(
+            result.unwrap_err(),
+            DuelActionFail::InvalidBuyOperation(BuyOperationFail::NotEnoughFunds(
+                Funds::default().with_pearls(1)
+            ))
+        );
+
+        board.players[0].tokens = Funds::new(1, 0, 0, 0, 0, 1);
+        let bag = board.bag;
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(9))]);
+        assert!(board.players[0].tokens.is_empty());
+        assert_eq!(board.bag, bag + Funds::new(1, 0, 0, 0, 0, 1));
+        assert_eq!(board.players[0].get_production()[Piece::Blue], 1);
+    }
+
+    /// The laid out board, with a single free card on the market of tier one.
+    fn get_board_selling(produces: Piece, crowns: u8, ability: DuelAbility) -> DuelBoard {
+        let card = get_card(10, Funds::default(), produces, 0, crowns, Some(ability));
+        let mut board = get_laid_out_board();
+        board.pyramid.insert(ProductionTier::One, vec![card]);
+        board
+    }
+
+    #[test]
+    fn play_again_keeps_the_turn() {
+        let board = get_board_selling(Piece::Blue, 0, DuelAbility::PlayAgain);
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(10))]);
+        assert_eq!(board.action_needed, DuelActionType::Normal);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(1));
+
+        let board = play(board, &[DuelAction::TakeTokens(vec![Cell::new(0, 3)])]);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn take_token_takes_one_of_the_card_color() {
+        let board = get_board_selling(Piece::Blue, 0, DuelAbility::TakeToken);
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(10))]);
+        assert_eq!(
+            board.action_needed,
+            DuelActionType::ResolveAbility(DuelAbility::TakeToken)
+        );
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::TakeToken(Cell::new(0, 0)));
+        assert_eq!(result.unwrap_err(), DuelActionFail::InvalidAbilityChoice);
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::ReplenishBoard);
+        assert!(result.is_err());
+
+        let blue = find(&board, Piece::Blue);
+        let board = play(board, &[DuelAction::TakeToken(blue)]);
+        assert_eq!(board.players[0].tokens[Piece::Blue], 1);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn steal_token_takes_anything_but_gold_from_the_opponent() {
+        let mut board = get_board_selling(Piece::Red, 0, DuelAbility::StealToken);
+        board.players[1].tokens = Funds::new(0, 2, 0, 0, 0, 1);
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(10))]);
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::StealToken(Piece::Golden));
+        assert_eq!(result.unwrap_err(), DuelActionFail::InvalidAbilityChoice);
+
+        let board = play(board, &[DuelAction::StealToken(Piece::Green)]);
+        assert_eq!(board.players[0].tokens[Piece::Green], 1);
+        assert_eq!(board.players[1].tokens[Piece::Green], 1);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn copy_color_needs_an_owned_color() {
+        let mut board = get_board_selling(Piece::Golden, 0, DuelAbility::CopyColor);
+
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::BuyCard(CardId::new(10)));
+        assert_eq!(
+            result.unwrap_err(),
+            DuelActionFail::InvalidBuyOperation(BuyOperationFail::NoColorToCopy)
+        );
+
+        let red = get_card(20, Funds::default(), Piece::Red, 0, 0, None);
+        board.players[0].cards.push(red);
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(10))]);
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::CopyColor(Piece::White));
+        assert_eq!(result.unwrap_err(), DuelActionFail::InvalidAbilityChoice);
+
+        let board = play(board, &[DuelAction::CopyColor(Piece::Red)]);
+        assert_eq!(
+            board.players[0].get_production(),
+            Funds::new(2, 0, 0, 0, 0, 0)
+        );
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn take_privilege_gives_a_privilege() {
+        let board = get_board_selling(Piece::Red, 0, DuelAbility::TakePrivilege);
+        let privileges = board.players[0].privileges;
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(10))]);
+        assert_eq!(board.players[0].privileges, privileges + 1);
+        assert_eq!(board.action_needed, DuelActionType::Normal);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn play_again_waits_for_the_royal_card() {
+        let board = get_board_selling(Piece::Red, 3, DuelAbility::PlayAgain);
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(10))]);
+        assert_eq!(board.action_needed, DuelActionType::SelectRoyal);
+
+        let board = play(board, &[DuelAction::SelectRoyal(NobleId::new(1))]);
+        assert_eq!(board.action_needed, DuelActionType::Normal);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(1));
+    }
+
+    #[test]
+    fn play_again_waits_for_the_discard() {
+        let mut board = get_board_selling(Piece::Red, 0, DuelAbility::PlayAgain);
+        board.players[0].tokens = Funds::new(3, 3, 3, 2, 0, 0);
+        board.bag = Funds::default();
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(10))]);
+        assert_eq!(board.action_needed, DuelActionType::Discard);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(1));
+
+        let board = play(board, &[DuelAction::Discard(Funds::new(1, 0, 0, 0, 0, 0))]);
+        assert_eq!(board.action_needed, DuelActionType::Normal);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(1));
+    }
+
+    #[test]
+    fn crowns_bring_royal_cards() {
+        let crowned = get_card(20, Funds::default(), Piece::Red, 0, 3, None);
+        let mut board = get_board();
+        board.pyramid.insert(ProductionTier::Two, vec![crowned]);
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(20))]);
+        assert_eq!(board.action_needed, DuelActionType::SelectRoyal);
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::SelectRoyal(NobleId::new(9)));
+        assert_eq!(result.unwrap_err(), DuelActionFail::RoyalNotFound);
+
+        let board = play(board, &[DuelAction::SelectRoyal(NobleId::new(2))]);
+        assert_eq!(board.players[0].total_victory_points(), 2);
+        assert_eq!(board.players[0].privileges, 1);
+        assert_eq!(board.get_royals().len(), 1);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn the_second_royal_card_comes_at_six_crowns() {
+        let mut board = get_board();
+        let crowned = vec![
+            get_card(21, Funds::default(), Piece::Red, 0, 1, None),
+            get_card(20, Funds::default(), Piece::Red, 0, 2, None),
+        ];
+        board.pyramid.insert(ProductionTier::Two, crowned);
+        let owned = get_card(30, Funds::default(), Piece::Red, 0, 3, None);
+        board.players[0].cards.push(owned);
+        let royal = board.royals.remove(0);
+        board.players[0].royals.push(royal);
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(20))]);
+        assert_eq!(board.players[0].crowns(), 5);
+        assert_eq!(board.action_needed, DuelActionType::Normal);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(1))]);
+        let privileges = board.players[0].privileges;
+        let board = play(board, &[DuelAction::BuyCard(CardId::new(21))]);
+        assert_eq!(board.players[0].crowns(), 6);
+        assert_eq!(board.action_needed, DuelActionType::SelectRoyal);
+
+        let board = play(board, &[DuelAction::SelectRoyal(NobleId::new(2))]);
+        assert_eq!(board.players[0].royals.len(), 2);
+        assert_eq!(board.players[0].privileges, privileges + 1);
+        assert!(board.get_royals().is_empty());
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn players_give_back_tokens_above_ten() {
+        let mut board = get_laid_out_board();
+        board.players[0].tokens = Funds::new(3, 3, 3, 0, 0, 0);
+        board.bag = Funds::default();
+
+        let row = vec![Cell::new(3, 2), Cell::new(3, 3)];
+        let board = play(board, &[DuelAction::TakeTokens(row)]);
+        assert_eq!(board.action_needed, DuelActionType::Discard);
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(1));
+
+        let result = DuelBoard::do_action(board.clone(), &DuelAction::Discard(Funds::default()));
+        assert_eq!(result.unwrap_err(), DuelActionFail::MustDiscard(1));
+        let result = DuelBoard::do_action(
+            board.clone(),
+            &DuelAction::Discard(Funds::new(0, 0, 0, 0, 1, 0)),
+        );
+        assert_eq!(result.unwrap_err(), DuelActionFail::MustDiscard(1));
+
+        let board = play(board, &[DuelAction::Discard(Funds::new(1, 0, 0, 0, 0, 0))]);
+        assert_eq!(board.players[0].tokens.total(), 10);
+        assert_eq!(board.bag, Funds::new(1, 0, 0, 0, 0, 0));
+        assert_eq!(board.get_who_is_playing_now().id, PlayerId::new(2));
+    }
+
+    #[test]
+    fn wins_with_points_crowns_or_a_single_color() {
+        let win_with = |card: Identifiable<DuelCard, CardId>| {
+            let mut board = get_board();
+            board.royals.clear();
+            board.players[0]
+                .cards
+                .push(get_card(30, Funds::default(), Piece::Blue, 9, 0, None));
+            board.pyramid.insert(ProductionTier::Three, vec![card]);
+            let board = play(board, &[DuelAction::BuyCard(CardId::new(31))]);
+            board.get_winner()
+        };
+
+        let winner = |condition| {
+            Some(DuelWinner {
+                player: PlayerId::new(1),
+                condition,
+            })
+        };
+        let points = get_card(31, Funds::default(), Piece::Red, 9, 0, None);
+        assert_eq!(win_with(points), None);
+        let points = get_card(31, Funds::default(), Piece::Red, 11, 0, None);
+        assert_eq!(win_with(points), winner(VictoryCondition::Points));
+        let crowns = get_card(31, Funds::default(), Piece::Red, 0, 10, None);
+        assert_eq!(win_with(crowns), winner(VictoryCondition::Crowns));
+        let color = get_card(31, Funds::default(), Piece::Blue, 1, 0, None);
+        assert_eq!(
+            win_with(color),
+            winner(VictoryCondition::ColorPoints(Piece::Blue))
+        );
+
+        let mut board = get_board();
+        board.winner = winner(VictoryCondition::Points);
+        let result = DuelBoard::do_action(board, &DuelAction::ReplenishBoard);
+        assert_eq!(result.unwrap_err(), DuelActionFail::GameIsOver);
+    }
+}
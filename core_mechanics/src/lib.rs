@@ -1,9 +1,21 @@
 pub mod bank;
 pub mod board;
+pub mod catalog;
 pub mod noble;
 pub mod piece;
 pub mod player;
 pub mod production_card;
+pub mod rules;
+pub mod summary;
+mod zobrist;
 
+#[cfg(feature = "cities")]
+pub mod cities;
+#[cfg(feature = "duel")]
+pub mod duel;
+#[cfg(feature = "orient")]
+pub mod orient;
 #[cfg(feature = "original-game")]
 pub mod original_game;
+#[cfg(feature = "trading-posts")]
+pub mod trading_posts;
@@ -1,28 +1,139 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 use super::bank::Funds;
+use super::piece::Piece;
 
+/// Points of a noble that does not say otherwise, as in the original game.
 pub const NOBLE_VICTORY_POINTS: u8 = 3;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Noble {
     pub id: NobleId,
     pub cost: Funds,
+    #[serde(default = "default_victory_points")]
+    pub victory_points: u8,
+    /// Shown to players, for catalogs that name their tiles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Identifies the artwork of the tile for user interfaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub art_key: Option<String>,
+}
+
+fn default_victory_points() -> u8 {
+    NOBLE_VICTORY_POINTS
 }
 
 impl Noble {
     pub fn new(id: NobleId, cost: Funds) -> Self {
-        Self { id, cost }
+        Self {
+            id,
+            cost,
+            victory_points: NOBLE_VICTORY_POINTS,
+            name: None,
+            art_key: None,
+        }
+    }
+
+    pub fn with_victory_points(self, victory_points: u8) -> Self {
+        Self {
+            victory_points,
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    pub fn with_art_key(self, art_key: impl Into<String>) -> Self {
+        Self {
+            art_key: Some(art_key.into()),
+            ..self
+        }
+    }
+
+    /// Whether a player with this production attracts the noble. Golden pieces in the cost stand
+    /// for production of any color beyond what the rest of the cost asks for.
+    pub fn is_attracted_by(&self, production: &Funds) -> bool {
+        let mut surplus: u16 = 0;
+        for (piece, cost) in self.cost.iter() {
+            if piece.is_wildcard() {
+                continue;
+            }
+            if production[piece] < cost {
+                return false;
+            }
+            surplus += u16::from(production[piece] - cost);
+        }
+
+        surplus >= u16::from(self.cost[Piece::Golden])
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct NobleId {
-    id: u8,
+    id: u32,
 }
 
 impl NobleId {
-    pub fn new(id: u8) -> Self {
+    pub fn new(id: u32) -> Self {
         Self { id }
     }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl fmt::Display for NobleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "noble {}", self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn production_must_cover_the_cost() {
+        let noble = Noble::new(NobleId::new(1), Funds::new(3, 3, 0, 0, 0, 0));
+
+        assert!(noble.is_attracted_by(&Funds::new(3, 3, 0, 0, 0, 0)));
+        assert!(noble.is_attracted_by(&Funds::new(4, 3, 1, 0, 0, 0)));
+        assert!(!noble.is_attracted_by(&Funds::new(2, 3, 5, 0, 0, 0)));
+        assert!(!noble.is_attracted_by(&Funds::new(3, 2, 0, 0, 0, 5)));
+    }
+
+    #[test]
+    fn golden_cost_is_paid_by_surplus_production() {
+        let noble = Noble::new(NobleId::new(1), Funds::new(2, 0, 0, 0, 0, 2));
+
+        assert!(!noble.is_attracted_by(&Funds::new(2, 0, 0, 0, 0, 0)));
+        assert!(!noble.is_attracted_by(&Funds::new(3, 0, 0, 0, 0, 0)));
+        assert!(noble.is_attracted_by(&Funds::new(4, 0, 0, 0, 0, 0)));
+        assert!(noble.is_attracted_by(&Funds::new(2, 1, 0, 0, 1, 0)));
+        assert!(!noble.is_attracted_by(&Funds::new(1, 1, 1, 0, 1, 0)));
+    }
+
+    #[test]
+    fn nobles_stored_without_points_are_worth_the_default() {
+        let json = r#"{"id":{"id":1},"cost":{"funds":{"Red":3}}}"#;
+        let noble: Noble = serde_json::from_str(json).unwrap();
+        assert_eq!(noble.victory_points, NOBLE_VICTORY_POINTS);
+        assert_eq!(noble.name, None);
+
+        let noble = Noble::new(NobleId::new(2), Funds::new(0, 4, 0, 0, 0, 0))
+            .with_victory_points(5)
+            .with_name("Catherine de' Medici")
+            .with_art_key("nobles/catherine");
+        let json = serde_json::to_string(&noble).unwrap();
+        assert_eq!(serde_json::from_str::<Noble>(&json).unwrap(), noble);
+    }
 }
@@ -0,0 +1,183 @@
+//! The card abilities of the Orient expansion, played with stand-in cards.
+//!
+//! This is not the Orient expansion yet: its printed decks are not transcribed. The stand-in
+//! cards give every ability of the expansion at made-up costs, and are shuffled into the decks of
+//! their tier instead of having decks and markets of their own.
+
+use std::collections::HashMap;
+
+use crate::bank::Funds;
+use crate::board::{Board, ProductionTier, SetupError};
+use crate::catalog;
+use crate::original_game::{
+    get_game_builder_with_extra_cards, get_original_game_rules, get_random_nobles,
+};
+use crate::piece::Piece;
+use crate::production_card::{CardAbility, ProductionCard};
+
+/// # Panics
+///
+/// Panics if `n_of_players` is not between 2 and 4, see `try_get_stand_in_orient_board`.
+pub fn get_stand_in_orient_board(n_of_players: u8) -> Board {
+    try_get_stand_in_orient_board(n_of_players).unwrap_or_else(|_| {
+        panic!(
+            "The stand-in Orient game is only defined for 2 to 4 players. '{:?}' given",
+            n_of_players
+        )
+    })
+}
+
+pub fn try_get_stand_in_orient_board(n_of_players: u8) -> Result<Board, SetupError> {
+    get_game_builder_with_extra_cards(n_of_players, &get_stand_in_orient_cards())?
+        .nobles(get_random_nobles(n_of_players + 1))
+        .rules(get_original_game_rules())
+        .build()
+}
+
+/// Cards with the Orient abilities by tier, numbered from `catalog::ORIENT_FIRST_ID`. They stand
+/// in for the printed cards, see the module documentation.
+pub fn get_stand_in_orient_cards() -> HashMap<ProductionTier, Vec<ProductionCard>> {
+    catalog::numbered(
+        catalog::ORIENT_FIRST_ID,
+        [
+            (ProductionTier::One, get_tier_one_cards()),
+            (ProductionTier::Two, get_tier_two_cards()),
+            (ProductionTier::Three, get_tier_three_cards()),
+        ],
+    )
+}
+
+fn get_tier_one_cards() -> Vec<ProductionCard> {
+    vec![
+        ProductionCard::new(Funds::new(3, 0, 0, 0, 0, 0), Piece::Golden, None)
+            .with_ability(CardAbility::CopyColor),
+        ProductionCard::new(Funds::new(0, 3, 0, 0, 0, 0), Piece::Golden, None)
+            .with_ability(CardAbility::CopyColor),
+        ProductionCard::new(Funds::new(0, 0, 3, 0, 0, 0), Piece::Golden, None)
+            .with_ability(CardAbility::CopyColor),
+        ProductionCard::new(Funds::new(0, 0, 0, 3, 0, 0), Piece::Golden, None)
+            .with_ability(CardAbility::CopyColor),
+        ProductionCard::new(Funds::new(0, 0, 0, 0, 3, 0), Piece::Golden, None)
+            .with_ability(CardAbility::CopyColor),
+        ProductionCard::new(Funds::new(1, 2, 0, 0, 1, 0), Piece::Red, None)
+            .with_ability(CardAbility::DoubleBonus),
+        ProductionCard::new(Funds::new(1, 1, 2, 0, 0, 0), Piece::Green, None)
+            .with_ability(CardAbility::DoubleBonus),
+        ProductionCard::new(Funds::new(0, 1, 1, 2, 0, 0), Piece::Blue, None)
+            .with_ability(CardAbility::DoubleBonus),
+        ProductionCard::new(Funds::new(0, 0, 1, 1, 2, 0), Piece::Brown, None)
+            .with_ability(CardAbility::DoubleBonus),
+        ProductionCard::new(Funds::new(2, 0, 0, 1, 1, 0), Piece::White, None)
+            .with_ability(CardAbility::DoubleBonus),
+    ]
+}
+
+fn get_tier_two_cards() -> Vec<ProductionCard> {
+    vec![
+        ProductionCard::new(Funds::new(0, 4, 0, 2, 0, 0), Piece::Red, Some(1))
+            .with_ability(CardAbility::TakeLowerTierCard),
+        ProductionCard::new(Funds::new(0, 0, 4, 0, 2, 0), Piece::Green, Some(1))
+            .with_ability(CardAbility::TakeLowerTierCard),
+        ProductionCard::new(Funds::new(2, 0, 0, 4, 0, 0), Piece::Blue, Some(1))
+            .with_ability(CardAbility::TakeLowerTierCard),
+        ProductionCard::new(Funds::new(0, 2, 0, 0, 4, 0), Piece::Brown, Some(1))
+            .with_ability(CardAbility::TakeLowerTierCard),
+        ProductionCard::new(Funds::new(4, 0, 2, 0, 0, 0), Piece::White, Some(1))
+            .with_ability(CardAbility::TakeLowerTierCard),
+        ProductionCard::new(Funds::new(0, 5, 0, 0, 0, 0), Piece::Golden, Some(1))
+            .with_ability(CardAbility::CopyColor),
+        ProductionCard::new(Funds::new(0, 0, 0, 0, 5, 0), Piece::Golden, Some(1))
+            .with_ability(CardAbility::CopyColor),
+    ]
+}
+
+fn get_tier_three_cards() -> Vec<ProductionCard> {
+    vec![
+        ProductionCard::new(Funds::new(0, 0, 0, 6, 0, 0), Piece::Red, Some(3))
+            .with_ability(CardAbility::TakeLowerTierCard),
+        ProductionCard::new(Funds::new(0, 0, 0, 0, 6, 0), Piece::Blue, Some(3))
+            .with_ability(CardAbility::TakeLowerTierCard),
+        ProductionCard::new(Funds::new(3, 0, 0, 0, 4, 0), Piece::Green, Some(3))
+            .with_ability(CardAbility::ReserveNoble),
+        ProductionCard::new(Funds::new(0, 4, 3, 0, 0, 0), Piece::Brown, Some(3))
+            .with_ability(CardAbility::ReserveNoble),
+        ProductionCard::new(Funds::new(0, 0, 0, 3, 5, 0), Piece::White, Some(3))
+            .with_ability(CardAbility::ReserveNoble),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::board::{AbilityChoice, Action, ActionType};
+    use crate::player::{Player, PlayerId};
+    use crate::production_card::{CanonicalId, CardId, Identifiable};
+
+    #[test]
+    fn orient_cards_join_the_decks_of_their_tier() {
+        let board = get_stand_in_orient_board(3);
+        assert_eq!(board.validate(), Ok(()));
+
+        let cards_in_tier = |tier: ProductionTier| {
+            board.get_deck_size(&tier) + board.iter_cards_for_sale(&tier).count()
+        };
+        assert_eq!(cards_in_tier(ProductionTier::One), 40 + 10);
+        assert_eq!(cards_in_tier(ProductionTier::Two), 30 + 7);
+        assert_eq!(cards_in_tier(ProductionTier::Three), 20 + 5);
+
+        let (tier, card) = catalog::card(CanonicalId::new(catalog::ORIENT_FIRST_ID)).unwrap();
+        assert_eq!(tier, ProductionTier::One);
+        assert_eq!(card.ability, Some(CardAbility::CopyColor));
+    }
+
+    #[test]
+    fn orient_cards_resolve_their_abilities_in_play() {
+        let players = (1..=2)
+            .map(|id| {
+                let funds = Funds::new(5, 5, 5, 5, 5, 0);
+                Player::new(PlayerId::new(id), funds, vec![], vec![])
+            })
+            .collect();
+        let mut builder = Board::builder()
+            .players(players)
+            .bank(Funds::new(4, 4, 4, 4, 4, 5));
+        for (tier, cards) in get_stand_in_orient_cards() {
+            let deck = cards
+                .into_iter()
+                .map(|card| {
+                    let id = card.canonical_id.unwrap().id();
+                    Identifiable::new(card, CardId::new(u32::from(id)))
+                })
+                .collect();
+            builder = builder.deck(tier, deck);
+        }
+        let board = builder.build().unwrap();
+        let on_market_with = |board: &Board, tier, ability| {
+            board
+                .iter_cards_for_sale(&tier)
+                .find(|card| card.data.ability == Some(ability))
+                .map(|card| (card.uid, card.data.produces))
+                .unwrap()
+        };
+
+        let (taker, taker_color) =
+            on_market_with(&board, ProductionTier::Two, CardAbility::TakeLowerTierCard);
+        let board = Board::do_action(board, &Action::BuyCard(taker, None)).unwrap();
+        assert_eq!(board.get_action_needed(), ActionType::ResolveAbility);
+
+        let (doubler, doubler_color) =
+            on_market_with(&board, ProductionTier::One, CardAbility::DoubleBonus);
+        let take = &Action::ResolveAbility(AbilityChoice::TakeCard(doubler));
+        let board = Board::do_action(board, take).unwrap();
+
+        let mut expected = Funds::default();
+        expected[taker_color] += 1;
+        expected[doubler_color] += 2;
+        let player = board.get_players().next().unwrap();
+        assert_eq!(player.get_production(), expected);
+        assert_eq!(board.get_action_needed(), ActionType::Normal);
+        assert_eq!(board.player_to_act(), PlayerId::new(2));
+        assert_eq!(board.validate(), Ok(()));
+    }
+}
@@ -4,30 +4,59 @@ use std::vec;
 
 use crate::bank::Funds;
 use crate::board::Board;
+use crate::board::BoardBuilder;
 use crate::board::ProductionTier;
+use crate::board::SetupError;
+use crate::catalog;
 use crate::noble::Noble;
 use crate::noble::NobleId;
-use crate::piece::Piece;
 use crate::player::Player;
 use crate::player::PlayerId;
 use crate::production_card::CardId;
 use crate::production_card::Identifiable;
 use crate::production_card::ProductionCard;
+use crate::rules::Rules;
 
+/// # Panics
+///
+/// Panics if `n_of_players` is not between 2 and 4, see `try_get_original_game_board`.
 pub fn get_original_game_board(n_of_players: u8) -> Board {
-    let allowed_n_of_players = 2..=4;
-    if !allowed_n_of_players.contains(&n_of_players) {
+    try_get_original_game_board(n_of_players).unwrap_or_else(|_| {
         panic!(
             "The original game is only defined for 2 to 4 players. '{:?}' given",
             n_of_players
         )
+    })
+}
+
+pub fn try_get_original_game_board(n_of_players: u8) -> Result<Board, SetupError> {
+    get_original_game_builder(n_of_players)?
+        .nobles(get_random_nobles(n_of_players + 1))
+        .rules(get_original_game_rules())
+        .build()
+}
+
+/// Players, bank and shuffled decks of the original game, to be completed with nobles or whatever
+/// replaces them.
+pub(crate) fn get_original_game_builder(n_of_players: u8) -> Result<BoardBuilder, SetupError> {
+    get_game_builder_with_extra_cards(n_of_players, &HashMap::new())
+}
+
+/// Same as `get_original_game_builder`, with `extra_cards` shuffled into the deck of their tier.
+pub(crate) fn get_game_builder_with_extra_cards(
+    n_of_players: u8,
+    extra_cards: &HashMap<ProductionTier, Vec<ProductionCard>>,
+) -> Result<BoardBuilder, SetupError> {
+    let allowed_n_of_players = 2..=4;
+    if !allowed_n_of_players.contains(&n_of_players) {
+        return Err(SetupError::UnsupportedPlayerCount(n_of_players));
     }
     let mut players = vec![];
-    let empty_funds = &Funds::new(0, 0, 0, 0, 0, 0);
+    let empty_funds = Funds::new(0, 0, 0, 0, 0, 0);
     for n in 1..=n_of_players {
         players.push(Player::new(
-            PlayerId::new(n),
-            empty_funds.clone(),
+            PlayerId::new(u32::from(n)),
+            empty_funds,
             vec![],
             vec![],
         ));
@@ -40,45 +69,51 @@ pub fn get_original_game_board(n_of_players: u8) -> Board {
         bank = Funds::new(4, 4, 4, 4, 4, 5);
     }
 
-    let decks = get_shuffled_decks();
-    let nobles = get_random_nobles(n_of_players + 1);
-
-    Board::new(players, bank, decks, nobles)
+    let mut builder = Board::builder().players(players).bank(bank);
+    for (tier, cards) in get_shuffled_decks(extra_cards) {
+        builder = builder.deck(tier, cards);
+    }
+    Ok(builder)
 }
 
-fn get_shuffled_decks() -> HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>> {
-    let mut unique_id = 0;
-    let tier_one: Vec<Identifiable<ProductionCard, CardId>> = get_tier_one_cards()
-        .iter()
-        .map(|c| {
-            unique_id += 1;
-            Identifiable::new(c.clone(), CardId::new(unique_id))
-        })
-        .collect();
+/// Rules from the rulebook that a bare `Board` does not enforce.
+pub fn get_original_game_rules() -> Rules {
+    Rules {
+        pass_only_when_stuck: true,
+        ..Rules::default()
+    }
+}
 
-    let tier_two: Vec<Identifiable<ProductionCard, CardId>> = get_tier_two_cards()
+/// Shuffles the cards of every tier into its deck, numbering the base cards first and then
+/// `extra_cards`, tier by tier.
+fn get_shuffled_decks(
+    extra_cards: &HashMap<ProductionTier, Vec<ProductionCard>>,
+) -> HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>> {
+    let mut base_cards = catalog::get_original_cards();
+    let base_cards = ProductionTier::ALL
         .iter()
-        .map(|c| {
-            unique_id += 1;
-            Identifiable::new(c.clone(), CardId::new(unique_id))
-        })
-        .collect();
-
-    let tier_three: Vec<Identifiable<ProductionCard, CardId>> = get_tier_three_cards()
+        .filter_map(|tier| base_cards.remove(tier).map(|cards| (*tier, cards)));
+    let extra_cards = ProductionTier::ALL
         .iter()
-        .map(|c| {
-            unique_id += 1;
-            Identifiable::new(c.clone(), CardId::new(unique_id))
-        })
-        .collect();
+        .filter_map(|tier| extra_cards.get(tier).map(|cards| (*tier, cards.clone())));
 
+    let mut unique_id = 0;
     let mut decks: HashMap<ProductionTier, Vec<Identifiable<ProductionCard, CardId>>> =
         HashMap::new();
-    decks.insert(ProductionTier::One, shuffle_vec(tier_one));
-    decks.insert(ProductionTier::Two, shuffle_vec(tier_two));
-    decks.insert(ProductionTier::Three, shuffle_vec(tier_three));
+    for (tier, cards) in base_cards.into_iter().chain(extra_cards) {
+        decks
+            .entry(tier)
+            .or_default()
+            .extend(cards.into_iter().map(|c| {
+                unique_id += 1;
+                Identifiable::new(c, CardId::new(unique_id))
+            }));
+    }
 
     decks
+        .into_iter()
+        .map(|(tier, deck)| (tier, shuffle_vec(deck)))
+        .collect()
 }
 
 fn shuffle_vec<T: Clone>(v: Vec<T>) -> Vec<T> {
@@ -86,7 +121,7 @@ fn shuffle_vec<T: Clone>(v: Vec<T>) -> Vec<T> {
     v.choose_multiple(rng, v.len()).cloned().collect()
 }
 
-fn get_random_nobles(quantity: u8) -> Vec<Noble> {
+pub(crate) fn get_random_nobles(quantity: u8) -> Vec<Noble> {
     let nobles = get_nobles();
     let rng = &mut rand::thread_rng();
     nobles
@@ -95,109 +130,6 @@ fn get_random_nobles(quantity: u8) -> Vec<Noble> {
         .collect()
 }
 
-fn get_tier_one_cards() -> Vec<ProductionCard> {
-    vec![
-        ProductionCard::new(Funds::new(0, 0, 2, 0, 2, 0), Piece::Green, None),
-        ProductionCard::new(Funds::new(1, 0, 1, 2, 1, 0), Piece::Green, None),
-        ProductionCard::new(Funds::new(1, 0, 1, 1, 1, 0), Piece::Green, None),
-        ProductionCard::new(Funds::new(0, 3, 0, 0, 0, 0), Piece::Brown, None),
-        ProductionCard::new(Funds::new(0, 0, 4, 0, 0, 0), Piece::Brown, Some(1)),
-        ProductionCard::new(Funds::new(1, 1, 2, 0, 1, 0), Piece::Brown, None),
-        ProductionCard::new(Funds::new(1, 3, 1, 0, 0, 0), Piece::Blue, None),
-        ProductionCard::new(Funds::new(2, 1, 0, 1, 1, 0), Piece::Blue, None),
-        ProductionCard::new(Funds::new(0, 2, 0, 2, 0, 0), Piece::Blue, None),
-        ProductionCard::new(Funds::new(0, 0, 2, 2, 0, 0), Piece::White, None),
-        ProductionCard::new(Funds::new(0, 0, 0, 0, 3, 0), Piece::Red, None),
-        ProductionCard::new(Funds::new(0, 0, 0, 4, 0, 0), Piece::Green, Some(1)),
-        ProductionCard::new(Funds::new(0, 1, 3, 0, 1, 0), Piece::Green, None),
-        ProductionCard::new(Funds::new(2, 0, 1, 2, 0, 0), Piece::Green, None),
-        ProductionCard::new(Funds::new(1, 0, 0, 3, 1, 0), Piece::Red, None),
-        ProductionCard::new(Funds::new(0, 0, 0, 0, 4, 0), Piece::Red, Some(1)),
-        ProductionCard::new(Funds::new(0, 0, 3, 0, 0, 0), Piece::White, None),
-        ProductionCard::new(Funds::new(2, 2, 0, 0, 0, 0), Piece::Brown, None),
-        ProductionCard::new(Funds::new(3, 1, 0, 1, 0, 0), Piece::Brown, None),
-        ProductionCard::new(Funds::new(0, 2, 0, 0, 2, 0), Piece::Brown, None),
-        ProductionCard::new(Funds::new(1, 1, 0, 1, 1, 0), Piece::Blue, None),
-        ProductionCard::new(Funds::new(4, 0, 0, 0, 0, 0), Piece::Blue, Some(1)),
-        ProductionCard::new(Funds::new(0, 1, 0, 2, 2, 0), Piece::Red, None),
-        ProductionCard::new(Funds::new(2, 0, 0, 0, 2, 0), Piece::Red, None),
-        ProductionCard::new(Funds::new(0, 1, 2, 0, 0, 0), Piece::Red, None),
-        ProductionCard::new(Funds::new(1, 0, 2, 0, 2, 0), Piece::Brown, None),
-        ProductionCard::new(Funds::new(2, 2, 0, 0, 1, 0), Piece::Blue, None),
-        ProductionCard::new(Funds::new(0, 0, 0, 3, 0, 0), Piece::Blue, None),
-        ProductionCard::new(Funds::new(0, 0, 2, 1, 2, 0), Piece::White, None),
-        ProductionCard::new(Funds::new(1, 1, 1, 1, 0, 0), Piece::White, None),
-        ProductionCard::new(Funds::new(0, 0, 0, 2, 1, 0), Piece::Blue, None),
-        ProductionCard::new(Funds::new(1, 1, 1, 0, 1, 0), Piece::Brown, None),
-        ProductionCard::new(Funds::new(2, 0, 2, 0, 0, 0), Piece::Green, None),
-        ProductionCard::new(Funds::new(3, 0, 0, 0, 0, 0), Piece::Green, None),
-        ProductionCard::new(Funds::new(1, 2, 1, 1, 0, 0), Piece::White, None),
-        ProductionCard::new(Funds::new(2, 0, 0, 1, 0, 0), Piece::White, None),
-        ProductionCard::new(Funds::new(0, 0, 1, 1, 3, 0), Piece::White, None),
-        ProductionCard::new(Funds::new(0, 4, 0, 0, 0, 0), Piece::White, Some(1)),
-        ProductionCard::new(Funds::new(0, 1, 1, 1, 2, 0), Piece::Red, None),
-        ProductionCard::new(Funds::new(0, 1, 1, 1, 1, 0), Piece::Red, None),
-    ]
-}
-fn get_tier_two_cards() -> Vec<ProductionCard> {
-    vec![
-        ProductionCard::new(Funds::new(0, 3, 0, 2, 3, 0), Piece::Brown, Some(1)),
-        ProductionCard::new(Funds::new(3, 2, 0, 0, 3, 0), Piece::Green, Some(1)),
-        ProductionCard::new(Funds::new(2, 0, 3, 3, 0, 0), Piece::Red, Some(1)),
-        ProductionCard::new(Funds::new(0, 0, 6, 0, 0, 0), Piece::Blue, Some(3)),
-        ProductionCard::new(Funds::new(1, 0, 0, 4, 2, 0), Piece::Blue, Some(2)),
-        ProductionCard::new(Funds::new(3, 0, 3, 0, 2, 0), Piece::White, Some(1)),
-        ProductionCard::new(Funds::new(0, 0, 2, 1, 4, 0), Piece::Green, Some(2)),
-        ProductionCard::new(Funds::new(0, 0, 5, 0, 0, 0), Piece::Blue, Some(2)),
-        ProductionCard::new(Funds::new(0, 0, 0, 0, 5, 0), Piece::Brown, Some(2)),
-        ProductionCard::new(Funds::new(2, 0, 0, 3, 2, 0), Piece::Red, Some(1)),
-        ProductionCard::new(Funds::new(0, 0, 0, 0, 6, 0), Piece::White, Some(3)),
-        ProductionCard::new(Funds::new(0, 2, 4, 0, 1, 0), Piece::Red, Some(2)),
-        ProductionCard::new(Funds::new(5, 0, 0, 0, 0, 0), Piece::White, Some(2)),
-        ProductionCard::new(Funds::new(0, 6, 0, 0, 0, 0), Piece::Green, Some(3)),
-        ProductionCard::new(Funds::new(0, 5, 0, 0, 0, 0), Piece::Green, Some(2)),
-        ProductionCard::new(Funds::new(0, 0, 0, 5, 0, 0), Piece::Red, Some(2)),
-        ProductionCard::new(Funds::new(0, 2, 2, 0, 3, 0), Piece::Brown, Some(1)),
-        ProductionCard::new(Funds::new(0, 0, 0, 6, 0, 0), Piece::Brown, Some(3)),
-        ProductionCard::new(Funds::new(3, 5, 0, 0, 0, 0), Piece::Brown, Some(2)),
-        ProductionCard::new(Funds::new(0, 3, 5, 0, 0, 0), Piece::Green, Some(2)),
-        ProductionCard::new(Funds::new(0, 3, 2, 3, 0, 0), Piece::Blue, Some(1)),
-        ProductionCard::new(Funds::new(2, 2, 2, 0, 0, 0), Piece::Blue, Some(1)),
-        ProductionCard::new(Funds::new(0, 0, 3, 0, 5, 0), Piece::Blue, Some(2)),
-        ProductionCard::new(Funds::new(0, 0, 3, 2, 2, 0), Piece::Green, Some(1)),
-        ProductionCard::new(Funds::new(5, 0, 0, 3, 0, 0), Piece::White, Some(2)),
-        ProductionCard::new(Funds::new(4, 1, 0, 2, 0, 0), Piece::White, Some(2)),
-        ProductionCard::new(Funds::new(2, 4, 0, 1, 0, 0), Piece::Brown, Some(2)),
-        ProductionCard::new(Funds::new(2, 3, 0, 2, 0, 0), Piece::White, Some(1)),
-        ProductionCard::new(Funds::new(6, 0, 0, 0, 0, 0), Piece::Red, Some(3)),
-        ProductionCard::new(Funds::new(0, 0, 0, 5, 3, 0), Piece::Red, Some(2)),
-    ]
-}
-fn get_tier_three_cards() -> Vec<ProductionCard> {
-    vec![
-        ProductionCard::new(Funds::new(3, 0, 3, 3, 5, 0), Piece::Green, Some(3)),
-        ProductionCard::new(Funds::new(3, 3, 0, 5, 3, 0), Piece::Blue, Some(3)),
-        ProductionCard::new(Funds::new(0, 3, 6, 0, 3, 0), Piece::Green, Some(4)),
-        ProductionCard::new(Funds::new(0, 0, 0, 7, 3, 0), Piece::White, Some(5)),
-        ProductionCard::new(Funds::new(7, 0, 0, 0, 0, 0), Piece::Brown, Some(4)),
-        ProductionCard::new(Funds::new(6, 3, 0, 3, 0, 0), Piece::Brown, Some(4)),
-        ProductionCard::new(Funds::new(0, 0, 3, 3, 6, 0), Piece::Blue, Some(4)),
-        ProductionCard::new(Funds::new(0, 7, 0, 0, 0, 0), Piece::Red, Some(4)),
-        ProductionCard::new(Funds::new(0, 3, 5, 3, 3, 0), Piece::Red, Some(3)),
-        ProductionCard::new(Funds::new(3, 6, 3, 0, 0, 0), Piece::Red, Some(4)),
-        ProductionCard::new(Funds::new(3, 0, 0, 6, 3, 0), Piece::White, Some(4)),
-        ProductionCard::new(Funds::new(3, 5, 3, 0, 3, 0), Piece::Brown, Some(3)),
-        ProductionCard::new(Funds::new(0, 0, 3, 0, 7, 0), Piece::Blue, Some(5)),
-        ProductionCard::new(Funds::new(3, 7, 0, 0, 0, 0), Piece::Red, Some(5)),
-        ProductionCard::new(Funds::new(0, 3, 7, 0, 0, 0), Piece::Green, Some(5)),
-        ProductionCard::new(Funds::new(0, 0, 0, 7, 0, 0), Piece::White, Some(4)),
-        ProductionCard::new(Funds::new(0, 0, 7, 0, 0, 0), Piece::Green, Some(4)),
-        ProductionCard::new(Funds::new(5, 3, 3, 3, 0, 0), Piece::White, Some(3)),
-        ProductionCard::new(Funds::new(0, 0, 0, 0, 7, 0), Piece::Blue, Some(4)),
-        ProductionCard::new(Funds::new(7, 0, 0, 3, 0, 0), Piece::Brown, Some(5)),
-    ]
-}
-
 fn get_nobles() -> Vec<Noble> {
     vec![
         Noble::new(NobleId::new(1), Funds::new(0, 4, 4, 0, 0, 0)),
@@ -212,3 +144,40 @@ fn get_nobles() -> Vec<Noble> {
         Noble::new(NobleId::new(10), Funds::new(0, 0, 3, 3, 3, 0)),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn original_game_is_defined_for_2_to_4_players() {
+        for n_of_players in 2..=4 {
+            let board = try_get_original_game_board(n_of_players).unwrap();
+            assert_eq!(board.get_players().len(), n_of_players as usize);
+            assert_eq!(board.get_nobles().len(), n_of_players as usize + 1);
+            assert_eq!(board.validate(), Ok(()));
+            assert_eq!(board.get_rules(), &get_original_game_rules());
+        }
+
+        let board = get_original_game_board(2);
+        let canonical_ids: HashSet<_> = ProductionTier::ALL
+            .iter()
+            .flat_map(|tier| board.iter_deck(tier).chain(board.iter_cards_for_sale(tier)))
+            .map(|card| {
+                card.data
+                    .canonical_id
+                    .expect("Every card is in the catalog")
+            })
+            .collect();
+        assert_eq!(canonical_ids.len(), 90);
+
+        for n_of_players in [0, 1, 5] {
+            assert_eq!(
+                try_get_original_game_board(n_of_players).unwrap_err(),
+                SetupError::UnsupportedPlayerCount(n_of_players)
+            );
+        }
+    }
+}
@@ -1,11 +1,288 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
-pub enum Piece {
-    Red,
-    Green,
-    Blue,
-    Brown,
-    White,
-    Golden,
+/// A kind of token.
+///
+/// The pieces of the original game and of Duel are built in, like `Piece::Red`. Variants with
+/// other colors add them to their `PieceSet`, which hands out custom pieces. Pieces are stored by
+/// name for the built-in ones, like `"Red"`, and by index otherwise, like `"7"`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Piece(u8);
+
+/// What a piece is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PieceKind {
+    /// Produced by cards and paid for them.
+    Color,
+    /// Stands for any color when paying, and is only gained by reserving.
+    Wildcard,
+    /// Neither a color nor a wildcard, like the pearls of Duel.
+    Special,
+}
+
+#[allow(non_upper_case_globals)]
+impl Piece {
+    pub const Red: Piece = Piece(0);
+    pub const Green: Piece = Piece(1);
+    pub const Blue: Piece = Piece(2);
+    pub const Brown: Piece = Piece(3);
+    pub const White: Piece = Piece(4);
+    pub const Golden: Piece = Piece(5);
+    /// Only used by the Duel variant.
+    pub const Pearl: Piece = Piece(6);
+
+    /// The built-in pieces, in the order used by `Funds` for storage and iteration.
+    pub const ALL: [Piece; 7] = [
+        Piece::Red,
+        Piece::Green,
+        Piece::Blue,
+        Piece::Brown,
+        Piece::White,
+        Piece::Golden,
+        Piece::Pearl,
+    ];
+
+    /// The colors of the original game, leaving out golden pieces and pearls.
+    pub const COLORS: [Piece; 5] = [
+        Piece::Red,
+        Piece::Green,
+        Piece::Blue,
+        Piece::Brown,
+        Piece::White,
+    ];
+
+    /// How many pieces, built-in and custom ones, a game can use.
+    pub const CAPACITY: usize = 16;
+
+    /// The piece stored at this index of `Funds`, if there is room for it.
+    pub const fn new(index: usize) -> Option<Piece> {
+        if index < Piece::CAPACITY {
+            Some(Piece(index as u8))
+        } else {
+            None
+        }
+    }
+
+    /// The nth custom color, counting from 0.
+    pub const fn custom(n: usize) -> Option<Piece> {
+        Piece::new(Piece::ALL.len() + n)
+    }
+
+    /// Every piece a game can use, built-in ones first.
+    pub fn every() -> impl Iterator<Item = Piece> {
+        (0..Piece::CAPACITY as u8).map(Piece)
+    }
+
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub const fn kind(self) -> PieceKind {
+        match self {
+            Piece::Golden => PieceKind::Wildcard,
+            Piece::Pearl => PieceKind::Special,
+            _ => PieceKind::Color,
+        }
+    }
+
+    pub const fn is_wildcard(self) -> bool {
+        matches!(self.kind(), PieceKind::Wildcard)
+    }
+
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Piece::Red => Some("Red"),
+            Piece::Green => Some("Green"),
+            Piece::Blue => Some("Blue"),
+            Piece::Brown => Some("Brown"),
+            Piece::White => Some("White"),
+            Piece::Golden => Some("Golden"),
+            Piece::Pearl => Some("Pearl"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "Color{}", self.0),
+        }
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Serialize for Piece {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.name() {
+            Some(name) => serializer.serialize_str(name),
+            None => serializer.collect_str(&self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Piece {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let piece = String::deserialize(deserializer)?;
+        Piece::ALL
+            .into_iter()
+            .find(|built_in| built_in.name() == Some(piece.as_str()))
+            .or_else(|| piece.parse().ok().and_then(Piece::new))
+            .ok_or_else(|| {
+                serde::de::Error::invalid_value(serde::de::Unexpected::Str(&piece), &"a piece")
+            })
+    }
+}
+
+/// The pieces a game is played with: its colors, whether it has a wildcard, and its special
+/// pieces. The default is the original game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceSet {
+    colors: Vec<Piece>,
+    wildcard: bool,
+    special: Vec<Piece>,
+    /// Names of the custom colors.
+    names: BTreeMap<Piece, String>,
+}
+
+impl PieceSet {
+    /// A game without any color yet, see `PieceSet::add_color`.
+    pub fn new(wildcard: bool) -> Self {
+        Self {
+            colors: vec![],
+            wildcard,
+            special: vec![],
+            names: BTreeMap::new(),
+        }
+    }
+
+    /// The original colors, golden pieces and pearls.
+    pub fn duel() -> Self {
+        Self {
+            special: vec![Piece::Pearl],
+            ..Self::default()
+        }
+    }
+
+    /// Adds a custom color, returning its piece, or `None` once every piece is used.
+    pub fn add_color(&mut self, name: impl Into<String>) -> Option<Piece> {
+        let piece = (0..)
+            .map_while(Piece::custom)
+            .find(|piece| !self.contains(*piece))?;
+        self.colors.push(piece);
+        self.names.insert(piece, name.into());
+        Some(piece)
+    }
+
+    /// Plays with a built-in color, like `Piece::Red`.
+    pub fn with_color(mut self, piece: Piece) -> Self {
+        if piece.kind() == PieceKind::Color && !self.colors.contains(&piece) {
+            self.colors.push(piece);
+        }
+        self
+    }
+
+    pub fn colors(&self) -> &[Piece] {
+        &self.colors
+    }
+
+    pub fn wildcard(&self) -> Option<Piece> {
+        self.wildcard.then_some(Piece::Golden)
+    }
+
+    /// Every piece of the game: its colors, then the wildcard and the special pieces.
+    pub fn pieces(&self) -> impl Iterator<Item = Piece> + '_ {
+        self.colors
+            .iter()
+            .copied()
+            .chain(self.wildcard())
+            .chain(self.special.iter().copied())
+    }
+
+    pub fn is_color(&self, piece: Piece) -> bool {
+        self.colors.contains(&piece)
+    }
+
+    pub fn contains(&self, piece: Piece) -> bool {
+        self.pieces().any(|other| other == piece)
+    }
+
+    /// The name to show for a piece, given by `PieceSet::add_color` for custom colors.
+    pub fn name(&self, piece: Piece) -> String {
+        self.names
+            .get(&piece)
+            .cloned()
+            .unwrap_or_else(|| piece.to_string())
+    }
+}
+
+impl Default for PieceSet {
+    fn default() -> Self {
+        Self {
+            colors: Piece::COLORS.to_vec(),
+            wildcard: true,
+            special: vec![],
+            names: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_colors_take_the_free_pieces() {
+        let mut pieces = PieceSet::new(false).with_color(Piece::Red);
+        let silver = pieces.add_color("Silver").unwrap();
+        let copper = pieces.add_color("Copper").unwrap();
+
+        assert_eq!(silver, Piece::custom(0).unwrap());
+        assert_eq!(copper.index(), 8);
+        assert_eq!(pieces.colors(), &[Piece::Red, silver, copper]);
+        assert_eq!(pieces.wildcard(), None);
+        assert_eq!(pieces.name(copper), "Copper");
+        assert_eq!(pieces.name(Piece::Red), "Red");
+        assert!(!pieces.contains(Piece::Blue));
+
+        let free = Piece::CAPACITY - Piece::ALL.len() - 2;
+        for n in 0..free {
+            assert!(pieces.add_color(format!("Color {}", n)).is_some());
+        }
+        assert_eq!(pieces.add_color("One too many"), None);
+    }
+
+    #[test]
+    fn wildcards_are_not_colors() {
+        let pieces = PieceSet::duel();
+
+        assert!(Piece::Golden.is_wildcard());
+        assert_eq!(Piece::Pearl.kind(), PieceKind::Special);
+        assert_eq!(Piece::custom(3).unwrap().kind(), PieceKind::Color);
+        assert!(!pieces.is_color(Piece::Golden));
+        assert!(!pieces.is_color(Piece::Pearl));
+        assert!(pieces.contains(Piece::Pearl));
+        assert_eq!(pieces.pieces().count(), 7);
+        assert!(!PieceSet::default().contains(Piece::Pearl));
+    }
+
+    #[test]
+    fn pieces_are_stored_by_name_or_index() {
+        let pieces = vec![Piece::Brown, Piece::custom(1).unwrap()];
+
+        let json = serde_json::to_string(&pieces).unwrap();
+        assert_eq!(json, r#"["Brown","8"]"#);
+        let loaded: Vec<Piece> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, pieces);
+        assert!(serde_json::from_str::<Piece>(r#""16""#).is_err());
+        assert!(serde_json::from_str::<Piece>(r#""Silver""#).is_err());
+    }
 }
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 
 use serde::Deserialize;
 use serde::Serialize;
@@ -6,27 +7,36 @@ use serde::Serialize;
 use super::bank;
 use super::board;
 use super::noble::Noble;
-use super::noble::NOBLE_VICTORY_POINTS;
 use super::piece::Piece;
 use super::production_card;
 use super::production_card::CardId;
 use super::production_card::Identifiable;
+#[cfg(feature = "trading-posts")]
+use super::trading_posts::PlayerPowers;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub const MAXIMUM_RESERVED_CARDS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PlayerId {
-    id: u8,
+    id: u32,
 }
 
 impl PlayerId {
-    pub fn new(id: u8) -> Self {
+    pub fn new(id: u32) -> Self {
         Self { id }
     }
 
-    pub fn id(&self) -> u8 {
+    pub fn id(&self) -> u32 {
         self.id
     }
 }
 
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "player {}", self.id)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Player {
     pub id: PlayerId,
@@ -34,6 +44,13 @@ pub struct Player {
     pub production_cards: Vec<Identifiable<production_card::ProductionCard, CardId>>,
     pub reserved_cards: Vec<Identifiable<production_card::ProductionCard, CardId>>,
     pub nobles: Vec<Noble>,
+    /// Nobles reserved by a card ability, waiting for this player to attract them.
+    #[serde(default)]
+    pub reserved_nobles: Vec<Noble>,
+    /// Powers from the trading posts claimed so far.
+    #[cfg(feature = "trading-posts")]
+    #[serde(default)]
+    pub powers: PlayerPowers,
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -43,6 +60,41 @@ pub enum ReserveOperationFail {
     CardNotFound,
 }
 
+impl fmt::Display for ReserveOperationFail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReserveOperationFail::NotEnoughPieces(missing) => {
+                let missing = bank::Funds::new_from(missing.iter().copied().collect());
+                write!(f, "You need ")?;
+                missing.write_list(f, " more")?;
+                write!(f, " to reserve this card")
+            }
+            ReserveOperationFail::MaximumReservedCardsExceed => write!(
+                f,
+                "You cannot reserve more than {} cards",
+                MAXIMUM_RESERVED_CARDS
+            ),
+            ReserveOperationFail::CardNotFound => write!(f, "This card is not on sale"),
+        }
+    }
+}
+
+impl Error for ReserveOperationFail {}
+
+/// Where the victory points of a player come from. Sums saturate instead of overflowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    pub player: PlayerId,
+    pub card_points: u16,
+    pub noble_points: u16,
+    /// Points from trading posts.
+    #[cfg(feature = "trading-posts")]
+    #[serde(default)]
+    pub power_points: u16,
+    pub total: u16,
+    pub production_cards: usize,
+}
+
 #[derive(Debug)]
 pub struct ReserveOperationSuccess {
     pub bank_funds: bank::Funds,
@@ -68,33 +120,73 @@ impl Player {
             production_cards,
             reserved_cards,
             nobles: vec![],
+            reserved_nobles: vec![],
+            #[cfg(feature = "trading-posts")]
+            powers: PlayerPowers::default(),
         }
     }
 
     pub fn get_production(&self) -> bank::Funds {
-        Self::get_funds_from_production_cards(self.production_cards.clone())
+        let mut funds = bank::Funds::default();
+
+        for card in &self.production_cards {
+            funds[card.data.produces] += card.data.bonus();
+        }
+
+        funds
+    }
+
+    pub fn can_afford(&self, card: &production_card::ProductionCard) -> bool {
+        self.payment_plan(card).is_ok()
+    }
+
+    /// Pieces this player would spend to buy the card, be it on the market or reserved.
+    pub fn payment_plan(
+        &self,
+        card: &production_card::ProductionCard,
+    ) -> Result<production_card::Payment, board::BuyOperationFail> {
+        card.payment_plan_with(self.funds, self.get_production(), self.has_double_golden())
+    }
+
+    /// Checks a payment this player chose for the card instead of the automatic one.
+    pub fn check_payment(
+        &self,
+        card: &production_card::ProductionCard,
+        payment: bank::Funds,
+    ) -> Result<production_card::Payment, board::BuyOperationFail> {
+        card.check_payment_with(
+            payment,
+            self.funds,
+            self.get_production(),
+            self.has_double_golden(),
+        )
+    }
+
+    /// How many more golden pieces this player would need to buy the card.
+    pub fn shortfall(&self, card: &production_card::ProductionCard) -> u8 {
+        card.shortfall_with(self.funds, self.get_production(), self.has_double_golden())
+    }
+
+    /// Whether each golden piece this player spends stands for two, see
+    /// `trading_posts::PostPower::DoubleGolden`.
+    pub(crate) fn has_double_golden(&self) -> bool {
+        #[cfg(feature = "trading-posts")]
+        let double_golden = self.powers.double_golden;
+        #[cfg(not(feature = "trading-posts"))]
+        let double_golden = false;
+        double_golden
     }
 
     pub fn get_funds_from_production_cards(
         production_cards: Vec<Identifiable<production_card::ProductionCard, CardId>>,
     ) -> bank::Funds {
-        let mut funds = HashMap::from([
-            (Piece::Red, 0),
-            (Piece::Green, 0),
-            (Piece::Blue, 0),
-            (Piece::Brown, 0),
-            (Piece::White, 0),
-            (Piece::Golden, 0),
-        ]);
+        let mut funds = bank::Funds::default();
 
         for card in production_cards {
-            let card = card.data;
-            let current_funds = funds.clone();
-            let current_amount = current_funds.get(&card.produces).unwrap();
-            funds.insert(card.produces, *current_amount + 1);
+            funds[card.data.produces] += card.data.bonus();
         }
 
-        bank::Funds::new_from(funds)
+        funds
     }
 
     pub fn reserve_card(
@@ -106,51 +198,66 @@ impl Player {
 
         let player = board.get_who_is_playing_now();
 
-        if player.reserved_cards.len() >= 3 {
+        if player.reserved_cards.len() >= MAXIMUM_RESERVED_CARDS {
             return Result::Err(ReserveOperationFail::MaximumReservedCardsExceed);
         }
 
-        let bank_funds = &board.bank;
-
-        let mut bank = bank_funds.funds.clone();
+        let mut bank_funds = board.get_bank();
         let mut player_updated = player.clone();
-        let bank_golden_pieces = bank_funds.funds.get(&Piece::Golden).unwrap_or(&0);
         player_updated.reserved_cards.push(card);
 
-        if *bank_golden_pieces > 0 {
-            bank.insert(Piece::Golden, bank_golden_pieces - 1);
-            let player_golden_quantity = *player.funds.funds.get(&Piece::Golden).unwrap_or(&0);
-            player_updated
-                .funds
-                .funds
-                .insert(Piece::Golden, player_golden_quantity + 1);
+        if bank_funds[Piece::Golden] > 0 {
+            if let Some(golden) = player_updated.funds[Piece::Golden].checked_add(1) {
+                bank_funds[Piece::Golden] -= 1;
+                player_updated.funds[Piece::Golden] = golden;
+            }
         }
 
-        Result::Ok(ReserveOperationSuccess::new(
-            bank::Funds::new_from(bank),
-            player_updated,
-        ))
+        Result::Ok(ReserveOperationSuccess::new(bank_funds, player_updated))
     }
 
-    pub fn total_victory_points(&self) -> u8 {
-        let mut total_points = 0;
+    pub fn total_victory_points(&self) -> u16 {
+        self.score_breakdown().total
+    }
+
+    pub fn score_breakdown(&self) -> ScoreBreakdown {
+        let mut card_points: u16 = 0;
         for p in &self.production_cards {
             if let Some(points) = p.data.victory_points {
-                total_points += points;
+                card_points = card_points.saturating_add(u16::from(points));
             }
         }
 
-        for _ in &self.nobles {
-            total_points += NOBLE_VICTORY_POINTS;
+        let mut noble_points: u16 = 0;
+        for noble in &self.nobles {
+            noble_points = noble_points.saturating_add(u16::from(noble.victory_points));
         }
 
-        total_points
+        #[cfg(feature = "trading-posts")]
+        let power_points = self.powers.points();
+        #[cfg(not(feature = "trading-posts"))]
+        let power_points = 0;
+
+        ScoreBreakdown {
+            player: self.id,
+            card_points,
+            noble_points,
+            #[cfg(feature = "trading-posts")]
+            power_points,
+            total: card_points
+                .saturating_add(noble_points)
+                .saturating_add(power_points),
+            production_cards: self.production_cards.len(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use std::collections::HashMap;
+
+    use crate::noble::NobleId;
     use board::{Board, ProductionTier};
     use production_card::ProductionCard;
 
@@ -176,6 +283,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn can_reserve_card() {
         let player_funds = bank::Funds::new(3, 2, 3, 1, 1, 1);
 
@@ -204,13 +312,14 @@ mod tests {
         assert_eq!(result.player.funds, expected_player_funds);
 
         assert_eq!(result.player.reserved_cards.len(), 1);
-        let player_prod_card = result.player.reserved_cards.get(0).unwrap();
+        let player_prod_card = result.player.reserved_cards.first().unwrap();
         let expected_prod_card = get_production_card(CardId::new(1));
         assert_eq!(player_prod_card.uid, expected_prod_card.uid);
         assert_eq!(player_prod_card.data, expected_prod_card.data);
     }
 
     #[test]
+    #[allow(deprecated)]
     fn cannot_reserve_more_than_3() {
         let prod_card = production_card::ProductionCard::new(get_default_cost(), Piece::Red, None);
 
@@ -247,6 +356,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn do_not_get_golden_if_there_is_none() {
         let bank_funds = bank::Funds::new(8, 8, 8, 8, 8, 0);
         let player_funds = bank::Funds::new(3, 2, 3, 1, 1, 1);
@@ -277,9 +387,87 @@ mod tests {
         assert_eq!(result.player.funds, expected_player_funds);
 
         assert_eq!(result.player.reserved_cards.len(), 1);
-        let player_prod_card = result.player.reserved_cards.get(0).unwrap();
+        let player_prod_card = result.player.reserved_cards.first().unwrap();
         let expected_prod_card = get_production_card(CardId::new(1));
         assert_eq!(player_prod_card.uid, expected_prod_card.uid);
         assert_eq!(player_prod_card.data, expected_prod_card.data);
     }
+
+    #[test]
+    fn plans_payment_with_production_discount_and_gold() {
+        let cost = bank::Funds::new(0, 1, 2, 1, 1, 0);
+        let card = ProductionCard::new(cost, Piece::Red, None);
+        let production = vec![Identifiable::new(
+            ProductionCard::new(cost, Piece::Blue, None),
+            CardId::new(10),
+        )];
+        let funds = bank::Funds::new(2, 1, 0, 0, 1, 2);
+        let player = Player::new(PlayerId::new(1), funds, production, vec![]);
+
+        assert!(player.can_afford(&card));
+        assert_eq!(player.shortfall(&card), 0);
+
+        let payment = player.payment_plan(&card).unwrap();
+        assert_eq!(payment.colored, bank::Funds::new(0, 1, 0, 0, 1, 0));
+        assert_eq!(payment.golden, 2);
+        assert_eq!(payment.total(), bank::Funds::new(0, 1, 0, 0, 1, 2));
+    }
+
+    #[test]
+    fn nobles_score_their_own_points() {
+        let mut player = get_initial_player(PlayerId::new(1));
+        player
+            .production_cards
+            .push(get_production_card(CardId::new(1)));
+        player.nobles = vec![
+            Noble::new(NobleId::new(1), get_default_cost()),
+            Noble::new(NobleId::new(2), get_default_cost()).with_victory_points(5),
+        ];
+
+        let score = player.score_breakdown();
+        assert_eq!(score.noble_points, 8);
+        assert_eq!(score.total, 9);
+        assert_eq!(player.total_victory_points(), 9);
+    }
+
+    #[test]
+    fn ids_and_points_go_beyond_255() {
+        let mut player = get_initial_player(PlayerId::new(70_000));
+        player.production_cards = (0..20)
+            .map(|i| {
+                let card = ProductionCard::new(get_default_cost(), Piece::Red, Some(20));
+                Identifiable::new(card, CardId::new(1_000 + i))
+            })
+            .collect();
+
+        assert_eq!(player.total_victory_points(), 400);
+        let json = serde_json::to_string(&player).unwrap();
+        assert!(json.contains(r#""id":70000"#));
+        assert_eq!(serde_json::from_str::<Player>(&json).unwrap(), player);
+        assert_eq!(
+            serde_json::from_str::<PlayerId>(r#"{"id":3}"#).unwrap(),
+            PlayerId::new(3)
+        );
+    }
+
+    #[test]
+    fn reports_shortfall_in_golden_pieces() {
+        let card = ProductionCard::new(bank::Funds::new(0, 1, 2, 1, 1, 0), Piece::Red, None);
+        let player = Player::new(
+            PlayerId::new(1),
+            bank::Funds::new(0, 1, 0, 0, 0, 1),
+            vec![],
+            vec![Identifiable::new(card.clone(), CardId::new(6))],
+        );
+
+        let reserved = &player.reserved_cards[0].data;
+        assert!(!player.can_afford(reserved));
+        assert_eq!(player.shortfall(reserved), 3);
+        assert_eq!(
+            player.payment_plan(reserved),
+            Err(board::BuyOperationFail::NotEnoughFunds(bank::Funds::new(
+                0, 0, 2, 0, 1, 0
+            )))
+        );
+    }
 }
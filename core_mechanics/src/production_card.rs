@@ -1,3 +1,5 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 use super::bank;
@@ -10,21 +12,92 @@ pub struct ProductionCard {
     pub cost: bank::Funds,
     pub produces: Piece,
     pub victory_points: Option<u8>,
+    #[serde(default)]
+    pub ability: Option<CardAbility>,
+    /// The card in the `catalog`, the same in every game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_id: Option<CanonicalId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Identifies the artwork of the card for user interfaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub art_key: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Effects of the cards of the Orient expansion, resolved when the card is bought.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CardAbility {
+    /// Produces two pieces of its color instead of one.
+    DoubleBonus,
+    /// Takes a card of the tier below from the market, for free.
+    TakeLowerTierCard,
+    /// Reserves a noble, that can then only visit this player.
+    ReserveNoble,
+    /// Produces the color of a card the player already owns, chosen when it is bought. Until
+    /// then the card is listed as producing `Piece::Golden`.
+    CopyColor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CardId {
-    id: u8,
+    id: u32,
 }
 
 impl CardId {
-    pub fn new(id: u8) -> Self {
+    pub fn new(id: u32) -> Self {
+        Self { id }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl fmt::Display for CardId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "card {}", self.id)
+    }
+}
+
+/// Identifies a card of the `catalog` across games, unlike `CardId` that follows the shuffle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CanonicalId {
+    id: u16,
+}
+
+impl CanonicalId {
+    pub fn new(id: u16) -> Self {
         Self { id }
     }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+}
+
+impl fmt::Display for CanonicalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "catalog card {}", self.id)
+    }
+}
+
+/// Pieces spent on a card, after the discounts given by the buyer's production.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Payment {
+    pub colored: bank::Funds,
+    pub golden: u8,
+}
+
+impl Payment {
+    pub fn total(&self) -> bank::Funds {
+        let mut total = self.colored;
+        total[Piece::Golden] += self.golden;
+        total
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Identifiable<T, IdType = u8> {
+pub struct Identifiable<T, IdType = u32> {
     pub uid: IdType,
     pub data: T,
 }
@@ -41,6 +114,46 @@ impl ProductionCard {
             cost,
             produces,
             victory_points,
+            ability: None,
+            canonical_id: None,
+            name: None,
+            art_key: None,
+        }
+    }
+
+    pub fn with_ability(self, ability: CardAbility) -> Self {
+        Self {
+            ability: Some(ability),
+            ..self
+        }
+    }
+
+    pub fn with_canonical_id(self, canonical_id: CanonicalId) -> Self {
+        Self {
+            canonical_id: Some(canonical_id),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    pub fn with_art_key(self, art_key: impl Into<String>) -> Self {
+        Self {
+            art_key: Some(art_key.into()),
+            ..self
+        }
+    }
+
+    /// How many pieces of its color the card produces.
+    pub fn bonus(&self) -> u8 {
+        match self.ability {
+            Some(CardAbility::DoubleBonus) => 2,
+            _ => 1,
         }
     }
 
@@ -48,48 +161,161 @@ impl ProductionCard {
         player: player::Player,
         prod_card: ProductionCard,
     ) -> Result<bank::Funds, BuyOperationFail> {
-        let mut funds_remaining = player.funds.clone();
-        let mut new_missing_funds = bank::Funds::new(0, 0, 0, 0, 0, 0);
-        let mut is_missing_funds = false;
+        let payment = player.payment_plan(&prod_card)?;
 
-        let production_funds =
-            player::Player::get_funds_from_production_cards(player.production_cards);
+        Ok((player.funds - payment.total()).expect("The payment is taken from the buyer's funds"))
+    }
 
-        for (piece, cost) in prod_card.cost.funds {
-            let produces = production_funds.funds.get(&piece).unwrap_or(&0);
+    /// Same as `buy`, but works from the buyer's funds and production instead of a whole player.
+    pub fn funds_after_purchase(
+        &self,
+        funds: bank::Funds,
+        production_funds: bank::Funds,
+    ) -> Result<bank::Funds, BuyOperationFail> {
+        let payment = self.payment_plan(funds, production_funds)?;
 
-            if *produces >= cost {
+        Ok((funds - payment.total()).expect("The payment is taken from the buyer's funds"))
+    }
+
+    /// Pieces the buyer spends on this card: colored pieces first, gold only for what is left.
+    pub fn payment_plan(
+        &self,
+        funds: bank::Funds,
+        production_funds: bank::Funds,
+    ) -> Result<Payment, BuyOperationFail> {
+        self.payment_plan_with(funds, production_funds, false)
+    }
+
+    /// Same as `payment_plan`, each golden piece standing for two with `double_golden`.
+    pub(crate) fn payment_plan_with(
+        &self,
+        funds: bank::Funds,
+        production_funds: bank::Funds,
+        double_golden: bool,
+    ) -> Result<Payment, BuyOperationFail> {
+        let mut payment = Payment::default();
+        let mut goldens = funds[Piece::Golden];
+        let mut new_missing_funds = bank::Funds::default();
+        let mut is_missing_funds = false;
+
+        for (piece, cost) in self.cost.iter() {
+            let produces = production_funds[piece];
+
+            if produces >= cost {
                 continue;
             }
 
-            let current_amount = match funds_remaining.funds.get(&piece) {
-                None => 0,
-                Some(i) => *i,
-            };
+            let current_amount = funds[piece];
+            let golden_needed = golden_needed(
+                cost.saturating_sub(current_amount + produces),
+                double_golden,
+            );
             if current_amount + produces >= cost {
-                funds_remaining
-                    .funds
-                    .insert(piece, current_amount - (cost - produces));
+                payment.colored[piece] = cost - produces;
+            } else if golden_needed <= goldens {
+                payment.colored[piece] = current_amount;
+                payment.golden += golden_needed;
+                goldens -= golden_needed;
             } else {
-                let goldens = *funds_remaining.funds.get(&Piece::Golden).unwrap();
-                if current_amount + produces + goldens >= cost {
-                    funds_remaining.funds.insert(piece, 0);
-                    let r = goldens - (cost - current_amount - produces);
-                    funds_remaining.funds.insert(Piece::Golden, r);
-                } else {
-                    is_missing_funds = true;
-                    new_missing_funds
-                        .funds
-                        .insert(piece, cost - current_amount - produces);
-                }
+                is_missing_funds = true;
+                new_missing_funds[piece] = cost - current_amount - produces;
             }
         }
 
         if is_missing_funds {
             Err(BuyOperationFail::NotEnoughFunds(new_missing_funds))
         } else {
-            Ok(funds_remaining)
+            Ok(payment)
+        }
+    }
+
+    /// Checks a payment chosen by the buyer: it must come from their funds and cover exactly
+    /// what production does not, with golden pieces standing in for any color.
+    pub fn check_payment(
+        &self,
+        payment: bank::Funds,
+        funds: bank::Funds,
+        production_funds: bank::Funds,
+    ) -> Result<Payment, BuyOperationFail> {
+        self.check_payment_with(payment, funds, production_funds, false)
+    }
+
+    /// Same as `check_payment`, each golden piece standing for two with `double_golden`.
+    pub(crate) fn check_payment_with(
+        &self,
+        payment: bank::Funds,
+        funds: bank::Funds,
+        production_funds: bank::Funds,
+        double_golden: bool,
+    ) -> Result<Payment, BuyOperationFail> {
+        let not_owned = payment.saturating_sub(funds);
+        if !not_owned.is_empty() {
+            return Err(BuyOperationFail::PaymentNotOwned(not_owned));
+        }
+
+        let mut colored = bank::Funds::default();
+        let mut excess = bank::Funds::default();
+        let mut uncovered: u16 = 0;
+
+        // Up to the last piece of the payment or of the cost, so that every owed piece counts.
+        let pieces = payment.iter_with(&self.cost);
+        for (piece, paid, cost) in pieces.filter(|(piece, _, _)| !piece.is_wildcard()) {
+            let due = cost.saturating_sub(production_funds[piece]);
+            colored[piece] = paid.min(due);
+            excess[piece] = paid.saturating_sub(due);
+            uncovered += u16::from(golden_needed(due - colored[piece], double_golden));
+        }
+
+        let golden = u16::from(payment[Piece::Golden]);
+        if golden > uncovered {
+            excess[Piece::Golden] = u8::try_from(golden - uncovered).unwrap_or(u8::MAX);
+        }
+        if !excess.is_empty() {
+            return Err(BuyOperationFail::Overpaid(excess));
+        }
+        if golden < uncovered {
+            let missing = u8::try_from(uncovered - golden).unwrap_or(u8::MAX);
+            return Err(BuyOperationFail::Underpaid(missing));
         }
+
+        Ok(Payment {
+            colored,
+            golden: payment[Piece::Golden],
+        })
+    }
+
+    /// How many golden pieces the buyer is missing to afford this card, zero if they can.
+    pub fn shortfall(&self, funds: bank::Funds, production_funds: bank::Funds) -> u8 {
+        self.shortfall_with(funds, production_funds, false)
+    }
+
+    /// Same as `shortfall`, each golden piece standing for two with `double_golden`.
+    pub(crate) fn shortfall_with(
+        &self,
+        funds: bank::Funds,
+        production_funds: bank::Funds,
+        double_golden: bool,
+    ) -> u8 {
+        let missing: u16 = self
+            .cost
+            .iter()
+            .map(|(piece, cost)| {
+                let missing =
+                    cost.saturating_sub(production_funds[piece].saturating_add(funds[piece]));
+                u16::from(golden_needed(missing, double_golden))
+            })
+            .sum();
+
+        u8::try_from(missing.saturating_sub(u16::from(funds[Piece::Golden]))).unwrap_or(u8::MAX)
+    }
+}
+
+/// How many golden pieces cover `missing` pieces of a single color.
+fn golden_needed(missing: u8, double_golden: bool) -> u8 {
+    if double_golden {
+        missing.div_ceil(2)
+    } else {
+        missing
     }
 }
 
@@ -135,6 +361,25 @@ mod tests {
         assert_eq!(result.unwrap(), should_remain_funds);
     }
 
+    #[test]
+    fn custom_colors_of_the_cost_must_be_paid() {
+        let silver = Piece::custom(0).unwrap();
+        let mut cost = Funds::new(1, 0, 0, 0, 0, 0);
+        cost[silver] = 3;
+        let card = ProductionCard::new(cost, Piece::Red, None);
+        let mut funds = Funds::new(1, 0, 0, 0, 0, 0);
+        funds[silver] = 3;
+
+        let result = card.check_payment(Funds::default(), funds, Funds::default());
+        assert_eq!(result.unwrap_err(), BuyOperationFail::Underpaid(4));
+
+        let short = Funds::new(1, 0, 0, 0, 0, 0);
+        let result = card.check_payment(short, funds, Funds::default());
+        assert_eq!(result.unwrap_err(), BuyOperationFail::Underpaid(3));
+
+        assert!(card.check_payment(funds, funds, Funds::default()).is_ok());
+    }
+
     #[test]
     fn cannot_buy_if_there_is_not_enough_funds() {
         let prod_card = ProductionCard::new(get_default_cost(), Piece::Red, None);
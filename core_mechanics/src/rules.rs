@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// Optional rules, on top of the ones every `Board` follows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    /// Ends the game once this many rounds were played.
+    #[serde(default)]
+    pub max_rounds: Option<u32>,
+    /// Ends the game once every player passed their turn in a row.
+    #[serde(default)]
+    pub end_on_stalemate: bool,
+    /// Rejects `Action::PassTheTurn` while the player can collect, reserve or buy.
+    #[serde(default)]
+    pub pass_only_when_stuck: bool,
+    /// Keeps the game going when a player reaches the winning points, for variants that decide
+    /// the winner some other way.
+    #[serde(default)]
+    pub disable_winning_points: bool,
+    /// Lets players put strongholds on market cards, see `Action::PlaceStronghold`. The
+    /// `strongholds` feature only makes this rule available, it stays off by default.
+    #[cfg(feature = "strongholds")]
+    #[serde(default)]
+    pub strongholds: bool,
+}
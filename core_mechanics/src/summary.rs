@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::bank::Funds;
+use super::board::{Board, ProductionTier, Winner};
+use super::player::{Player, PlayerId};
+
+/// What a player finished the game with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerSummary {
+    pub player: PlayerId,
+    /// Points from cards, by the tier they were dealt from. Cards a player started the game with
+    /// only count towards `card_points`.
+    pub card_points_by_tier: HashMap<ProductionTier, u16>,
+    pub card_points: u16,
+    pub noble_points: u16,
+    pub total_points: u16,
+    pub production: Funds,
+    pub cards_bought: usize,
+    pub reserves_left: usize,
+    pub tokens: Funds,
+}
+
+/// Position of a player in the final ranking. Players that tie on points and on number of
+/// production cards share the same place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ranking {
+    pub place: usize,
+    pub player: PlayerId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameSummary {
+    pub players: Vec<PlayerSummary>,
+    pub ranking: Vec<Ranking>,
+    pub winner: Option<Winner>,
+}
+
+impl PlayerSummary {
+    pub fn new(board: &Board, player: &Player) -> Self {
+        let score = player.score_breakdown();
+
+        let mut card_points_by_tier: HashMap<ProductionTier, u16> = board
+            .get_market_layout()
+            .tiers()
+            .map(|tier| (tier, 0))
+            .collect();
+        for card in &player.production_cards {
+            if let Some(tier) = board.get_card_tier(&card.uid) {
+                let points = card_points_by_tier.entry(tier).or_default();
+                *points = points.saturating_add(u16::from(card.data.victory_points.unwrap_or(0)));
+            }
+        }
+
+        Self {
+            player: player.id,
+            card_points_by_tier,
+            card_points: score.card_points,
+            noble_points: score.noble_points,
+            total_points: score.total,
+            production: player.get_production(),
+            cards_bought: score.production_cards,
+            reserves_left: player.reserved_cards.len(),
+            tokens: player.funds,
+        }
+    }
+}
+
+impl GameSummary {
+    pub fn new(board: &Board) -> Self {
+        let players: Vec<PlayerSummary> = board
+            .get_players()
+            .map(|player| PlayerSummary::new(board, player))
+            .collect();
+
+        // Most points first, then fewest production cards, as in the winner selection.
+        let mut ranked: Vec<&PlayerSummary> = players.iter().collect();
+        ranked.sort_by_key(|summary| {
+            (
+                std::cmp::Reverse(summary.total_points),
+                summary.cards_bought,
+            )
+        });
+
+        let mut ranking: Vec<Ranking> = Vec::with_capacity(ranked.len());
+        for (position, summary) in ranked.iter().enumerate() {
+            let place = match position.checked_sub(1).map(|previous| ranked[previous]) {
+                Some(previous)
+                    if previous.total_points == summary.total_points
+                        && previous.cards_bought == summary.cards_bought =>
+                {
+                    ranking[position - 1].place
+                }
+                _ => position + 1,
+            };
+            ranking.push(Ranking {
+                place,
+                player: summary.player,
+            });
+        }
+
+        Self {
+            players,
+            ranking,
+            winner: board.get_winner().cloned(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+impl fmt::Display for GameSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tiers: Vec<ProductionTier> = self
+            .players
+            .iter()
+            .flat_map(|summary| summary.card_points_by_tier.keys().copied())
+            .collect();
+        tiers.sort();
+        tiers.dedup();
+
+        write!(f, "{:<6}{:<12}", "Place", "Player")?;
+        for tier in &tiers {
+            write!(f, "{:>7}", format!("Tier {}", tier.level()))?;
+        }
+        writeln!(
+            f,
+            "{:>8}{:>7}{:>7}{:>10}  Production / Tokens",
+            "Nobles", "Total", "Cards", "Reserved"
+        )?;
+
+        for ranking in &self.ranking {
+            let Some(summary) = self.players.iter().find(|p| p.player == ranking.player) else {
+                continue;
+            };
+            write!(f, "{:<6}{:<12}", ranking.place, summary.player.to_string())?;
+            for tier in &tiers {
+                let points = summary.card_points_by_tier.get(tier).copied().unwrap_or(0);
+                write!(f, "{:>7}", points)?;
+            }
+            writeln!(
+                f,
+                "{:>8}{:>7}{:>7}{:>10}  {} / {}",
+                summary.noble_points,
+                summary.total_points,
+                summary.cards_bought,
+                summary.reserves_left,
+                summary.production,
+                summary.tokens
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::board::Action;
+    use crate::noble::{Noble, NobleId};
+    use crate::piece::Piece;
+    use crate::production_card::{CardId, Identifiable, ProductionCard};
+
+    fn get_card(
+        id: u32,
+        produces: Piece,
+        victory_points: u8,
+    ) -> Identifiable<ProductionCard, CardId> {
+        let card = ProductionCard::new(Funds::default(), produces, Some(victory_points));
+        Identifiable::new(card, CardId::new(id))
+    }
+
+    fn get_finished_board() -> Board {
+        let p1 = Player::new(
+            PlayerId::new(1),
+            Funds::new(1, 0, 0, 0, 0, 1),
+            vec![],
+            vec![],
+        );
+        let p2 = Player::new(PlayerId::new(2), Funds::default(), vec![], vec![]);
+        let p3 = Player::new(PlayerId::new(3), Funds::default(), vec![], vec![]);
+        let board = Board::builder()
+            .players(vec![p1, p2, p3])
+            .bank(Funds::new(7, 7, 7, 7, 7, 5))
+            .deck(ProductionTier::One, vec![get_card(1, Piece::Red, 1)])
+            .deck(ProductionTier::Two, vec![get_card(2, Piece::Blue, 2)])
+            .deck(ProductionTier::Three, vec![get_card(3, Piece::Green, 15)])
+            .nobles(vec![Noble::new(
+                NobleId::new(1),
+                Funds::new(1, 0, 0, 0, 0, 0),
+            )])
+            .build()
+            .unwrap();
+
+        let actions = [
+            Action::BuyCard(CardId::new(1), None),
+            Action::SelectNoble(NobleId::new(1)),
+            Action::BuyCard(CardId::new(2), None),
+            Action::BuyCard(CardId::new(3), None),
+        ];
+        actions.iter().fold(board, |board, action| {
+            Board::do_action(board, action).unwrap()
+        })
+    }
+
+    #[test]
+    fn summarizes_every_player() {
+        let board = get_finished_board();
+        let summary = GameSummary::new(&board);
+
+        let p1 = &summary.players[0];
+        assert_eq!(p1.card_points_by_tier[&ProductionTier::One], 1);
+        assert_eq!(p1.card_points, 1);
+        assert_eq!(p1.noble_points, 3);
+        assert_eq!(p1.total_points, 4);
+        assert_eq!(p1.production, Funds::new(1, 0, 0, 0, 0, 0));
+        assert_eq!(p1.cards_bought, 1);
+        assert_eq!(p1.reserves_left, 0);
+        assert_eq!(p1.tokens, Funds::new(1, 0, 0, 0, 0, 1));
+
+        let p3 = &summary.players[2];
+        assert_eq!(p3.card_points_by_tier[&ProductionTier::Three], 15);
+        assert_eq!(p3.total_points, 15);
+    }
+
+    #[test]
+    fn ranks_players_with_the_tie_break() {
+        let board = get_finished_board();
+        let summary = GameSummary::new(&board);
+
+        assert_eq!(summary.winner, Some(Winner::Winner(PlayerId::new(3))));
+        let ranking: Vec<(usize, u32)> = summary
+            .ranking
+            .iter()
+            .map(|ranking| (ranking.place, ranking.player.id()))
+            .collect();
+        assert_eq!(ranking, vec![(1, 3), (2, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn players_that_tie_share_a_place() {
+        let p1 = Player::new(PlayerId::new(1), Funds::default(), vec![], vec![]);
+        let p2 = Player::new(
+            PlayerId::new(2),
+            Funds::default(),
+            vec![get_card(1, Piece::Red, 2)],
+            vec![],
+        );
+        let p3 = Player::new(PlayerId::new(3), Funds::default(), vec![], vec![]);
+        let board = Board::builder()
+            .players(vec![p1, p2, p3])
+            .deck(ProductionTier::One, vec![])
+            .deck(ProductionTier::Two, vec![])
+            .deck(ProductionTier::Three, vec![])
+            .build()
+            .unwrap();
+
+        let summary = GameSummary::new(&board);
+        let ranking: Vec<(usize, u32)> = summary
+            .ranking
+            .iter()
+            .map(|ranking| (ranking.place, ranking.player.id()))
+            .collect();
+        assert_eq!(ranking, vec![(1, 2), (2, 1), (2, 3)]);
+        assert_eq!(
+            summary.players[1].card_points_by_tier[&ProductionTier::One],
+            0
+        );
+        assert_eq!(summary.players[1].card_points, 2);
+    }
+
+    #[test]
+    fn renders_as_json_and_text() {
+        let summary = GameSummary::new(&get_finished_board());
+
+        let json = summary.to_json().unwrap();
+        let loaded: GameSummary = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, summary);
+
+        let table = summary.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("Place Player"));
+        assert!(lines[1].starts_with("1     player 3"));
+        assert!(lines[2].contains("1 Red / 1 Red and 1 Golden"));
+    }
+}
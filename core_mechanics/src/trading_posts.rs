@@ -0,0 +1,171 @@
+//! The Trading Posts expansion: a track of posts that give lasting powers.
+//!
+//! Every player moves along the same track, claiming the next post at the end of any turn their
+//! production covers its requirement. The requirements are simplified to production alone, the
+//! printed board also asks for nobles on some posts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::bank::Funds;
+use crate::board::{Board, SetupError};
+use crate::original_game::{get_original_game_builder, get_original_game_rules, get_random_nobles};
+
+/// A stop on the trading posts track. Each player claims the posts in order, once their
+/// production covers the requirement, and keeps the power for the rest of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradingPost {
+    pub requirement: Funds,
+    pub power: PostPower,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PostPower {
+    /// Take a colored piece from the bank after buying a card.
+    PieceAfterBuying,
+    /// Take a piece of another color along with two of the same color.
+    PieceWithTwoOfAKind,
+    /// Each golden piece spent stands for two pieces of the same color.
+    DoubleGolden,
+    /// Points on top of cards and nobles.
+    BonusPoints(u8),
+    /// Points for every trading post claimed, this one included.
+    PointsPerPost(u8),
+}
+
+/// Lasting powers of a player, from the trading posts they claimed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PlayerPowers {
+    /// Takes a colored piece from the bank after buying a card.
+    pub piece_after_buying: bool,
+    /// Takes a piece of another color along with two of the same color.
+    pub piece_with_two_of_a_kind: bool,
+    /// Each golden piece spent stands for two pieces of the same color.
+    pub double_golden: bool,
+    pub bonus_points: u8,
+    pub points_per_post: u8,
+    pub posts_claimed: u8,
+}
+
+impl PlayerPowers {
+    pub fn points(&self) -> u16 {
+        u16::from(self.bonus_points)
+            .saturating_add(u16::from(self.points_per_post) * u16::from(self.posts_claimed))
+    }
+}
+
+impl TradingPost {
+    pub fn new(requirement: Funds, power: PostPower) -> Self {
+        Self { requirement, power }
+    }
+
+    pub fn can_be_claimed_with(&self, production: &Funds) -> bool {
+        (*production - self.requirement).is_ok()
+    }
+
+    /// Powers of a player once they claim this post. Points saturate instead of overflowing.
+    pub fn grant(&self, powers: PlayerPowers) -> PlayerPowers {
+        let mut powers = PlayerPowers {
+            posts_claimed: powers.posts_claimed.saturating_add(1),
+            ..powers
+        };
+        match self.power {
+            PostPower::PieceAfterBuying => powers.piece_after_buying = true,
+            PostPower::PieceWithTwoOfAKind => powers.piece_with_two_of_a_kind = true,
+            PostPower::DoubleGolden => powers.double_golden = true,
+            PostPower::BonusPoints(points) => {
+                powers.bonus_points = powers.bonus_points.saturating_add(points)
+            }
+            PostPower::PointsPerPost(points) => {
+                powers.points_per_post = powers.points_per_post.saturating_add(points)
+            }
+        }
+        powers
+    }
+}
+
+/// # Panics
+///
+/// Panics if `n_of_players` is not between 2 and 4, see `try_get_trading_posts_board`.
+pub fn get_trading_posts_board(n_of_players: u8) -> Board {
+    try_get_trading_posts_board(n_of_players).unwrap_or_else(|_| {
+        panic!(
+            "The Trading Posts expansion is only defined for 2 to 4 players. '{:?}' given",
+            n_of_players
+        )
+    })
+}
+
+pub fn try_get_trading_posts_board(n_of_players: u8) -> Result<Board, SetupError> {
+    get_original_game_builder(n_of_players)?
+        .nobles(get_random_nobles(n_of_players + 1))
+        .trading_posts(get_trading_posts())
+        .rules(get_original_game_rules())
+        .build()
+}
+
+/// The track, in the order the posts are claimed.
+pub fn get_trading_posts() -> Vec<TradingPost> {
+    vec![
+        TradingPost::new(Funds::new(3, 0, 0, 0, 1, 0), PostPower::PieceAfterBuying),
+        TradingPost::new(Funds::new(3, 0, 0, 0, 2, 0), PostPower::PieceWithTwoOfAKind),
+        TradingPost::new(Funds::new(3, 0, 2, 0, 2, 0), PostPower::DoubleGolden),
+        TradingPost::new(Funds::new(3, 2, 2, 0, 2, 0), PostPower::BonusPoints(5)),
+        TradingPost::new(Funds::new(3, 2, 2, 3, 2, 0), PostPower::PointsPerPost(1)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claiming_a_post_adds_its_power() {
+        let post = TradingPost::new(Funds::new(2, 0, 0, 0, 0, 0), PostPower::BonusPoints(5));
+        assert!(!post.can_be_claimed_with(&Funds::new(1, 3, 0, 0, 0, 0)));
+        assert!(post.can_be_claimed_with(&Funds::new(2, 0, 0, 0, 0, 0)));
+
+        let powers = post.grant(PlayerPowers::default());
+        assert_eq!(powers.bonus_points, 5);
+        assert_eq!(powers.posts_claimed, 1);
+        assert_eq!(powers.points(), 5);
+
+        let post = TradingPost::new(Funds::default(), PostPower::PointsPerPost(1));
+        let powers = post.grant(powers);
+        assert_eq!(powers.points(), 7);
+    }
+
+    #[test]
+    fn granting_powers_saturates() {
+        let powers = PlayerPowers {
+            bonus_points: 250,
+            points_per_post: 250,
+            posts_claimed: u8::MAX,
+            ..PlayerPowers::default()
+        };
+
+        let powers = TradingPost::new(Funds::default(), PostPower::BonusPoints(10)).grant(powers);
+        let powers = TradingPost::new(Funds::default(), PostPower::PointsPerPost(10)).grant(powers);
+
+        assert_eq!(powers.bonus_points, u8::MAX);
+        assert_eq!(powers.points_per_post, u8::MAX);
+        assert_eq!(powers.posts_claimed, u8::MAX);
+    }
+
+    #[test]
+    fn trading_posts_game_starts_with_the_whole_track() {
+        let board = get_trading_posts_board(4);
+        assert_eq!(board.get_trading_posts(), get_trading_posts().as_slice());
+        assert_eq!(board.validate(), Ok(()));
+        assert!(board
+            .get_players()
+            .all(|player| player.powers.posts_claimed == 0));
+    }
+
+    #[test]
+    fn every_post_asks_more_than_the_one_before() {
+        let posts = get_trading_posts();
+        for pair in posts.windows(2) {
+            assert!((pair[1].requirement - pair[0].requirement).is_ok());
+        }
+    }
+}
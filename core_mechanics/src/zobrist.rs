@@ -0,0 +1,156 @@
+//! Keys of the incremental position hash kept by `Board`.
+//!
+//! Every feature of a position (a token count, a card in some place, the side to move...) has its
+//! own pseudo random 64 bit key, and the hash of a position is the XOR of the keys of all its
+//! features. Keys are derived on the fly with SplitMix64, so there are no tables to initialize.
+
+use super::board::{ActionType, ProductionTier, RoundType};
+use super::noble::NobleId;
+use super::piece::Piece;
+use super::production_card::CardId;
+
+const SEED: u64 = 0x5EED_5B1E_D0A5_0000;
+
+#[derive(Clone, Copy)]
+enum Feature {
+    BankFunds,
+    PlayerFunds,
+    Tableau,
+    Reserve,
+    PlayerNoble,
+    ReservedNoble,
+    CopiedColor,
+    #[cfg(feature = "trading-posts")]
+    PlayerPowers,
+    Market,
+    Noble,
+    Turn,
+    ActionNeeded,
+    Round,
+    #[cfg(feature = "strongholds")]
+    Stronghold,
+}
+
+/// Also used to draw the starting player from a seed.
+pub(crate) const fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn key(feature: Feature, a: u64, b: u64, c: u64) -> u64 {
+    let mut hash = splitmix64(SEED ^ feature as u64);
+    hash = splitmix64(hash ^ a);
+    hash = splitmix64(hash ^ b);
+    splitmix64(hash ^ c)
+}
+
+/// Empty piles have no key, so that funds hash the same whatever pieces they list.
+pub(crate) fn bank_funds(piece: Piece, quantity: u8) -> u64 {
+    if quantity == 0 {
+        return 0;
+    }
+    key(
+        Feature::BankFunds,
+        piece.index() as u64,
+        u64::from(quantity),
+        0,
+    )
+}
+
+pub(crate) fn player_funds(seat: usize, piece: Piece, quantity: u8) -> u64 {
+    if quantity == 0 {
+        return 0;
+    }
+    key(
+        Feature::PlayerFunds,
+        seat as u64,
+        piece.index() as u64,
+        u64::from(quantity),
+    )
+}
+
+pub(crate) fn tableau(seat: usize, card_id: CardId) -> u64 {
+    key(Feature::Tableau, seat as u64, u64::from(card_id.id()), 0)
+}
+
+pub(crate) fn reserve(seat: usize, card_id: CardId) -> u64 {
+    key(Feature::Reserve, seat as u64, u64::from(card_id.id()), 0)
+}
+
+pub(crate) fn player_noble(seat: usize, noble_id: NobleId) -> u64 {
+    key(
+        Feature::PlayerNoble,
+        seat as u64,
+        u64::from(noble_id.id()),
+        0,
+    )
+}
+
+pub(crate) fn reserved_noble(seat: usize, noble_id: NobleId) -> u64 {
+    key(
+        Feature::ReservedNoble,
+        seat as u64,
+        u64::from(noble_id.id()),
+        0,
+    )
+}
+
+pub(crate) fn copied_color(card_id: CardId, piece: Piece) -> u64 {
+    key(
+        Feature::CopiedColor,
+        u64::from(card_id.id()),
+        piece.index() as u64,
+        0,
+    )
+}
+
+#[cfg(feature = "trading-posts")]
+pub(crate) fn player_powers(seat: usize, powers: &super::trading_posts::PlayerPowers) -> u64 {
+    let flags = u64::from(powers.piece_after_buying)
+        | u64::from(powers.piece_with_two_of_a_kind) << 1
+        | u64::from(powers.double_golden) << 2;
+    let points = u64::from(powers.bonus_points) | u64::from(powers.points_per_post) << 8;
+    key(
+        Feature::PlayerPowers,
+        seat as u64,
+        flags | u64::from(powers.posts_claimed) << 8,
+        points,
+    )
+}
+
+#[cfg(feature = "strongholds")]
+pub(crate) fn stronghold(stronghold: &super::board::Stronghold) -> u64 {
+    key(
+        Feature::Stronghold,
+        u64::from(stronghold.card_id.id()),
+        u64::from(stronghold.owner.id()),
+        0,
+    )
+}
+
+pub(crate) fn market(tier: ProductionTier, card_id: CardId) -> u64 {
+    key(
+        Feature::Market,
+        u64::from(tier.level()),
+        u64::from(card_id.id()),
+        0,
+    )
+}
+
+pub(crate) fn noble(noble_id: NobleId) -> u64 {
+    key(Feature::Noble, u64::from(noble_id.id()), 0, 0)
+}
+
+pub(crate) fn turn(seat: usize) -> u64 {
+    key(Feature::Turn, seat as u64, 0, 0)
+}
+
+pub(crate) fn action_needed(action_needed: ActionType) -> u64 {
+    key(Feature::ActionNeeded, action_needed as u64, 0, 0)
+}
+
+pub(crate) fn round(round_type: RoundType) -> u64 {
+    key(Feature::Round, round_type as u64, 0, 0)
+}